@@ -0,0 +1,82 @@
+//! With the `vendored` feature, build libsndfile from source with CMake and link it statically.
+//!
+//! The source tree is taken from `SNDFILE_SOURCE_DIR`, or `vendor/libsndfile` by default, e.g.
+//! `git clone --depth 1 --branch 1.2.2 https://github.com/libsndfile/libsndfile vendor/libsndfile`.
+//! Codecs needing external libraries are only enabled by `vendored_external_libs` (FLAC, Ogg,
+//! Vorbis and Opus) and `vendored_mpeg` (MP3), CMake must find their static libraries.
+//!
+//! The default `system_linking` feature must be disabled, otherwise `sndfile-sys` also links the
+//! system libsndfile.
+
+fn main() {
+  #[cfg(feature = "vendored")]
+  vendored::build();
+}
+
+#[cfg(feature = "vendored")]
+mod vendored {
+  use std::env;
+  use std::path::PathBuf;
+
+  fn on_off(x: bool) -> &'static str {
+    if x {
+      "ON"
+    } else {
+      "OFF"
+    }
+  }
+
+  pub fn build() {
+    if cfg!(feature = "system_linking") {
+      panic!(
+        "`vendored` links its own libsndfile, disable default features to not link the system one too."
+      );
+    }
+    println!("cargo:rerun-if-env-changed=SNDFILE_SOURCE_DIR");
+    let src = env::var_os("SNDFILE_SOURCE_DIR")
+      .map(PathBuf::from)
+      .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("vendor/libsndfile"));
+    if !src.join("CMakeLists.txt").exists() {
+      panic!(
+        "libsndfile source not found at `{}`, set `SNDFILE_SOURCE_DIR` or clone it there.",
+        src.display()
+      );
+    }
+    println!(
+      "cargo:rerun-if-changed={}",
+      src.join("CMakeLists.txt").display()
+    );
+
+    let external_libs = cfg!(feature = "vendored_external_libs");
+    let mpeg = cfg!(feature = "vendored_mpeg");
+    let dst = cmake::Config::new(&src)
+      .define("BUILD_SHARED_LIBS", "OFF")
+      .define("BUILD_PROGRAMS", "OFF")
+      .define("BUILD_EXAMPLES", "OFF")
+      .define("BUILD_TESTING", "OFF")
+      .define("ENABLE_CPACK", "OFF")
+      .define("ENABLE_PACKAGE_CONFIG", "OFF")
+      .define("INSTALL_PKGCONFIG_MODULE", "OFF")
+      .define("ENABLE_EXTERNAL_LIBS", on_off(external_libs))
+      .define("ENABLE_MPEG", on_off(mpeg))
+      .build();
+
+    for dir in ["lib", "lib64"] {
+      println!("cargo:rustc-link-search=native={}", dst.join(dir).display());
+    }
+    println!("cargo:rustc-link-lib=static=sndfile");
+    if external_libs {
+      for x in ["FLAC", "vorbisenc", "vorbis", "opus", "ogg"] {
+        println!("cargo:rustc-link-lib=static={}", x);
+      }
+    }
+    if mpeg {
+      for x in ["mp3lame", "mpg123"] {
+        println!("cargo:rustc-link-lib=static={}", x);
+      }
+    }
+    if env::var("CARGO_CFG_TARGET_FAMILY").as_deref() == Ok("unix") {
+      println!("cargo:rustc-link-lib=m");
+    }
+  }
+}
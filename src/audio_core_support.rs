@@ -0,0 +1,87 @@
+use super::edit::BLOCK_FRAMES;
+use super::{SndFile, SndFileError, SndFileIO};
+use audio_core::{Buf, BufMut, Channel, ChannelMut, ExactSizeBuf};
+
+impl SndFile {
+  fn check_buf_channels(&self, channels: usize) -> Result<usize, SndFileError> {
+    if channels != self.get_channels() {
+      return Err(SndFileError::InvalidParameter(format!(
+        "Got a buffer of {} channels, expect {} channels.",
+        channels,
+        self.get_channels()
+      )));
+    }
+    Ok(channels)
+  }
+
+  /// Read frames from current I/O cursor into `dst`, an `audio` buffer of any layout, returns the
+  /// number of frames read if success.
+  ///
+  /// Up to `dst.frames()` frames are read, the remaining frames of `dst` are left untouched. Returns
+  /// `SndFileError::InvalidParameter` if `dst` does not have the channel count of the file.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn read_to_buf<B>(&mut self, dst: &mut B) -> Result<usize, SndFileError>
+  where
+    B: BufMut + ExactSizeBuf,
+    B::Sample: 'static + Default + Copy,
+    SndFile: SndFileIO<B::Sample>,
+  {
+    let channels = self.check_buf_channels(dst.channels())?;
+    let frames = dst.frames();
+    let mut buf = vec![B::Sample::default(); frames.min(BLOCK_FRAMES) * channels];
+    let mut done = 0;
+    while done < frames {
+      let n_block = (frames - done).min(BLOCK_FRAMES);
+      let n = self.read_to_slice(&mut buf[..n_block * channels])?;
+      for (c, mut ch) in dst.iter_channels_mut().enumerate() {
+        let src = buf[..n * channels].iter().skip(c).step_by(channels);
+        for (x, y) in ch.iter_mut().skip(done).zip(src) {
+          *x = *y;
+        }
+      }
+      done += n;
+      if n < n_block {
+        break;
+      }
+    }
+    Ok(done)
+  }
+
+  /// Write all frames of `src`, an `audio` buffer of any layout, returns the number of frames
+  /// written if success.
+  ///
+  /// Returns `SndFileError::InvalidParameter` if `src` does not have the channel count of the
+  /// file.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn write_from_buf<B>(&mut self, src: &B) -> Result<usize, SndFileError>
+  where
+    B: Buf + ExactSizeBuf,
+    B::Sample: 'static + Default + Copy,
+    SndFile: SndFileIO<B::Sample>,
+  {
+    let channels = self.check_buf_channels(src.channels())?;
+    let frames = src.frames();
+    let mut buf = vec![B::Sample::default(); frames.min(BLOCK_FRAMES) * channels];
+    let mut done = 0;
+    while done < frames {
+      let n_block = (frames - done).min(BLOCK_FRAMES);
+      for (c, ch) in src.iter_channels().enumerate() {
+        let dst = buf[..n_block * channels]
+          .iter_mut()
+          .skip(c)
+          .step_by(channels);
+        for (x, y) in dst.zip(ch.iter().skip(done)) {
+          *x = y;
+        }
+      }
+      let n = self.write_from_slice(&buf[..n_block * channels])?;
+      done += n;
+      if n < n_block {
+        break;
+      }
+    }
+    Ok(done)
+  }
+}
@@ -0,0 +1,108 @@
+use super::{SndFile, SndFileError, SndFileIO, SndReadWriter, SndReader, SndWriter};
+use std::io::SeekFrom;
+
+/// Stream parameters shared by `AudioRead` and `AudioWrite`.
+pub trait AudioInfo {
+  fn samplerate(&self) -> usize;
+  fn channels(&self) -> usize;
+}
+
+/// Object-safe source of interleaved `f32` frames, e.g. `Box<dyn AudioRead>`.
+///
+/// Implemented by `SndFile`, `SndReader` and `SndReadWriter`, and can be implemented for sources
+/// from memory or network.
+pub trait AudioRead: AudioInfo {
+  /// Read interleaved frames, returns the number of frames read, `0` at the end of the stream.
+  ///
+  /// `dst.len()` must be a multiple of the channel count.
+  fn read_f32(&mut self, dst: &mut [f32]) -> Result<usize, SndFileError>;
+
+  /// Seek to a frame position, returns the new position. Sources that can't seek return an error.
+  fn seek(&mut self, pos: SeekFrom) -> Result<u64, SndFileError>;
+}
+
+/// Object-safe sink of interleaved `f32` frames, e.g. `Box<dyn AudioWrite>`.
+///
+/// Implemented by `SndFile`, `SndWriter` and `SndReadWriter`.
+pub trait AudioWrite: AudioInfo {
+  /// Write interleaved frames, returns the number of frames written.
+  ///
+  /// `src.len()` must be a multiple of the channel count.
+  fn write_f32(&mut self, src: &[f32]) -> Result<usize, SndFileError>;
+}
+
+impl AudioInfo for SndFile {
+  fn samplerate(&self) -> usize {
+    self.get_samplerate()
+  }
+
+  fn channels(&self) -> usize {
+    self.get_channels()
+  }
+}
+
+impl AudioRead for SndFile {
+  fn read_f32(&mut self, dst: &mut [f32]) -> Result<usize, SndFileError> {
+    self.read_to_slice(dst)
+  }
+
+  fn seek(&mut self, pos: SeekFrom) -> Result<u64, SndFileError> {
+    SndFile::seek(self, pos)
+  }
+}
+
+impl AudioWrite for SndFile {
+  fn write_f32(&mut self, src: &[f32]) -> Result<usize, SndFileError> {
+    self.write_from_slice(src)
+  }
+}
+
+macro_rules! impl_audio_info {
+  ($t:ident) => {
+    impl AudioInfo for $t {
+      fn samplerate(&self) -> usize {
+        self.get_ref().get_samplerate()
+      }
+
+      fn channels(&self) -> usize {
+        self.get_ref().get_channels()
+      }
+    }
+  };
+}
+
+impl_audio_info!(SndReader);
+impl_audio_info!(SndWriter);
+impl_audio_info!(SndReadWriter);
+
+impl AudioRead for SndReader {
+  fn read_f32(&mut self, dst: &mut [f32]) -> Result<usize, SndFileError> {
+    self.read_to_slice(dst)
+  }
+
+  fn seek(&mut self, pos: SeekFrom) -> Result<u64, SndFileError> {
+    SndReader::seek(self, pos)
+  }
+}
+
+impl AudioRead for SndReadWriter {
+  fn read_f32(&mut self, dst: &mut [f32]) -> Result<usize, SndFileError> {
+    self.read_to_slice(dst)
+  }
+
+  fn seek(&mut self, pos: SeekFrom) -> Result<u64, SndFileError> {
+    SndReadWriter::seek(self, pos)
+  }
+}
+
+impl AudioWrite for SndWriter {
+  fn write_f32(&mut self, src: &[f32]) -> Result<usize, SndFileError> {
+    self.write_from_slice(src)
+  }
+}
+
+impl AudioWrite for SndReadWriter {
+  fn write_f32(&mut self, src: &[f32]) -> Result<usize, SndFileError> {
+    self.write_from_slice(src)
+  }
+}
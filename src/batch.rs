@@ -0,0 +1,71 @@
+//! Operations over many files at once, e.g. for library maintenance scripts.
+//!
+//! Failures are reported per file, so one broken file does not stop the whole batch.
+
+use super::{OpenOptions, ReadOptions, SndFileError, TagType};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Tags to write, e.g. as returned by `SndFile::get_tags`.
+pub type TagSet = HashMap<TagType, String>;
+
+/// What `retag` does with tags already set in a file.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum TagConflict {
+  /// Keep the tags already set, only add the missing ones.
+  SkipExisting,
+  /// Replace the tags already set.
+  Overwrite,
+}
+
+/// Open each file of `paths` for reading and writing and set `tags`, returns the number of tags
+/// written to each file, in the order of `paths`.
+///
+/// Files are closed before the next one is opened. Closing errors are reported as well, so a
+/// successful result means the file is updated.
+///
+/// ```ignore
+/// let mut tags = TagSet::new();
+/// tags.insert(TagType::Album, "Live at Home".to_string());
+/// for (path, r) in retag(&paths, &tags, TagConflict::SkipExisting) {
+///   if let Err(e) = r {
+///     eprintln!("{}: {:?}", path.display(), e);
+///   }
+/// }
+/// ```
+pub fn retag<I, P>(
+  paths: I,
+  tags: &TagSet,
+  conflict: TagConflict,
+) -> Vec<(PathBuf, Result<usize, SndFileError>)>
+where
+  I: IntoIterator<Item = P>,
+  P: AsRef<Path>,
+{
+  paths
+    .into_iter()
+    .map(|path| {
+      let r = retag_file(path.as_ref(), tags, conflict);
+      (path.as_ref().to_path_buf(), r)
+    })
+    .collect()
+}
+
+fn retag_file(path: &Path, tags: &TagSet, conflict: TagConflict) -> Result<usize, SndFileError> {
+  let mut snd = OpenOptions::ReadWrite(ReadOptions::Auto).from_path(path)?;
+  let mut n = 0;
+  // `TagType::ALL` gives the same order of writes for every file.
+  for t in TagType::ALL.iter() {
+    let v = match tags.get(t) {
+      Some(v) => v,
+      None => continue,
+    };
+    if conflict == TagConflict::SkipExisting && snd.get_tag(*t).is_some() {
+      continue;
+    }
+    snd.set_tag(*t, v)?;
+    n += 1;
+  }
+  snd.close_into_inner()?;
+  Ok(n)
+}
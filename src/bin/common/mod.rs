@@ -0,0 +1,54 @@
+//! Helpers shared by command line tools.
+
+#![allow(dead_code)]
+
+use sndfile::{SndFileInfo, TagType, WriteOptions};
+use std::time::Duration;
+
+/// Lowercase names of tags, used as command line flags and JSON keys.
+pub fn tag_name(t: TagType) -> &'static str {
+  match t {
+    TagType::Title => "title",
+    TagType::Copyright => "copyright",
+    TagType::Software => "software",
+    TagType::Artist => "artist",
+    TagType::Comment => "comment",
+    TagType::Date => "date",
+    TagType::Album => "album",
+    TagType::License => "license",
+    TagType::Tracknumber => "tracknumber",
+    TagType::Genre => "genre",
+  }
+}
+
+pub fn tag_from_name(name: &str) -> Option<TagType> {
+  TagType::ALL.iter().copied().find(|&t| tag_name(t) == name)
+}
+
+/// Parse `ss`, `mm:ss` or `hh:mm:ss` into a duration.
+pub fn parse_time(s: &str) -> Option<Duration> {
+  let mut secs = 0.0;
+  for (i, part) in s.split(':').enumerate() {
+    if i > 2 {
+      return None;
+    }
+    let v: f64 = part.parse().ok()?;
+    if v < 0.0 {
+      return None;
+    }
+    secs = secs * 60.0 + v;
+  }
+  // Rejects `inf`, `nan` and durations too long to represent.
+  Duration::try_from_secs_f64(secs).ok()
+}
+
+/// `WriteOptions` producing a file in the same format as the file of `info`.
+pub fn write_options_like(info: &SndFileInfo) -> WriteOptions {
+  WriteOptions::new(
+    info.major_format,
+    info.subtype_format,
+    info.endian,
+    info.samplerate,
+    info.channels,
+  )
+}
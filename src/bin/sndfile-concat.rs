@@ -0,0 +1,55 @@
+//! Concatenate audio files.
+//!
+//! Usage: `sndfile-concat -o <output> <path>...`
+//!
+//! The output has the same format as the first input.
+
+mod common;
+
+use common::write_options_like;
+use sndfile::*;
+
+const USAGE: &str = "Usage: sndfile-concat -o <output> <path>...
+
+The output has the same format as the first input.";
+
+fn usage_error(msg: &str) -> ! {
+  eprintln!("{}\n\n{}", msg, USAGE);
+  std::process::exit(2);
+}
+
+fn run(paths: &[String], output: &str) -> Result<u64, SndFileError> {
+  let first = probe(&paths[0])?;
+  concat(paths, output, write_options_like(&first), true)
+}
+
+fn main() {
+  let mut args = std::env::args().skip(1);
+  let mut output = None;
+  let mut paths = Vec::new();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        return;
+      }
+      "-o" => {
+        output = Some(
+          args
+            .next()
+            .unwrap_or_else(|| usage_error("Missing output.")),
+        )
+      }
+      _ => paths.push(arg),
+    }
+  }
+  let output = output.unwrap_or_else(|| usage_error("Missing `-o <output>`."));
+  if paths.is_empty() {
+    usage_error("Missing path.");
+  }
+
+  if let Err(e) = run(&paths, &output) {
+    eprintln!("{}: {:?}", output, e);
+    std::process::exit(1);
+  }
+}
@@ -0,0 +1,94 @@
+//! Print information of audio files.
+//!
+//! Usage: `sndfile-info [--json] <path>...`
+
+mod common;
+
+use common::tag_name;
+use sndfile::*;
+use std::path::Path;
+
+/// JSON item of a file, the fields of `SndFileInfo` with its path.
+#[derive(serde::Serialize)]
+struct JsonItem<'a> {
+  path: &'a str,
+  #[serde(flatten)]
+  info: &'a SndFileInfo,
+}
+
+fn print_text(path: &str, info: &SndFileInfo) {
+  println!("{}:", path);
+  println!("  Major format: {:?}", info.major_format);
+  println!("  Subtype format: {:?}", info.subtype_format);
+  println!("  Endian: {:?}", info.endian);
+  println!("  Sample rate: {} Hz", info.samplerate);
+  println!("  Channels: {}", info.channels);
+  match info.frames {
+    Some(x) => println!("  Frames: {}", x),
+    None => println!("  Frames: unknown"),
+  }
+  match info.duration {
+    Some(x) => println!("  Duration: {:.3} seconds", x.as_secs_f64()),
+    None => println!("  Duration: unknown"),
+  }
+  println!("  Seekable: {}", info.seekable);
+  if !info.tags.is_empty() {
+    println!("  Tags:");
+    for t in TagType::ALL.iter() {
+      if let Some(v) = info.tags.get(t) {
+        println!("    {}: {}", tag_name(*t), v);
+      }
+    }
+  }
+  if !info.chunks.is_empty() {
+    println!("  Chunks:");
+    for x in info.chunks.iter() {
+      println!("    {}: {} bytes", x.id, x.size);
+    }
+  }
+}
+
+fn main() {
+  let mut json = false;
+  let mut paths = Vec::new();
+  for arg in std::env::args().skip(1) {
+    match arg.as_str() {
+      "--json" => json = true,
+      "-h" | "--help" => {
+        println!("Usage: sndfile-info [--json] <path>...");
+        return;
+      }
+      _ => paths.push(arg),
+    }
+  }
+  if paths.is_empty() {
+    eprintln!("Usage: sndfile-info [--json] <path>...");
+    std::process::exit(2);
+  }
+
+  let mut failed = false;
+  let mut json_items = Vec::new();
+  for path in paths.iter() {
+    match probe(Path::new(path)) {
+      Ok(info) => {
+        if json {
+          let item = JsonItem { path, info: &info };
+          json_items
+            .push(serde_json::to_string(&item).expect("SndFileInfo is always serializable."));
+        } else {
+          print_text(path, &info);
+        }
+      }
+      Err(e) => {
+        eprintln!("{}: {:?}", path, e);
+        failed = true;
+      }
+    }
+  }
+  if json {
+    println!("[{}]", json_items.join(", "));
+  }
+  if failed {
+    std::process::exit(1);
+  }
+}
@@ -0,0 +1,113 @@
+//! Dump or edit tags of audio files.
+//!
+//! Usage:
+//! * `sndfile-meta dump [--json] <path>...`
+//! * `sndfile-meta set [--title X] [--artist X] ... <path>...`
+
+mod common;
+
+use common::{tag_from_name, tag_name};
+use sndfile::*;
+use std::path::Path;
+
+const USAGE: &str = "Usage:
+  sndfile-meta dump [--json] <path>...
+  sndfile-meta set [--<tag> <value>]... <path>...
+
+Tags: title, copyright, software, artist, comment, date, album, license, tracknumber, genre";
+
+fn usage_error(msg: &str) -> ! {
+  eprintln!("{}\n\n{}", msg, USAGE);
+  std::process::exit(2);
+}
+
+/// JSON item of a file, its path and its tags in the order of `TagType::ALL`.
+#[derive(serde::Serialize)]
+struct JsonItem<'a> {
+  path: &'a str,
+  #[serde(serialize_with = "serialize_tags")]
+  tags: &'a [(TagType, String)],
+}
+
+fn serialize_tags<S: serde::Serializer>(
+  tags: &&[(TagType, String)],
+  serializer: S,
+) -> Result<S::Ok, S::Error> {
+  serializer.collect_map(tags.iter().map(|(t, v)| (tag_name(*t), v)))
+}
+
+fn dump(path: &str, json: bool) -> Result<(), SndFileError> {
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto).from_path(Path::new(path))?;
+  let mut tag_dict = snd.get_tags();
+  let tags: Vec<(TagType, String)> = TagType::ALL
+    .iter()
+    .filter_map(|t| tag_dict.remove(t).map(|v| (*t, v)))
+    .collect();
+  if json {
+    let item = JsonItem { path, tags: &tags };
+    println!(
+      "{}",
+      serde_json::to_string(&item).expect("Tags are always serializable.")
+    );
+  } else {
+    println!("{}:", path);
+    for (t, v) in tags.iter() {
+      println!("  {}: {}", tag_name(*t), v);
+    }
+  }
+  Ok(())
+}
+
+fn set(path: &str, tags: &[(TagType, String)]) -> Result<(), SndFileError> {
+  let mut snd = OpenOptions::ReadWrite(ReadOptions::Auto).from_path(Path::new(path))?;
+  snd.set_tags(tags.iter().map(|(t, v)| (*t, v.as_str())))
+}
+
+fn main() {
+  let mut args = std::env::args().skip(1);
+  let subcommand = args
+    .next()
+    .unwrap_or_else(|| usage_error("Missing subcommand."));
+  let mut json = false;
+  let mut tags = Vec::new();
+  let mut paths = Vec::new();
+  while let Some(arg) = args.next() {
+    if arg == "-h" || arg == "--help" {
+      println!("{}", USAGE);
+      return;
+    } else if arg == "--json" && subcommand == "dump" {
+      json = true;
+    } else if arg.starts_with("--") && subcommand == "set" {
+      let t = tag_from_name(&arg[2..])
+        .unwrap_or_else(|| usage_error(&format!("Unknown tag `{}`.", &arg[2..])));
+      let v = args
+        .next()
+        .unwrap_or_else(|| usage_error(&format!("Missing value for `{}`.", arg)));
+      tags.push((t, v));
+    } else {
+      paths.push(arg);
+    }
+  }
+  if paths.is_empty() {
+    usage_error("Missing path.");
+  }
+  if subcommand == "set" && tags.is_empty() {
+    usage_error("Missing tag to set.");
+  }
+
+  let mut failed = false;
+  for path in paths.iter() {
+    let r = match subcommand.as_str() {
+      "dump" => dump(path, json),
+      "set" => set(path, &tags),
+      _ => usage_error(&format!("Unknown subcommand `{}`.", subcommand)),
+    };
+    if let Err(e) = r {
+      eprintln!("{}: {:?}", path, e);
+      failed = true;
+    }
+  }
+  if failed {
+    std::process::exit(1);
+  }
+}
@@ -0,0 +1,142 @@
+//! Play an audio file with the default output device.
+//!
+//! Usage: `sndfile-play [--seek <[[hh:]mm:]ss>] <path>`
+
+mod common;
+
+use common::parse_time;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use sndfile::*;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+
+const USAGE: &str = "Usage: sndfile-play [--seek <[[hh:]mm:]ss>] <path>";
+const BLOCK_FRAMES: usize = 4096;
+
+fn usage_error(msg: &str) -> ! {
+  eprintln!("{}\n\n{}", msg, USAGE);
+  std::process::exit(2);
+}
+
+fn fail(msg: String) -> ! {
+  eprintln!("{}", msg);
+  std::process::exit(1);
+}
+
+fn main() {
+  let mut args = std::env::args().skip(1);
+  let mut seek = Duration::from_secs(0);
+  let mut path = None;
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        return;
+      }
+      "--seek" => {
+        let v = args
+          .next()
+          .unwrap_or_else(|| usage_error("Missing value for `--seek`."));
+        seek = parse_time(&v).unwrap_or_else(|| usage_error("Invalid time for `--seek`."));
+      }
+      _ => path = Some(arg),
+    }
+  }
+  let path = path.unwrap_or_else(|| usage_error("Missing path."));
+
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(Path::new(&path))
+    .unwrap_or_else(|e| fail(format!("{}: {:?}", path, e)));
+  let samplerate = snd.get_samplerate();
+  let file_channels = snd.get_channels();
+  if seek > Duration::from_secs(0) {
+    let frame = (seek.as_secs_f64() * samplerate as f64) as u64;
+    snd
+      .seek(SeekFrom::Start(frame))
+      .unwrap_or_else(|e| fail(format!("Failed to seek: {:?}", e)));
+  }
+
+  let host = cpal::default_host();
+  let device = host
+    .default_output_device()
+    .unwrap_or_else(|| fail("No output device available.".to_string()));
+  let rate = cpal::SampleRate(samplerate as u32);
+  let supported = device
+    .supported_output_configs()
+    .unwrap_or_else(|e| fail(format!("Failed to query output device: {}", e)))
+    .filter(|x| x.sample_format() == cpal::SampleFormat::F32)
+    .filter(|x| x.min_sample_rate() <= rate && rate <= x.max_sample_rate())
+    .max_by_key(|x| x.channels() as usize == file_channels)
+    .unwrap_or_else(|| {
+      fail(format!(
+        "The output device does not support {} Hz float playback.",
+        samplerate
+      ))
+    });
+  let config: cpal::StreamConfig = supported.with_sample_rate(rate).into();
+  let device_channels = config.channels as usize;
+
+  // Decode on this thread, play on the audio thread.
+  let (tx, rx) = mpsc::sync_channel::<Vec<f32>>(8);
+  let finished = Arc::new(AtomicBool::new(false));
+  let finished_cb = finished.clone();
+  let mut block: Vec<f32> = Vec::new();
+  let mut pos = 0;
+  let stream = device
+    .build_output_stream(
+      &config,
+      move |out: &mut [f32], _: &cpal::OutputCallbackInfo| {
+        for frame in out.chunks_mut(device_channels) {
+          if pos >= block.len() {
+            match rx.try_recv() {
+              Ok(x) => {
+                block = x;
+                pos = 0;
+              }
+              Err(mpsc::TryRecvError::Empty) => {
+                frame.iter_mut().for_each(|x| *x = 0.0);
+                continue;
+              }
+              Err(mpsc::TryRecvError::Disconnected) => {
+                frame.iter_mut().for_each(|x| *x = 0.0);
+                finished_cb.store(true, Ordering::SeqCst);
+                continue;
+              }
+            }
+          }
+          for (i, x) in frame.iter_mut().enumerate() {
+            *x = block[pos + i.min(file_channels - 1)];
+          }
+          pos += file_channels;
+        }
+      },
+      |e| eprintln!("Stream error: {}", e),
+      None,
+    )
+    .unwrap_or_else(|e| fail(format!("Failed to open output stream: {}", e)));
+  stream
+    .play()
+    .unwrap_or_else(|e| fail(format!("Failed to start playback: {}", e)));
+
+  loop {
+    let mut buf = vec![0.0f32; BLOCK_FRAMES * file_channels];
+    let n = snd
+      .read_to_slice(&mut buf)
+      .unwrap_or_else(|e| fail(format!("Failed to decode: {:?}", e)));
+    if n == 0 {
+      break;
+    }
+    buf.truncate(n * file_channels);
+    if tx.send(buf).is_err() {
+      break;
+    }
+  }
+  drop(tx);
+  while !finished.load(Ordering::SeqCst) {
+    std::thread::sleep(Duration::from_millis(50));
+  }
+}
@@ -0,0 +1,72 @@
+//! Split an audio file at given time points.
+//!
+//! Usage: `sndfile-split -o <template> <path> <[[hh:]mm:]ss>...`
+//!
+//! `{}` in the template is replaced by the 1-based segment index.
+
+mod common;
+
+use common::parse_time;
+use sndfile::*;
+use std::ops::Range;
+use std::time::Duration;
+
+const USAGE: &str = "Usage: sndfile-split -o <template> <path> <[[hh:]mm:]ss>...
+
+`{}` in the template is replaced by the 1-based segment index, e.g. `track_{}.wav`.";
+
+fn usage_error(msg: &str) -> ! {
+  eprintln!("{}\n\n{}", msg, USAGE);
+  std::process::exit(2);
+}
+
+fn run(path: &str, points: &[Duration], template: &str) -> Result<(), SndFileError> {
+  let mut bounds = points.to_vec();
+  bounds.insert(0, Duration::from_secs(0));
+  // The last segment is clamped to the end of the file.
+  bounds.push(Duration::from_secs(u64::MAX));
+  let segments: Vec<Range<Duration>> = bounds.windows(2).map(|w| w[0]..w[1]).collect();
+  for out_path in split(path, &segments, template)? {
+    println!("{}", out_path.display());
+  }
+  Ok(())
+}
+
+fn main() {
+  let mut args = std::env::args().skip(1);
+  let mut template = None;
+  let mut positional = Vec::new();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        return;
+      }
+      "-o" => {
+        template = Some(
+          args
+            .next()
+            .unwrap_or_else(|| usage_error("Missing template.")),
+        )
+      }
+      _ => positional.push(arg),
+    }
+  }
+  let template = template.unwrap_or_else(|| usage_error("Missing `-o <template>`."));
+  if !template.contains("{}") {
+    usage_error("Template must contain `{}`.");
+  }
+  if positional.len() < 2 {
+    usage_error("Missing path or time points.");
+  }
+  let mut points: Vec<Duration> = positional[1..]
+    .iter()
+    .map(|x| parse_time(x).unwrap_or_else(|| usage_error(&format!("Invalid time `{}`.", x))))
+    .collect();
+  points.sort();
+
+  if let Err(e) = run(&positional[0], &points, &template) {
+    eprintln!("{}: {:?}", positional[0], e);
+    std::process::exit(1);
+  }
+}
@@ -0,0 +1,471 @@
+use super::edit::BLOCK_FRAMES;
+use super::{SndFile, SndFileError, SndFileIO};
+use std::io::SeekFrom;
+use std::iter::FromIterator;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Crate-wide limit of `read_all_*` functions in bytes, `u64::MAX` means unlimited.
+static DEFAULT_READ_LIMIT: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Set the maximum number of bytes `read_all_to_vec`, `read_all_to_ndarray` and alike may allocate,
+/// or remove the limit with `None`. There is no limit by default.
+///
+/// Reading a file whose decoded size is above the limit returns
+/// `SndFileError::MemoryLimitExceeded` instead of allocating.
+pub fn set_default_read_limit(max_bytes: Option<u64>) {
+  DEFAULT_READ_LIMIT.store(max_bytes.unwrap_or(u64::MAX), Ordering::Relaxed);
+}
+
+/// Returns the limit set by `set_default_read_limit`.
+pub fn default_read_limit() -> Option<u64> {
+  match DEFAULT_READ_LIMIT.load(Ordering::Relaxed) {
+    u64::MAX => None,
+    x => Some(x),
+  }
+}
+
+/// Return value of progress callbacks.
+///
+/// Callbacks returning `()` never cancel, callbacks returning `ControlFlow::Break(())` cancel the
+/// operation, which then fails with `SndFileError::Cancelled`. A shared flag can be checked like:
+///
+/// ```ignore
+/// let cancelled = Arc::new(AtomicBool::new(false));
+/// snd.read_all_to_vec_with_progress::<f32, _, _>(|_, _| {
+///   if cancelled.load(Ordering::Relaxed) {
+///     ControlFlow::Break(())
+///   } else {
+///     ControlFlow::Continue(())
+///   }
+/// })?;
+/// ```
+pub trait ProgressControl {
+  /// Returns `true` if the operation should go on.
+  fn should_continue(self) -> bool;
+}
+
+impl ProgressControl for () {
+  fn should_continue(self) -> bool {
+    true
+  }
+}
+
+impl ProgressControl for ControlFlow<()> {
+  fn should_continue(self) -> bool {
+    matches!(self, ControlFlow::Continue(()))
+  }
+}
+
+/// Call `progress` and return `SndFileError::Cancelled` if it asks to stop.
+pub(crate) fn report<F, R>(progress: &mut F, done: u64, total: u64) -> Result<(), SndFileError>
+where
+  F: FnMut(u64, u64) -> R,
+  R: ProgressControl,
+{
+  if progress(done, total).should_continue() {
+    Ok(())
+  } else {
+    Err(SndFileError::Cancelled)
+  }
+}
+
+impl SndFile {
+  /// Returns the number of samples of the whole file, or an error if they would take more than
+  /// `max_bytes` bytes as `T`.
+  pub(crate) fn check_read_all_len<T>(
+    &mut self,
+    max_bytes: Option<u64>,
+  ) -> Result<usize, SndFileError> {
+    let limit = max_bytes.unwrap_or(u64::MAX);
+    let required = self
+      .len()?
+      .checked_mul(self.get_channels() as u64)
+      .and_then(|x| x.checked_mul(std::mem::size_of::<T>() as u64));
+    match required {
+      Some(x) if x <= limit && x <= isize::MAX as u64 => {
+        Ok((x / std::mem::size_of::<T>() as u64) as usize)
+      }
+      _ => Err(SndFileError::MemoryLimitExceeded {
+        required: required.unwrap_or(u64::MAX),
+        limit,
+      }),
+    }
+  }
+
+  /// Read frames from the I/O cursor until EOF growing the buffer, for files which are not
+  /// seekable, or return an error once they take more than `max_bytes` bytes as `T`.
+  pub(crate) fn read_to_end<T>(&mut self, max_bytes: Option<u64>) -> Result<Vec<T>, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+  {
+    let mut buf = Vec::new();
+    self.read_blocks_to_end(max_bytes, |block: &[T], _| {
+      buf.extend_from_slice(block);
+      Ok(())
+    })?;
+    Ok(buf)
+  }
+
+  /// Read blocks of frames from the I/O cursor until EOF, calling `f(block, frames_done)` for each,
+  /// returns the number of frames read if success.
+  ///
+  /// Returns an error once the frames read take more than `max_bytes` bytes as `T`.
+  fn read_blocks_to_end<T, F>(
+    &mut self,
+    max_bytes: Option<u64>,
+    mut f: F,
+  ) -> Result<u64, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+    F: FnMut(&[T], u64) -> Result<(), SndFileError>,
+  {
+    let limit = max_bytes.unwrap_or(u64::MAX);
+    let channels = self.get_channels();
+    let mut buf = vec![T::default(); BLOCK_FRAMES * channels];
+    let mut done = 0;
+    loop {
+      let n = self.read_to_slice(&mut buf)?;
+      done += n as u64;
+      let bytes = done * channels as u64 * std::mem::size_of::<T>() as u64;
+      if bytes > limit {
+        return Err(SndFileError::MemoryLimitExceeded {
+          required: bytes,
+          limit,
+        });
+      }
+      if n == 0 {
+        return Ok(done);
+      }
+      f(&buf[..n * channels], done)?;
+    }
+  }
+
+  /// Run `f`, then seek back to the current I/O cursor even if `f` fails.
+  ///
+  /// Both cursors are restored in read-write mode.
+  pub(crate) fn preserving_cursor<R, F>(&mut self, f: F) -> Result<R, SndFileError>
+  where
+    F: FnOnce(&mut SndFile) -> Result<R, SndFileError>,
+  {
+    let cursors = self.cursors()?;
+    let r = f(self);
+    let restored = self.restore_cursors(cursors);
+    let r = r?;
+    restored.map(|_| r)
+  }
+
+  /// Read all frames into a `Vec<_>` like `read_all_to_vec`, or returns
+  /// `SndFileError::MemoryLimitExceeded` without reading if the result would take more than
+  /// `max_bytes` bytes.
+  ///
+  /// This function ignores the limit set by `set_default_read_limit`.
+  ///
+  /// The I/O cursor is left unchanged. Files which are not seekable are decoded from the I/O
+  /// cursor until EOF, and the limit is checked while reading.
+  pub fn read_all_to_vec_limited<T>(&mut self, max_bytes: u64) -> Result<Vec<T>, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+  {
+    if !self.is_seekable() {
+      return self.read_to_end(Some(max_bytes));
+    }
+    self.preserving_cursor(|snd| {
+      let n = snd.check_read_all_len::<T>(Some(max_bytes))?;
+      snd.seek(SeekFrom::Start(0))?;
+      let mut buf = vec![T::default(); n];
+      let n_frames = snd.read_to_slice(&mut buf)?;
+      buf.truncate(n_frames * snd.get_channels());
+      Ok(buf)
+    })
+  }
+
+  /// Read all frames into an `Arc<[_]>`, which can be shared between threads, e.g. analysis and
+  /// playback, without copying.
+  ///
+  /// Frames are decoded directly into the shared allocation. The limit set by
+  /// `set_default_read_limit` and the handling of short reads are like `read_all_to_vec`.
+  ///
+  /// The I/O cursor is left unchanged. Files which are not seekable are decoded from the I/O
+  /// cursor until EOF into a growing buffer, which is then copied.
+  pub fn read_all_to_arc<T>(&mut self) -> Result<Arc<[T]>, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+  {
+    if !self.is_seekable() {
+      return self.read_to_end(default_read_limit()).map(Arc::from);
+    }
+    self.preserving_cursor(|snd| {
+      let n = snd.check_read_all_len::<T>(default_read_limit())?;
+      snd.seek(SeekFrom::Start(0))?;
+      let mut buf = Arc::<[T]>::new_uninit_slice(n);
+      let read = snd.read_to_uninit_slice(Arc::get_mut(&mut buf).unwrap())?;
+      if read.len() < n {
+        // Short read of a truncated file, copy the frames decoded into a smaller allocation.
+        return Ok(Arc::from(&*read));
+      }
+      Ok(unsafe { buf.assume_init() })
+    })
+  }
+
+  /// Read all samples into any collection implementing `FromIterator`, e.g. `VecDeque`, without an
+  /// intermediate `Vec` of the whole file.
+  ///
+  /// The limit set by `set_default_read_limit` applies like `read_all_to_vec`.
+  ///
+  /// The I/O cursor is left unchanged. Files which are not seekable are decoded from the I/O
+  /// cursor until EOF, and the limit is checked while reading.
+  pub fn read_all_to<T, C>(&mut self) -> Result<C, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+    C: FromIterator<T>,
+  {
+    if !self.is_seekable() {
+      return self.collect_blocks(default_read_limit());
+    }
+    self.preserving_cursor(|snd| {
+      snd.check_read_all_len::<T>(default_read_limit())?;
+      snd.seek(SeekFrom::Start(0))?;
+      snd.collect_blocks(None)
+    })
+  }
+
+  /// Collect samples from the I/O cursor until EOF, or return an error once they take more than
+  /// `max_bytes` bytes as `T`.
+  fn collect_blocks<T, C>(&mut self, max_bytes: Option<u64>) -> Result<C, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+    C: FromIterator<T>,
+  {
+    let mut error = None;
+    let c = BlockSamples {
+      buf: vec![T::default(); BLOCK_FRAMES * self.get_channels()],
+      snd: self,
+      pos: 0,
+      len: 0,
+      read_bytes: 0,
+      max_bytes: max_bytes.unwrap_or(u64::MAX),
+      error: &mut error,
+    }
+    .collect();
+    match error {
+      Some(e) => Err(e),
+      None => Ok(c),
+    }
+  }
+
+  /// Append all samples to `dst`, returns the number of frames appended if success.
+  ///
+  /// Samples are appended block by block, so on error `dst` holds the samples read before it. The
+  /// limit set by `set_default_read_limit` applies like `read_all_to_vec`.
+  ///
+  /// The I/O cursor is left unchanged. Files which are not seekable are decoded from the I/O
+  /// cursor until EOF, and the limit is checked while reading.
+  pub fn read_append_to<T, E>(&mut self, dst: &mut E) -> Result<u64, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+    E: Extend<T>,
+  {
+    let mut append = |block: &[T], _| {
+      dst.extend(block.iter().copied());
+      Ok(())
+    };
+    if !self.is_seekable() {
+      return self.read_blocks_to_end(default_read_limit(), append);
+    }
+    self.preserving_cursor(|snd| {
+      snd.check_read_all_len::<T>(default_read_limit())?;
+      snd.seek(SeekFrom::Start(0))?;
+      snd.read_blocks_to_end(None, &mut append)
+    })
+  }
+
+  /// Read frames starting at frame `offset`, returns the number of frames read if success.
+  ///
+  /// The seek and the read run under the `&mut self` borrow, so no other call on this handle can
+  /// move the cursor in between, whatever the caller did with it before. The cursor is not
+  /// restored: it is left after the last frame read, i.e. at `offset` plus the returned count. In
+  /// read-write mode both cursors are moved to `offset`, then only the read cursor advances. Use
+  /// one handle per thread for concurrent positional reads, see `SndFilePool`.
+  pub fn read_at<T>(&mut self, offset: u64, dst: &mut [T]) -> Result<usize, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+  {
+    self.seek(SeekFrom::Start(offset))?;
+    self.read_to_slice(dst)
+  }
+
+  /// Write frames starting at frame `offset`, returns the number of frames written if success.
+  ///
+  /// The seek and the write run under the `&mut self` borrow, like `read_at`. The cursor is not
+  /// restored: it is left after the last frame written, i.e. at `offset` plus the returned count.
+  /// In read-write mode both cursors are moved to `offset`, then only the write cursor advances.
+  pub fn write_at<T>(&mut self, offset: u64, src: &[T]) -> Result<usize, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+  {
+    self.seek(SeekFrom::Start(offset))?;
+    self.write_from_slice(src)
+  }
+
+  /// Read all frames into a `Vec<_>` like `read_all_to_vec`, calling
+  /// `progress(frames_done, frames_total)` after each block of frames.
+  ///
+  /// `progress` can cancel the operation, see `ProgressControl`.
+  ///
+  /// The I/O cursor is left unchanged. Files which are not seekable are decoded from the I/O
+  /// cursor until EOF, `frames_total` is then `len_hint`, or 0 if unknown.
+  pub fn read_all_to_vec_with_progress<T, F, R>(
+    &mut self,
+    mut progress: F,
+  ) -> Result<Vec<T>, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+    F: FnMut(u64, u64) -> R,
+    R: ProgressControl,
+  {
+    if !self.is_seekable() {
+      let total = self.len_hint().unwrap_or(0);
+      report(&mut progress, 0, total)?;
+      let mut buf = Vec::new();
+      self.read_blocks_to_end(default_read_limit(), |block: &[T], done| {
+        buf.extend_from_slice(block);
+        report(&mut progress, done, total)
+      })?;
+      return Ok(buf);
+    }
+    self.preserving_cursor(|snd| {
+      let n = snd.check_read_all_len::<T>(default_read_limit())?;
+      let total = snd.len()?;
+      let channels = snd.get_channels();
+      let mut buf = vec![T::default(); n];
+      snd.seek(SeekFrom::Start(0))?;
+      report(&mut progress, 0, total)?;
+      let mut done = 0;
+      for block in buf.chunks_mut(BLOCK_FRAMES * channels) {
+        let n = snd.read_to_slice(block)?;
+        done += n as u64;
+        report(&mut progress, done, total)?;
+        if n < block.len() / channels {
+          break;
+        }
+      }
+      buf.truncate(done as usize * channels);
+      Ok(buf)
+    })
+  }
+
+  /// Write all frames of `src` like `write_from_slice`, calling
+  /// `progress(frames_done, frames_total)` after each block of frames.
+  ///
+  /// `progress` can cancel the operation, see `ProgressControl`. Frames written before cancelling
+  /// are kept in the file.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn write_all_with_progress<T, F, R>(
+    &mut self,
+    src: &[T],
+    mut progress: F,
+  ) -> Result<usize, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+    F: FnMut(u64, u64) -> R,
+    R: ProgressControl,
+  {
+    let total = self.check_buffer_len(src.len())? as u64;
+    let channels = self.get_channels();
+    report(&mut progress, 0, total)?;
+    let mut done = 0;
+    for block in src.chunks(BLOCK_FRAMES * channels) {
+      let n = self.write_from_slice(block)?;
+      done += n as u64;
+      report(&mut progress, done, total)?;
+      if n < block.len() / channels {
+        break;
+      }
+    }
+    Ok(done as usize)
+  }
+
+  /// Write `n_frames` frames of silence, returns the number of frames written if success.
+  ///
+  /// Zeros are written block by block, so padding to a long duration needs no large buffer.
+  ///
+  /// This function affects the I/O cursor.
+  pub fn write_silence(&mut self, n_frames: u64) -> Result<u64, SndFileError> {
+    let channels = self.get_channels();
+    let block = n_frames.min(BLOCK_FRAMES as u64) as usize;
+    let buf = vec![0.0f64; block * channels];
+    let mut done = 0;
+    while done < n_frames {
+      let n_block = (n_frames - done).min(block as u64) as usize;
+      let n = self.write_from_slice(&buf[..n_block * channels])?;
+      done += n as u64;
+      if n < n_block {
+        break;
+      }
+    }
+    Ok(done)
+  }
+}
+
+/// Samples read block by block, stopping at the end of file or the first error, which is stored to
+/// `error`.
+struct BlockSamples<'a, T> {
+  snd: &'a mut SndFile,
+  buf: Vec<T>,
+  pos: usize,
+  len: usize,
+  /// Bytes read so far and the limit above which `SndFileError::MemoryLimitExceeded` is stored.
+  read_bytes: u64,
+  max_bytes: u64,
+  error: &'a mut Option<SndFileError>,
+}
+
+impl<T> Iterator for BlockSamples<'_, T>
+where
+  T: 'static + Default + Copy,
+  SndFile: SndFileIO<T>,
+{
+  type Item = T;
+
+  fn next(&mut self) -> Option<T> {
+    if self.pos == self.len {
+      match self.snd.read_to_slice(&mut self.buf) {
+        Ok(n) => {
+          self.len = n * self.snd.get_channels();
+          self.read_bytes += (self.len * std::mem::size_of::<T>()) as u64;
+          if self.read_bytes > self.max_bytes {
+            *self.error = Some(SndFileError::MemoryLimitExceeded {
+              required: self.read_bytes,
+              limit: self.max_bytes,
+            });
+            self.len = 0;
+          }
+        }
+        Err(e) => {
+          *self.error = Some(e);
+          self.len = 0;
+        }
+      }
+      self.pos = 0;
+      if self.len == 0 {
+        return None;
+      }
+    }
+    self.pos += 1;
+    Some(self.buf[self.pos - 1])
+  }
+}
@@ -0,0 +1,64 @@
+use super::{Endian, SndFile, SndFileError, SndFileIO};
+use bytemuck::Pod;
+
+/// Check that `endian` is the byte order of the running CPU.
+fn check_native_endian(endian: Endian) -> Result<(), SndFileError> {
+  let native = match endian {
+    Endian::CPU => true,
+    Endian::Little => cfg!(target_endian = "little"),
+    Endian::Big => cfg!(target_endian = "big"),
+    Endian::File => false,
+  };
+  if native {
+    Ok(())
+  } else {
+    Err(SndFileError::InvalidParameter(format!(
+      "Got {:?} endian bytes, expect native endian bytes.",
+      endian
+    )))
+  }
+}
+
+fn cast_error(e: bytemuck::PodCastError, size: usize) -> SndFileError {
+  SndFileError::InvalidParameter(format!(
+    "Got unaligned byte buffer or a length not multiple of {} ({:?}), expect a buffer of whole samples.",
+    size, e
+  ))
+}
+
+impl SndFile {
+  /// Read frames from current I/O cursor into raw bytes holding samples of type `T`, returns the
+  /// number of frames read if success.
+  ///
+  /// `dst` is reinterpreted as `&mut [T]` without copy, so it must be aligned for `T` and its length
+  /// must be a multiple of `size_of::<T>()`. `endian` is the byte order expected by the caller,
+  /// only native byte order is supported, i.e. `Endian::CPU` or the endian of the running CPU.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn read_to_bytes<T>(&mut self, dst: &mut [u8], endian: Endian) -> Result<usize, SndFileError>
+  where
+    T: 'static + Default + Copy + Pod,
+    SndFile: SndFileIO<T>,
+  {
+    check_native_endian(endian)?;
+    let dst = bytemuck::try_cast_slice_mut::<u8, T>(dst)
+      .map_err(|e| cast_error(e, std::mem::size_of::<T>()))?;
+    self.read_to_slice(dst)
+  }
+
+  /// Write raw bytes holding samples of type `T`, returns the number of frames written if success.
+  ///
+  /// The requirements on `src` and `endian` are the same as `read_to_bytes`.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn write_from_bytes<T>(&mut self, src: &[u8], endian: Endian) -> Result<usize, SndFileError>
+  where
+    T: 'static + Default + Copy + Pod,
+    SndFile: SndFileIO<T>,
+  {
+    check_native_endian(endian)?;
+    let src = bytemuck::try_cast_slice::<u8, T>(src)
+      .map_err(|e| cast_error(e, std::mem::size_of::<T>()))?;
+    self.write_from_slice(src)
+  }
+}
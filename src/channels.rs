@@ -0,0 +1,298 @@
+use super::ffi;
+#[cfg(not(feature = "system_linking"))]
+use super::sys as sndfile_sys;
+use super::{SndFile, SndFileError};
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+use std::os::raw::{c_int, c_void};
+
+/// Position of a channel, the `SF_CHANNEL_MAP_*` values of libsndfile.
+#[non_exhaustive]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum ChannelPosition {
+  Mono,
+  Left,
+  Right,
+  Center,
+  FrontLeft,
+  FrontRight,
+  FrontCenter,
+  RearCenter,
+  RearLeft,
+  RearRight,
+  Lfe,
+  FrontLeftOfCenter,
+  FrontRightOfCenter,
+  SideLeft,
+  SideRight,
+  TopCenter,
+  TopFrontLeft,
+  TopFrontRight,
+  TopFrontCenter,
+  TopRearLeft,
+  TopRearRight,
+  TopRearCenter,
+  AmbisonicBW,
+  AmbisonicBX,
+  AmbisonicBY,
+  AmbisonicBZ,
+  /// A position unknown to this crate, holds the raw libsndfile value.
+  Unknown(i32),
+}
+
+/// Positions in the order of their `SF_CHANNEL_MAP_*` values, starting from `1`.
+const POSITIONS: [ChannelPosition; 26] = [
+  ChannelPosition::Mono,
+  ChannelPosition::Left,
+  ChannelPosition::Right,
+  ChannelPosition::Center,
+  ChannelPosition::FrontLeft,
+  ChannelPosition::FrontRight,
+  ChannelPosition::FrontCenter,
+  ChannelPosition::RearCenter,
+  ChannelPosition::RearLeft,
+  ChannelPosition::RearRight,
+  ChannelPosition::Lfe,
+  ChannelPosition::FrontLeftOfCenter,
+  ChannelPosition::FrontRightOfCenter,
+  ChannelPosition::SideLeft,
+  ChannelPosition::SideRight,
+  ChannelPosition::TopCenter,
+  ChannelPosition::TopFrontLeft,
+  ChannelPosition::TopFrontRight,
+  ChannelPosition::TopFrontCenter,
+  ChannelPosition::TopRearLeft,
+  ChannelPosition::TopRearRight,
+  ChannelPosition::TopRearCenter,
+  ChannelPosition::AmbisonicBW,
+  ChannelPosition::AmbisonicBX,
+  ChannelPosition::AmbisonicBY,
+  ChannelPosition::AmbisonicBZ,
+];
+
+impl ChannelPosition {
+  fn from_raw(x: c_int) -> Self {
+    match POSITIONS.get((x as usize).wrapping_sub(1)) {
+      Some(p) => *p,
+      None => ChannelPosition::Unknown(x),
+    }
+  }
+
+  fn to_raw(self) -> c_int {
+    match self {
+      ChannelPosition::Unknown(x) => x,
+      p => POSITIONS.iter().position(|x| *x == p).unwrap() as c_int + 1,
+    }
+  }
+
+  /// The speaker bit of `WAVE_FORMAT_EXTENSIBLE`, `None` for positions without one.
+  fn speaker_bit(self) -> Option<u32> {
+    let i = WAVEX_SPEAKERS.iter().position(|x| x.contains(&self))?;
+    Some(1 << i)
+  }
+}
+
+/// Positions of the speaker bits of `WAVE_FORMAT_EXTENSIBLE`, from the lowest bit.
+const WAVEX_SPEAKERS: [&[ChannelPosition]; 18] = [
+  &[ChannelPosition::FrontLeft, ChannelPosition::Left],
+  &[ChannelPosition::FrontRight, ChannelPosition::Right],
+  &[ChannelPosition::FrontCenter, ChannelPosition::Center],
+  &[ChannelPosition::Lfe],
+  &[ChannelPosition::RearLeft],
+  &[ChannelPosition::RearRight],
+  &[ChannelPosition::FrontLeftOfCenter],
+  &[ChannelPosition::FrontRightOfCenter],
+  &[ChannelPosition::RearCenter],
+  &[ChannelPosition::SideLeft],
+  &[ChannelPosition::SideRight],
+  &[ChannelPosition::TopCenter],
+  &[ChannelPosition::TopFrontLeft],
+  &[ChannelPosition::TopFrontCenter],
+  &[ChannelPosition::TopFrontRight],
+  &[ChannelPosition::TopRearLeft],
+  &[ChannelPosition::TopRearCenter],
+  &[ChannelPosition::TopRearRight],
+];
+
+/// Speaker mask of `WAVE_FORMAT_EXTENSIBLE`, the `dwChannelMask` of Microsoft.
+///
+/// Channels of a file are stored in the order of their bits, from the lowest one. Presets can be
+/// combined with `|`, e.g. `ChannelMask::STEREO | ChannelMask::LOW_FREQUENCY`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct ChannelMask(u32);
+
+impl ChannelMask {
+  pub const FRONT_LEFT: ChannelMask = ChannelMask(0x1);
+  pub const FRONT_RIGHT: ChannelMask = ChannelMask(0x2);
+  pub const FRONT_CENTER: ChannelMask = ChannelMask(0x4);
+  pub const LOW_FREQUENCY: ChannelMask = ChannelMask(0x8);
+  pub const BACK_LEFT: ChannelMask = ChannelMask(0x10);
+  pub const BACK_RIGHT: ChannelMask = ChannelMask(0x20);
+  pub const FRONT_LEFT_OF_CENTER: ChannelMask = ChannelMask(0x40);
+  pub const FRONT_RIGHT_OF_CENTER: ChannelMask = ChannelMask(0x80);
+  pub const BACK_CENTER: ChannelMask = ChannelMask(0x100);
+  pub const SIDE_LEFT: ChannelMask = ChannelMask(0x200);
+  pub const SIDE_RIGHT: ChannelMask = ChannelMask(0x400);
+  pub const TOP_CENTER: ChannelMask = ChannelMask(0x800);
+  pub const TOP_FRONT_LEFT: ChannelMask = ChannelMask(0x1000);
+  pub const TOP_FRONT_CENTER: ChannelMask = ChannelMask(0x2000);
+  pub const TOP_FRONT_RIGHT: ChannelMask = ChannelMask(0x4000);
+  pub const TOP_BACK_LEFT: ChannelMask = ChannelMask(0x8000);
+  pub const TOP_BACK_CENTER: ChannelMask = ChannelMask(0x10000);
+  pub const TOP_BACK_RIGHT: ChannelMask = ChannelMask(0x20000);
+
+  /// Front center.
+  pub const MONO: ChannelMask = Self::FRONT_CENTER;
+  /// Front left and right.
+  pub const STEREO: ChannelMask = ChannelMask(0x3);
+  /// 5.1 with back surrounds, `KSAUDIO_SPEAKER_5POINT1`.
+  pub const SURROUND_5_1: ChannelMask = ChannelMask(0x3f);
+  /// 7.1 with side and back surrounds, `KSAUDIO_SPEAKER_7POINT1_SURROUND`.
+  pub const SURROUND_7_1: ChannelMask = ChannelMask(0x63f);
+  /// 7.1 with four height channels, front and back.
+  pub const SURROUND_7_1_4: ChannelMask = ChannelMask(0x2d63f);
+
+  /// Create a mask from raw bits, bits above `TOP_BACK_RIGHT` are kept as is.
+  pub const fn from_bits(bits: u32) -> Self {
+    ChannelMask(bits)
+  }
+
+  /// Get the raw bits.
+  pub const fn bits(self) -> u32 {
+    self.0
+  }
+
+  /// Get the number of channels.
+  pub const fn channels(self) -> usize {
+    self.0.count_ones() as usize
+  }
+
+  /// Check if all bits of `other` are set.
+  pub const fn contains(self, other: ChannelMask) -> bool {
+    self.0 & other.0 == other.0
+  }
+
+  /// Build a mask from the positions of the channels of a file, returns `None` if a position has no
+  /// speaker bit, is repeated, or is not in the order of the bits.
+  pub fn from_positions(positions: &[ChannelPosition]) -> Option<Self> {
+    let mut bits = 0u32;
+    for p in positions {
+      let bit = p.speaker_bit()?;
+      if bit <= bits {
+        return None;
+      }
+      bits |= bit;
+    }
+    Some(ChannelMask(bits))
+  }
+
+  /// Get the position of each channel in file order, returns `None` if an unknown bit is set.
+  pub fn positions(self) -> Option<Vec<ChannelPosition>> {
+    if self.0 >> WAVEX_SPEAKERS.len() != 0 {
+      return None;
+    }
+    Some(
+      WAVEX_SPEAKERS
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| self.0 & (1 << i) != 0)
+        .map(|(_, x)| x[0])
+        .collect(),
+    )
+  }
+}
+
+impl BitOr for ChannelMask {
+  type Output = ChannelMask;
+
+  fn bitor(self, rhs: ChannelMask) -> ChannelMask {
+    ChannelMask(self.0 | rhs.0)
+  }
+}
+
+impl BitOrAssign for ChannelMask {
+  fn bitor_assign(&mut self, rhs: ChannelMask) {
+    self.0 |= rhs.0;
+  }
+}
+
+impl BitAnd for ChannelMask {
+  type Output = ChannelMask;
+
+  fn bitand(self, rhs: ChannelMask) -> ChannelMask {
+    ChannelMask(self.0 & rhs.0)
+  }
+}
+
+impl SndFile {
+  /// Get the position of each channel, or `None` if the file does not store them.
+  pub fn get_channel_map(&self) -> Result<Option<Vec<ChannelPosition>>, SndFileError> {
+    let mut raw = vec![0 as c_int; self.get_channels()];
+    let r = unsafe {
+      ffi::sf_command(
+        self.unsafe_fields.sndfile_ptr,
+        sndfile_sys::SFC_GET_CHANNEL_MAP_INFO,
+        raw.as_mut_ptr() as *mut c_void,
+        (raw.len() * std::mem::size_of::<c_int>()) as c_int,
+      )
+    };
+    if r == sndfile_sys::SF_TRUE {
+      Ok(Some(
+        raw.into_iter().map(ChannelPosition::from_raw).collect(),
+      ))
+    } else {
+      Ok(None)
+    }
+  }
+
+  /// Set the position of each channel, must be called before writing any frame.
+  ///
+  /// Containers like WAVEX, CAF and AIFF store them, `WAVEX` as a `ChannelMask` which requires
+  /// the positions in the order of its bits. Returns `SndFileError::InvalidParameter` if the
+  /// length of `positions` is not the channel count or if libsndfile rejects them.
+  pub fn set_channel_map(&mut self, positions: &[ChannelPosition]) -> Result<(), SndFileError> {
+    if positions.len() != self.get_channels() {
+      return Err(SndFileError::InvalidParameter(format!(
+        "Got {} channel positions, expect {} positions.",
+        positions.len(),
+        self.get_channels()
+      )));
+    }
+    let mut raw: Vec<c_int> = positions.iter().map(|x| x.to_raw()).collect();
+    let r = unsafe {
+      ffi::sf_command(
+        self.unsafe_fields.sndfile_ptr,
+        sndfile_sys::SFC_SET_CHANNEL_MAP_INFO,
+        raw.as_mut_ptr() as *mut c_void,
+        (raw.len() * std::mem::size_of::<c_int>()) as c_int,
+      )
+    };
+    if r == sndfile_sys::SF_TRUE {
+      Ok(())
+    } else {
+      Err(SndFileError::InvalidParameter(
+        "Channel map is rejected by libsndfile.".to_string(),
+      ))
+    }
+  }
+
+  /// Get the channel map as a `ChannelMask`, or `None` if the file has no channel map or it has no
+  /// equivalent mask.
+  pub fn get_channel_mask(&self) -> Result<Option<ChannelMask>, SndFileError> {
+    Ok(
+      self
+        .get_channel_map()?
+        .and_then(|x| ChannelMask::from_positions(&x)),
+    )
+  }
+
+  /// Set the channel map from `mask`, see `set_channel_map`.
+  pub fn set_channel_mask(&mut self, mask: ChannelMask) -> Result<(), SndFileError> {
+    let positions = mask.positions().ok_or_else(|| {
+      SndFileError::InvalidParameter(format!(
+        "Got channel mask {:#x}, expect only known speaker bits.",
+        mask.bits()
+      ))
+    })?;
+    self.set_channel_map(&positions)
+  }
+}
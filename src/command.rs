@@ -0,0 +1,238 @@
+use super::ffi;
+#[cfg(not(feature = "system_linking"))]
+use super::sys as sndfile_sys;
+use super::trace::OpTimer;
+use super::{SndFile, SndFileError};
+use sndfile_sys::sf_count_t;
+use std::os::raw::{c_char, c_int, c_void};
+
+/// Typed `sf_command` operations, see `SndFile::command`.
+///
+/// Commands without a variant here can be sent with `SndFile::command_raw`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+  /// `SFC_GET_LOG_INFO`: get the internal log buffer of libsndfile, returns `CommandOutput::Text`.
+  GetLogInfo,
+  /// `SFC_GET_NORM_FLOAT`: returns `CommandOutput::Bool`.
+  GetNormFloat,
+  /// `SFC_GET_NORM_DOUBLE`: returns `CommandOutput::Bool`.
+  GetNormDouble,
+  /// `SFC_SET_NORM_FLOAT`: returns the previous setting as `CommandOutput::Bool`.
+  SetNormFloat(bool),
+  /// `SFC_SET_NORM_DOUBLE`: returns the previous setting as `CommandOutput::Bool`.
+  SetNormDouble(bool),
+  /// `SFC_SET_SCALE_FLOAT_INT_READ`: returns the previous setting as `CommandOutput::Bool`.
+  SetScaleFloatIntRead(bool),
+  /// `SFC_SET_SCALE_INT_FLOAT_WRITE`: returns the previous setting as `CommandOutput::Bool`.
+  SetScaleIntFloatWrite(bool),
+  /// `SFC_CALC_SIGNAL_MAX`: returns `CommandOutput::Double`.
+  ///
+  /// This command may affect the I/O cursor.
+  CalcSignalMax,
+  /// `SFC_CALC_NORM_SIGNAL_MAX`: returns `CommandOutput::Double`.
+  ///
+  /// This command may affect the I/O cursor.
+  CalcNormSignalMax,
+  /// `SFC_SET_ADD_PEAK_CHUNK`: returns `CommandOutput::Bool`.
+  SetAddPeakChunk(bool),
+  /// `SFC_UPDATE_HEADER_NOW`: returns `CommandOutput::None`.
+  UpdateHeaderNow,
+  /// `SFC_SET_UPDATE_HEADER_AUTO`: returns `CommandOutput::Bool`.
+  SetUpdateHeaderAuto(bool),
+  /// `SFC_FILE_TRUNCATE`: truncate the file to given frame count, returns `CommandOutput::None`.
+  FileTruncate(u64),
+  /// `SFC_SET_RAW_START_OFFSET`: returns `CommandOutput::None`.
+  SetRawStartOffset(u64),
+  /// `SFC_SET_CLIPPING`: returns `CommandOutput::Bool`.
+  SetClipping(bool),
+  /// `SFC_GET_CLIPPING`: returns `CommandOutput::Bool`.
+  GetClipping,
+  /// `SFC_RAW_DATA_NEEDS_ENDSWAP`: returns `CommandOutput::Bool`.
+  RawDataNeedsEndswap,
+  /// `SFC_SET_VBR_ENCODING_QUALITY`: a value between `0.0` and `1.0`, returns `CommandOutput::None`.
+  SetVbrEncodingQuality(f64),
+  /// `SFC_SET_COMPRESSION_LEVEL`: a value between `0.0` and `1.0`, returns `CommandOutput::None`.
+  SetCompressionLevel(f64),
+  /// `SFC_RF64_AUTO_DOWNGRADE`: returns `CommandOutput::Bool`.
+  Rf64AutoDowngrade(bool),
+  /// `SFC_SET_OGG_PAGE_LATENCY_MS`: target duration of Ogg pages in milliseconds, returns
+  /// `CommandOutput::None`.
+  ///
+  /// Shorter pages lower the latency of live streams at the cost of more container overhead. Must
+  /// be sent before writing any frame, requires libsndfile 1.1 or later. Only Ogg Opus files
+  /// honour it, others reject it.
+  SetOggPageLatencyMs(f64),
+}
+
+/// `SFC_SET_OGG_PAGE_LATENCY_MS` of libsndfile 1.1.
+const SFC_SET_OGG_PAGE_LATENCY_MS: c_int = 0x1302;
+
+/// Value returned by `SndFile::command`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandOutput {
+  None,
+  Bool(bool),
+  Double(f64),
+  Text(String),
+}
+
+fn bool_to_flag(x: bool) -> c_int {
+  if x {
+    sndfile_sys::SF_TRUE
+  } else {
+    sndfile_sys::SF_FALSE
+  }
+}
+
+impl SndFile {
+  /// Run a typed `sf_command` on this file.
+  pub fn command(&mut self, cmd: Command) -> Result<CommandOutput, SndFileError> {
+    let ptr = self.unsafe_fields.sndfile_ptr;
+    let _timer = OpTimer::new("command", ptr);
+    let flag_cmd = |sfc: c_int, x: bool| unsafe {
+      ffi::sf_command(ptr, sfc, std::ptr::null_mut(), bool_to_flag(x))
+    };
+    let query_cmd = |sfc: c_int| unsafe { ffi::sf_command(ptr, sfc, std::ptr::null_mut(), 0) };
+    match cmd {
+      Command::GetLogInfo => {
+        let mut buf = vec![0 as c_char; 4096];
+        unsafe {
+          ffi::sf_command(
+            ptr,
+            sndfile_sys::SFC_GET_LOG_INFO,
+            buf.as_mut_ptr() as *mut c_void,
+            buf.len() as c_int,
+          )
+        };
+        // Make sure the buffer is NUL terminated even if libsndfile filled all of it.
+        *buf.last_mut().unwrap() = 0;
+        let s = unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) };
+        Ok(CommandOutput::Text(s.to_string_lossy().into_owned()))
+      }
+      Command::GetNormFloat => Ok(CommandOutput::Bool(
+        query_cmd(sndfile_sys::SFC_GET_NORM_FLOAT) != sndfile_sys::SF_FALSE,
+      )),
+      Command::GetNormDouble => Ok(CommandOutput::Bool(
+        query_cmd(sndfile_sys::SFC_GET_NORM_DOUBLE) != sndfile_sys::SF_FALSE,
+      )),
+      Command::SetNormFloat(x) => Ok(CommandOutput::Bool(
+        flag_cmd(sndfile_sys::SFC_SET_NORM_FLOAT, x) != sndfile_sys::SF_FALSE,
+      )),
+      Command::SetNormDouble(x) => Ok(CommandOutput::Bool(
+        flag_cmd(sndfile_sys::SFC_SET_NORM_DOUBLE, x) != sndfile_sys::SF_FALSE,
+      )),
+      Command::SetScaleFloatIntRead(x) => Ok(CommandOutput::Bool(
+        flag_cmd(sndfile_sys::SFC_SET_SCALE_FLOAT_INT_READ, x) != sndfile_sys::SF_FALSE,
+      )),
+      Command::SetScaleIntFloatWrite(x) => Ok(CommandOutput::Bool(
+        flag_cmd(sndfile_sys::SFC_SET_SCALE_INT_FLOAT_WRITE, x) != sndfile_sys::SF_FALSE,
+      )),
+      Command::CalcSignalMax | Command::CalcNormSignalMax => {
+        let sfc = match cmd {
+          Command::CalcSignalMax => sndfile_sys::SFC_CALC_SIGNAL_MAX,
+          _ => sndfile_sys::SFC_CALC_NORM_SIGNAL_MAX,
+        };
+        let mut v: f64 = 0.0;
+        let r = unsafe {
+          ffi::sf_command(
+            ptr,
+            sfc,
+            &mut v as *mut f64 as *mut c_void,
+            std::mem::size_of::<f64>() as c_int,
+          )
+        };
+        if r == 0 {
+          Ok(CommandOutput::Double(v))
+        } else {
+          Err(self.get_last_error())
+        }
+      }
+      Command::SetAddPeakChunk(x) => Ok(CommandOutput::Bool(
+        flag_cmd(sndfile_sys::SFC_SET_ADD_PEAK_CHUNK, x) != sndfile_sys::SF_FALSE,
+      )),
+      Command::UpdateHeaderNow => {
+        query_cmd(sndfile_sys::SFC_UPDATE_HEADER_NOW);
+        Ok(CommandOutput::None)
+      }
+      Command::SetUpdateHeaderAuto(x) => Ok(CommandOutput::Bool(
+        flag_cmd(sndfile_sys::SFC_SET_UPDATE_HEADER_AUTO, x) != sndfile_sys::SF_FALSE,
+      )),
+      Command::FileTruncate(x) | Command::SetRawStartOffset(x) => {
+        let sfc = match cmd {
+          Command::FileTruncate(_) => sndfile_sys::SFC_FILE_TRUNCATE,
+          _ => sndfile_sys::SFC_SET_RAW_START_OFFSET,
+        };
+        let mut v = x as sf_count_t;
+        let r = unsafe {
+          ffi::sf_command(
+            ptr,
+            sfc,
+            &mut v as *mut sf_count_t as *mut c_void,
+            std::mem::size_of::<sf_count_t>() as c_int,
+          )
+        };
+        if r == 0 {
+          Ok(CommandOutput::None)
+        } else {
+          Err(self.get_last_error())
+        }
+      }
+      Command::SetClipping(x) => Ok(CommandOutput::Bool(
+        flag_cmd(sndfile_sys::SFC_SET_CLIPPING, x) != sndfile_sys::SF_FALSE,
+      )),
+      Command::GetClipping => Ok(CommandOutput::Bool(
+        query_cmd(sndfile_sys::SFC_GET_CLIPPING) != sndfile_sys::SF_FALSE,
+      )),
+      Command::RawDataNeedsEndswap => Ok(CommandOutput::Bool(
+        query_cmd(sndfile_sys::SFC_RAW_DATA_NEEDS_ENDSWAP) != sndfile_sys::SF_FALSE,
+      )),
+      Command::SetVbrEncodingQuality(x)
+      | Command::SetCompressionLevel(x)
+      | Command::SetOggPageLatencyMs(x) => {
+        let sfc = match cmd {
+          Command::SetVbrEncodingQuality(_) => sndfile_sys::SFC_SET_VBR_ENCODING_QUALITY,
+          Command::SetCompressionLevel(_) => sndfile_sys::SFC_SET_COMPRESSION_LEVEL,
+          _ => SFC_SET_OGG_PAGE_LATENCY_MS,
+        };
+        let mut v = x;
+        let r = unsafe {
+          ffi::sf_command(
+            ptr,
+            sfc,
+            &mut v as *mut f64 as *mut c_void,
+            std::mem::size_of::<f64>() as c_int,
+          )
+        };
+        if r == sndfile_sys::SF_TRUE {
+          Ok(CommandOutput::None)
+        } else {
+          Err(SndFileError::InvalidParameter(format!(
+            "Command `{:?}` is rejected by libsndfile.",
+            cmd
+          )))
+        }
+      }
+      Command::Rf64AutoDowngrade(x) => Ok(CommandOutput::Bool(
+        flag_cmd(sndfile_sys::SFC_RF64_AUTO_DOWNGRADE, x) != sndfile_sys::SF_FALSE,
+      )),
+    }
+  }
+
+  /// Run a raw `sf_command` on this file, returns the raw return value of `sf_command`.
+  ///
+  /// `data` is passed as the data pointer (or `NULL` if `None`) and `datasize` as is.
+  ///
+  /// # Safety
+  /// The caller must make sure that libsndfile does not access `data` beyond its length for given
+  /// `cmd` and `datasize`, and that the command does not break the invariants of `SndFile`, e.g.,
+  /// by changing the channel count.
+  pub unsafe fn command_raw(&mut self, cmd: i32, data: Option<&mut [u8]>, datasize: i32) -> i32 {
+    let data_ptr = match data {
+      Some(x) => x.as_mut_ptr() as *mut c_void,
+      None => std::ptr::null_mut(),
+    };
+    let _timer = OpTimer::new("command", self.unsafe_fields.sndfile_ptr);
+    ffi::sf_command(self.unsafe_fields.sndfile_ptr, cmd, data_ptr, datasize)
+  }
+}
@@ -0,0 +1,144 @@
+use super::{OpenOptions, ReadOptions, SndFile, SndFileError, SndFileIO};
+use std::io::SeekFrom;
+use std::path::Path;
+
+/// Several files with the same channel count and samplerate read as one continuous stream of
+/// frames, e.g. a session recorded into hourly files.
+#[derive(Debug)]
+pub struct ConcatReader {
+  files: Vec<SndFile>,
+  /// Position of the first frame of each file in the stream.
+  offsets: Vec<u64>,
+  len: u64,
+  current: usize,
+  pos: u64,
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl ConcatReader {
+  /// Concatenate `files`, which must be seekable and have the same channel count and samplerate.
+  ///
+  /// Each file is read from its start.
+  pub fn new(mut files: Vec<SndFile>) -> Result<ConcatReader, SndFileError> {
+    if files.is_empty() {
+      return Err(SndFileError::InvalidParameter(
+        "Got 0 files, expect at least 1 file.".to_string(),
+      ));
+    }
+    let (channels, samplerate) = (files[0].get_channels(), files[0].get_samplerate());
+    let mut offsets = Vec::with_capacity(files.len());
+    let mut len = 0;
+    for (i, snd) in files.iter_mut().enumerate() {
+      if snd.get_channels() != channels || snd.get_samplerate() != samplerate {
+        return Err(SndFileError::InvalidParameter(format!(
+          "File {} has {} channels at samplerate {}, expect {} channels at samplerate {}.",
+          i,
+          snd.get_channels(),
+          snd.get_samplerate(),
+          channels,
+          samplerate
+        )));
+      }
+      offsets.push(len);
+      len += snd.len()?;
+    }
+    files[0].seek(SeekFrom::Start(0))?;
+    Ok(ConcatReader {
+      files,
+      offsets,
+      len,
+      current: 0,
+      pos: 0,
+    })
+  }
+
+  /// Open and concatenate the files at `paths`, see `new`.
+  pub fn open<P: AsRef<Path>>(paths: &[P]) -> Result<ConcatReader, SndFileError> {
+    let files = paths
+      .iter()
+      .map(|x| OpenOptions::ReadOnly(ReadOptions::Auto).from_path(x))
+      .collect::<Result<Vec<_>, _>>()?;
+    ConcatReader::new(files)
+  }
+
+  /// Borrow the underlying files.
+  pub fn files(&self) -> &[SndFile] {
+    &self.files
+  }
+
+  /// Unwrap the underlying files.
+  pub fn into_inner(self) -> Vec<SndFile> {
+    self.files
+  }
+
+  /// Get sample rate.
+  pub fn get_samplerate(&self) -> usize {
+    self.files[0].get_samplerate()
+  }
+
+  /// Get channel count.
+  pub fn get_channels(&self) -> usize {
+    self.files[0].get_channels()
+  }
+
+  /// Total length in frames.
+  pub fn len(&self) -> u64 {
+    self.len
+  }
+
+  /// Current frame position in the stream.
+  pub fn position(&self) -> u64 {
+    self.pos
+  }
+
+  /// Seek to a frame position of the stream, returns the new position.
+  ///
+  /// Positions before the start or past the end return `SndFileError::InvalidParameter`.
+  pub fn seek(&mut self, pos: SeekFrom) -> Result<u64, SndFileError> {
+    let target = match pos {
+      SeekFrom::Start(x) => x as i128,
+      SeekFrom::Current(x) => self.pos as i128 + x as i128,
+      SeekFrom::End(x) => self.len as i128 + x as i128,
+    };
+    if target < 0 || target > self.len as i128 {
+      return Err(SndFileError::InvalidParameter(format!(
+        "Got position {}, expect a position within 0..={}.",
+        target, self.len
+      )));
+    }
+    let target = target as u64;
+    let i = self.offsets.partition_point(|x| *x <= target) - 1;
+    self.files[i].seek(SeekFrom::Start(target - self.offsets[i]))?;
+    self.current = i;
+    self.pos = target;
+    Ok(target)
+  }
+
+  /// Read frames across file boundaries, returns the number of frames read if success.
+  ///
+  /// `dst.len()` must be a multiple of the channel count, otherwise `SndFileError::InvalidParameter`
+  /// is returned.
+  pub fn read_to_slice<T>(&mut self, dst: &mut [T]) -> Result<usize, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+  {
+    let channels = self.get_channels();
+    let n_frames = self.files[0].check_buffer_len(dst.len())?;
+    let mut done = 0;
+    while done < n_frames {
+      let n = self.files[self.current].read_to_slice(&mut dst[done * channels..])?;
+      if n == 0 {
+        if self.current + 1 == self.files.len() {
+          break;
+        }
+        self.current += 1;
+        self.files[self.current].seek(SeekFrom::Start(0))?;
+        continue;
+      }
+      done += n;
+      self.pos += n as u64;
+    }
+    Ok(done)
+  }
+}
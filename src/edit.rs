@@ -0,0 +1,360 @@
+use super::bulk::{report, ProgressControl};
+use super::metadata::supports_chunks;
+use super::{
+  OpenOptions, ReadOptions, SndFile, SndFileError, SndFileIO, SubtypeFormat, WriteOptions,
+};
+use std::io::SeekFrom;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Number of frames processed at once by streaming operations.
+pub(crate) const BLOCK_FRAMES: usize = 4096;
+
+/// Number of decoded blocks `transcode_pipelined` buffers ahead of the encoder.
+const PIPELINE_BLOCKS: usize = 8;
+
+/// Convert a duration to a frame count, saturating on overflow.
+pub(crate) fn duration_to_frames(d: Duration, samplerate: usize) -> u64 {
+  let sr = samplerate as u64;
+  d.as_secs()
+    .saturating_mul(sr)
+    .saturating_add(d.subsec_nanos() as u64 * sr / 1_000_000_000)
+}
+
+/// `WriteOptions` with the same format as `src`.
+fn same_format_as(src: &SndFile) -> WriteOptions {
+  WriteOptions::new(
+    src.get_major_format(),
+    src.get_subtype_format(),
+    src.get_endian(),
+    src.get_samplerate(),
+    src.get_channels(),
+  )
+}
+
+/// Stream frames from the I/O cursor of `src` to `dst`, returns the number of frames copied.
+///
+/// At most `n_frames` frames are copied if specified, otherwise frames are copied until the end of
+/// `src`. Samples are transferred as `f64`, which is lossless for all PCM and float encodings.
+///
+/// This function affects the I/O cursor of both files.
+pub fn copy_frames(
+  src: &mut SndFile,
+  dst: &mut SndFile,
+  n_frames: Option<u64>,
+) -> Result<u64, SndFileError> {
+  copy_frames_with_progress(src, dst, n_frames, &mut |_| Ok(()))
+}
+
+/// `copy_frames` calling `progress` with the number of frames copied so far after each block,
+/// stops at the first error returned by `progress`.
+fn copy_frames_with_progress(
+  src: &mut SndFile,
+  dst: &mut SndFile,
+  n_frames: Option<u64>,
+  progress: &mut dyn FnMut(u64) -> Result<(), SndFileError>,
+) -> Result<u64, SndFileError> {
+  if src.get_channels() != dst.get_channels() {
+    return Err(SndFileError::InvalidParameter(format!(
+      "Got {} source channels, expect {} channels.",
+      src.get_channels(),
+      dst.get_channels()
+    )));
+  }
+  let channels = src.get_channels();
+  let mut buf = vec![0.0f64; BLOCK_FRAMES * channels];
+  let mut copied = 0;
+  loop {
+    let n_block = match n_frames {
+      Some(x) => (x - copied).min(BLOCK_FRAMES as u64) as usize,
+      None => BLOCK_FRAMES,
+    };
+    if n_block == 0 {
+      break;
+    }
+    let n = src.read_to_slice(&mut buf[..n_block * channels])?;
+    if n == 0 {
+      break;
+    }
+    dst.write_from_slice(&buf[..n * channels])?;
+    copied += n as u64;
+    progress(copied)?;
+  }
+  Ok(copied)
+}
+
+/// Concatenate `inputs` into a new file at `output`, returns the number of frames written.
+///
+/// All inputs must have the same channel count as `options`, and the same samplerate as well if
+/// `check_samplerate` is `true`. Inputs are checked before `output` is created.
+pub fn concat<P: AsRef<Path>, Q: AsRef<Path>>(
+  inputs: &[P],
+  output: Q,
+  options: WriteOptions,
+  check_samplerate: bool,
+) -> Result<u64, SndFileError> {
+  for path in inputs.iter() {
+    let src = OpenOptions::ReadOnly(ReadOptions::Auto).from_path(path)?;
+    if src.get_channels() != options.channels {
+      return Err(SndFileError::InvalidParameter(format!(
+        "`{}` has {} channels, expect {} channels.",
+        path.as_ref().display(),
+        src.get_channels(),
+        options.channels
+      )));
+    }
+    if check_samplerate && src.get_samplerate() != options.samplerate {
+      return Err(SndFileError::InvalidParameter(format!(
+        "`{}` has samplerate {}, expect {}.",
+        path.as_ref().display(),
+        src.get_samplerate(),
+        options.samplerate
+      )));
+    }
+  }
+
+  let mut dst = OpenOptions::WriteOnly(options).from_path(output)?;
+  let mut n = 0;
+  for path in inputs.iter() {
+    let mut src = OpenOptions::ReadOnly(ReadOptions::Auto).from_path(path)?;
+    n += copy_frames(&mut src, &mut dst, None)?;
+  }
+  Ok(n)
+}
+
+/// Split `input` into one file per segment, returns the paths of written files.
+///
+/// Output paths are built by replacing `{}` in `output_template` with the 1-based segment index,
+/// e.g. `"track_{}.flac"`. Outputs have the same format and tags as `input`. Segments are clamped
+/// to the length of `input`.
+pub fn split<P: AsRef<Path>>(
+  input: P,
+  segments: &[Range<Duration>],
+  output_template: &str,
+) -> Result<Vec<PathBuf>, SndFileError> {
+  if !output_template.contains("{}") {
+    return Err(SndFileError::InvalidParameter(
+      "Got invalid output template, expect a string containing `{}`.".to_string(),
+    ));
+  }
+  let mut src = OpenOptions::ReadOnly(ReadOptions::Auto).from_path(input)?;
+  let len = src.len()?;
+  let tags = src.get_tags();
+  let mut out = Vec::with_capacity(segments.len());
+  for (i, segment) in segments.iter().enumerate() {
+    let start = duration_to_frames(segment.start, src.get_samplerate()).min(len);
+    let end = duration_to_frames(segment.end, src.get_samplerate()).min(len);
+    let path = PathBuf::from(output_template.replace("{}", &(i + 1).to_string()));
+    let mut dst = OpenOptions::WriteOnly(same_format_as(&src)).from_path(&path)?;
+    dst.set_tags(tags.iter().map(|(t, v)| (*t, v.as_str())))?;
+    src.seek(SeekFrom::Start(start))?;
+    copy_frames(&mut src, &mut dst, Some(end.saturating_sub(start)))?;
+    out.push(path);
+  }
+  Ok(out)
+}
+
+/// Copy tags, broadcast info, cue points with their labels and raw chunks of `src` to `dst`, which must not have any
+/// frame written yet.
+fn copy_metadata(src: &SndFile, dst: &mut SndFile) -> Result<(), SndFileError> {
+  let tags = src.get_tags();
+  dst.set_tags(tags.iter().map(|(t, v)| (*t, v.as_str())))?;
+  if let Some(info) = src.get_broadcast_info() {
+    dst.set_broadcast_info(&info)?;
+  }
+  let cues = src.get_cues();
+  if !cues.is_empty() {
+    dst.set_cues(&cues)?;
+  }
+  for chunk in src.get_adtl_chunks().iter().chain(src.get_chunks().iter()) {
+    dst.add_chunk(chunk)?;
+  }
+  Ok(())
+}
+
+/// Bytes per sample of `subtype` if its encoded data can be copied with `read_raw` and
+/// `write_raw`, i.e. one sample per fixed number of bytes.
+fn raw_sample_size(subtype: SubtypeFormat) -> Option<usize> {
+  match subtype {
+    SubtypeFormat::PCM_S8 | SubtypeFormat::PCM_U8 | SubtypeFormat::ULAW | SubtypeFormat::ALAW => {
+      Some(1)
+    }
+    SubtypeFormat::PCM_16 => Some(2),
+    SubtypeFormat::PCM_24 => Some(3),
+    SubtypeFormat::PCM_32 | SubtypeFormat::FLOAT => Some(4),
+    SubtypeFormat::DOUBLE => Some(8),
+    _ => None,
+  }
+}
+
+/// Copy encoded frames from the I/O cursor of `src` to `dst`, which have the same format, returns
+/// the number of frames copied.
+fn copy_raw_frames(
+  src: &mut SndFile,
+  dst: &mut SndFile,
+  sample_size: usize,
+) -> Result<u64, SndFileError> {
+  let frame_size = sample_size * src.get_channels();
+  let mut buf = vec![0u8; BLOCK_FRAMES * frame_size];
+  let mut copied = 0;
+  loop {
+    let n = src.read_raw(&mut buf)? / frame_size;
+    if n == 0 {
+      break;
+    }
+    dst.write_raw(&buf[..n * frame_size])?;
+    copied += n as u64;
+  }
+  Ok(copied)
+}
+
+/// Copy `src` to `dst` with the same format, carrying over tags, broadcast info, cue points and
+/// raw chunks, returns the number of frames copied.
+///
+/// Frames of PCM, float, u-law and A-law encodings are copied byte for byte, without dither or
+/// clipping. Other encodings are decoded and encoded again, which is lossless for lossless ones.
+pub fn copy_with_metadata<P: AsRef<Path>, Q: AsRef<Path>>(
+  src: P,
+  dst: Q,
+) -> Result<u64, SndFileError> {
+  let mut src = OpenOptions::ReadOnly(ReadOptions::Auto).from_path(src)?;
+  let mut dst = OpenOptions::WriteOnly(same_format_as(&src)).from_path(dst)?;
+  copy_metadata(&src, &mut dst)?;
+  src.seek(SeekFrom::Start(0))?;
+  match raw_sample_size(src.get_subtype_format()) {
+    Some(x) => copy_raw_frames(&mut src, &mut dst, x),
+    None => copy_frames(&mut src, &mut dst, None),
+  }
+}
+
+/// Convert `src` to `dst` with given format, carrying over tags, returns the number of frames
+/// copied.
+///
+/// If `preserve_chunks` is `true`, raw chunks of `src` like `iXML` or `axml` are carried over as
+/// well when the format of `dst` supports them, i.e. WAV and AIFF. Otherwise they are dropped.
+pub fn transcode<P: AsRef<Path>, Q: AsRef<Path>>(
+  src: P,
+  dst: Q,
+  options: WriteOptions,
+  preserve_chunks: bool,
+) -> Result<u64, SndFileError> {
+  transcode_with_progress(src, dst, options, preserve_chunks, |_, _| ())
+}
+
+/// `transcode` calling `progress(frames_done, frames_total)` after each block of frames.
+///
+/// `progress` can cancel the operation, see `ProgressControl`. The partial output file is removed
+/// when cancelled.
+pub fn transcode_with_progress<P, Q, F, R>(
+  src: P,
+  dst: Q,
+  options: WriteOptions,
+  preserve_chunks: bool,
+  mut progress: F,
+) -> Result<u64, SndFileError>
+where
+  P: AsRef<Path>,
+  Q: AsRef<Path>,
+  F: FnMut(u64, u64) -> R,
+  R: ProgressControl,
+{
+  let mut src = OpenOptions::ReadOnly(ReadOptions::Auto).from_path(src)?;
+  let mut dst_file = OpenOptions::WriteOnly(options).from_path(&dst)?;
+  let r = transcode_to(&mut src, &mut dst_file, preserve_chunks, &mut progress);
+  if let Err(SndFileError::Cancelled) = r {
+    drop(dst_file);
+    std::fs::remove_file(dst).map_err(SndFileError::IOError)?;
+  }
+  r
+}
+
+/// Copy tags of `src` to `dst`, and raw chunks as well if `preserve_chunks` is `true`, see
+/// `transcode`.
+fn copy_transcode_metadata(
+  src: &SndFile,
+  dst: &mut SndFile,
+  preserve_chunks: bool,
+) -> Result<(), SndFileError> {
+  let tags = src.get_tags();
+  dst.set_tags(tags.iter().map(|(t, v)| (*t, v.as_str())))?;
+  if preserve_chunks && supports_chunks(dst.get_major_format()) {
+    for chunk in src.get_chunks() {
+      dst.add_chunk(&chunk)?;
+    }
+  }
+  Ok(())
+}
+
+fn transcode_to<F, R>(
+  src: &mut SndFile,
+  dst: &mut SndFile,
+  preserve_chunks: bool,
+  progress: &mut F,
+) -> Result<u64, SndFileError>
+where
+  F: FnMut(u64, u64) -> R,
+  R: ProgressControl,
+{
+  copy_transcode_metadata(src, dst, preserve_chunks)?;
+  let total = src.len()?;
+  src.seek(SeekFrom::Start(0))?;
+  report(progress, 0, total)?;
+  copy_frames_with_progress(src, dst, None, &mut |done| report(progress, done, total))
+}
+
+/// `transcode` decoding on a separate thread, so decoding and encoding run in parallel, e.g. to
+/// speed up FLAC to Ogg conversions on multicore machines.
+///
+/// Decoded blocks are passed to the encoding thread through a bounded channel, so memory use stays
+/// constant however fast the decoder is.
+pub fn transcode_pipelined<P: AsRef<Path>, Q: AsRef<Path>>(
+  src: P,
+  dst: Q,
+  options: WriteOptions,
+  preserve_chunks: bool,
+) -> Result<u64, SndFileError> {
+  let mut src = OpenOptions::ReadOnly(ReadOptions::Auto).from_path(src)?;
+  let mut dst = OpenOptions::WriteOnly(options).from_path(dst)?;
+  if src.get_channels() != dst.get_channels() {
+    return Err(SndFileError::InvalidParameter(format!(
+      "Got {} source channels, expect {} channels.",
+      src.get_channels(),
+      dst.get_channels()
+    )));
+  }
+  copy_transcode_metadata(&src, &mut dst, preserve_chunks)?;
+  src.seek(SeekFrom::Start(0))?;
+  let channels = src.get_channels();
+  let (full_tx, full_rx) = mpsc::sync_channel(PIPELINE_BLOCKS);
+  // Buffers written by the encoder are sent back, so the decoder does not allocate for each block.
+  let (empty_tx, empty_rx) = mpsc::channel::<Vec<f64>>();
+  let src = &mut src;
+  std::thread::scope(|s| {
+    s.spawn(move || loop {
+      let mut buf = empty_rx.try_recv().unwrap_or_default();
+      buf.resize(BLOCK_FRAMES * channels, 0.0);
+      let r = src.read_to_slice(&mut buf).map(|n| {
+        buf.truncate(n * channels);
+        buf
+      });
+      let last = !matches!(&r, Ok(x) if !x.is_empty());
+      // The encoder stops receiving if it fails.
+      if full_tx.send(r).is_err() || last {
+        break;
+      }
+    });
+    let mut copied = 0;
+    for block in full_rx {
+      let buf: Vec<f64> = block?;
+      if buf.is_empty() {
+        break;
+      }
+      dst.write_from_slice(&buf)?;
+      copied += (buf.len() / channels) as u64;
+      let _ = empty_tx.send(buf);
+    }
+    Ok(copied)
+  })
+}
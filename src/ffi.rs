@@ -0,0 +1,163 @@
+//! libsndfile functions used by this crate.
+//!
+//! They are linked at build time through `sndfile-sys`, or loaded at runtime with the
+//! `runtime_linking` feature, in which case `load_library` reports whether libsndfile is present.
+//! Every public function calling into libsndfile without an open `SndFile` checks it first.
+
+use super::metadata::{SfChunkInfo, SfChunkIterator};
+#[cfg(not(feature = "system_linking"))]
+use super::sys as sndfile_sys;
+use super::SndFileError;
+use sndfile_sys::{sf_count_t, SF_INFO, SF_VIRTUAL_IO, SNDFILE};
+use std::os::raw::{c_char, c_double, c_int, c_void};
+
+macro_rules! sndfile_api {
+  ($($(#[$m:meta])* fn $name:ident($($arg:ident: $t:ty),*) -> $ret:ty;)*) => {
+    #[cfg(not(feature = "runtime_linking"))]
+    extern "C" {
+      $($(#[$m])* pub(crate) fn $name($($arg: $t),*) -> $ret;)*
+    }
+
+    /// Function pointers of the loaded library.
+    #[cfg(feature = "runtime_linking")]
+    struct Api {
+      $($(#[$m])* $name: unsafe extern "C" fn($($t),*) -> $ret,)*
+      _lib: libloading::Library,
+    }
+
+    #[cfg(feature = "runtime_linking")]
+    impl Api {
+      unsafe fn load(lib: libloading::Library) -> Result<Api, libloading::Error> {
+        Ok(Api {
+          $($(#[$m])* $name: *lib.get(concat!(stringify!($name), "\0").as_bytes())?,)*
+          _lib: lib,
+        })
+      }
+    }
+
+    $(
+      $(#[$m])*
+      #[cfg(feature = "runtime_linking")]
+      pub(crate) unsafe fn $name($($arg: $t),*) -> $ret {
+        (api().$name)($($arg),*)
+      }
+    )*
+  };
+}
+
+sndfile_api! {
+  fn sf_open_virtual(
+    sfvirtual: *mut SF_VIRTUAL_IO,
+    mode: c_int,
+    sfinfo: *mut SF_INFO,
+    user_data: *mut c_void
+  ) -> *mut SNDFILE;
+  #[cfg(windows)]
+  fn sf_wchar_open(wpath: *const u16, mode: c_int, sfinfo: *mut SF_INFO) -> *mut SNDFILE;
+  fn sf_close(sndfile: *mut SNDFILE) -> c_int;
+  fn sf_error(sndfile: *mut SNDFILE) -> c_int;
+  fn sf_strerror(sndfile: *mut SNDFILE) -> *const c_char;
+  fn sf_error_number(errnum: c_int) -> *const c_char;
+  fn sf_version_string() -> *const c_char;
+  fn sf_command(sndfile: *mut SNDFILE, command: c_int, data: *mut c_void, datasize: c_int) -> c_int;
+  fn sf_format_check(info: *const SF_INFO) -> c_int;
+  fn sf_seek(sndfile: *mut SNDFILE, frames: sf_count_t, whence: c_int) -> sf_count_t;
+  fn sf_set_string(sndfile: *mut SNDFILE, str_type: c_int, str: *const c_char) -> c_int;
+  fn sf_get_string(sndfile: *mut SNDFILE, str_type: c_int) -> *const c_char;
+  fn sf_read_raw(sndfile: *mut SNDFILE, ptr: *mut c_void, bytes: sf_count_t) -> sf_count_t;
+  fn sf_write_raw(sndfile: *mut SNDFILE, ptr: *const c_void, bytes: sf_count_t) -> sf_count_t;
+  fn sf_readf_short(sndfile: *mut SNDFILE, ptr: *mut i16, frames: sf_count_t) -> sf_count_t;
+  fn sf_writef_short(sndfile: *mut SNDFILE, ptr: *const i16, frames: sf_count_t) -> sf_count_t;
+  fn sf_readf_int(sndfile: *mut SNDFILE, ptr: *mut c_int, frames: sf_count_t) -> sf_count_t;
+  fn sf_writef_int(sndfile: *mut SNDFILE, ptr: *const c_int, frames: sf_count_t) -> sf_count_t;
+  fn sf_readf_float(sndfile: *mut SNDFILE, ptr: *mut f32, frames: sf_count_t) -> sf_count_t;
+  fn sf_writef_float(sndfile: *mut SNDFILE, ptr: *const f32, frames: sf_count_t) -> sf_count_t;
+  fn sf_readf_double(sndfile: *mut SNDFILE, ptr: *mut c_double, frames: sf_count_t) -> sf_count_t;
+  fn sf_writef_double(
+    sndfile: *mut SNDFILE,
+    ptr: *const c_double,
+    frames: sf_count_t
+  ) -> sf_count_t;
+  fn sf_read_short(sndfile: *mut SNDFILE, ptr: *mut i16, items: sf_count_t) -> sf_count_t;
+  fn sf_write_short(sndfile: *mut SNDFILE, ptr: *const i16, items: sf_count_t) -> sf_count_t;
+  fn sf_read_int(sndfile: *mut SNDFILE, ptr: *mut c_int, items: sf_count_t) -> sf_count_t;
+  fn sf_write_int(sndfile: *mut SNDFILE, ptr: *const c_int, items: sf_count_t) -> sf_count_t;
+  fn sf_read_float(sndfile: *mut SNDFILE, ptr: *mut f32, items: sf_count_t) -> sf_count_t;
+  fn sf_write_float(sndfile: *mut SNDFILE, ptr: *const f32, items: sf_count_t) -> sf_count_t;
+  fn sf_read_double(sndfile: *mut SNDFILE, ptr: *mut c_double, items: sf_count_t) -> sf_count_t;
+  fn sf_write_double(sndfile: *mut SNDFILE, ptr: *const c_double, items: sf_count_t) -> sf_count_t;
+  fn sf_set_chunk(sndfile: *mut SNDFILE, chunk_info: *const SfChunkInfo) -> c_int;
+  fn sf_get_chunk_iterator(
+    sndfile: *mut SNDFILE,
+    chunk_info: *const SfChunkInfo
+  ) -> *mut SfChunkIterator;
+  fn sf_next_chunk_iterator(iterator: *mut SfChunkIterator) -> *mut SfChunkIterator;
+  fn sf_get_chunk_size(iterator: *const SfChunkIterator, chunk_info: *mut SfChunkInfo) -> c_int;
+  fn sf_get_chunk_data(iterator: *const SfChunkIterator, chunk_info: *mut SfChunkInfo) -> c_int;
+}
+
+/// File names tried in order when loading libsndfile at runtime.
+#[cfg(feature = "runtime_linking")]
+const LIBRARY_NAMES: &[&str] = if cfg!(windows) {
+  &["sndfile.dll", "libsndfile-1.dll", "libsndfile.dll"]
+} else if cfg!(target_os = "macos") {
+  &["libsndfile.1.dylib", "libsndfile.dylib"]
+} else {
+  &["libsndfile.so.1", "libsndfile.so"]
+};
+
+#[cfg(feature = "runtime_linking")]
+static API: std::sync::OnceLock<Result<Api, String>> = std::sync::OnceLock::new();
+
+#[cfg(feature = "runtime_linking")]
+fn load_api() -> &'static Result<Api, String> {
+  API.get_or_init(|| {
+    let names = match std::env::var_os("SNDFILE_LIBRARY_PATH") {
+      Some(x) => vec![x],
+      None => LIBRARY_NAMES.iter().map(Into::into).collect(),
+    };
+    let mut errors = Vec::new();
+    for name in names {
+      match unsafe { libloading::Library::new(&name).and_then(|lib| Api::load(lib)) } {
+        Ok(api) => return Ok(api),
+        Err(e) => errors.push(format!("`{}`: {}", name.to_string_lossy(), e)),
+      }
+    }
+    Err(format!(
+      "Failed to load libsndfile, tried {}.",
+      errors.join(", ")
+    ))
+  })
+}
+
+/// The loaded library.
+///
+/// Every entry point calling into libsndfile without an open `SndFile` checks `load_library`
+/// first and returns `SndFileError::LibraryNotFound`, e.g. `OpenOptions` or `library_version`, so
+/// this is only reached once the library is loaded.
+#[cfg(feature = "runtime_linking")]
+fn api() -> &'static Api {
+  match load_api() {
+    Ok(x) => x,
+    Err(e) => panic!("{}", e),
+  }
+}
+
+/// Make sure libsndfile can be used, returns `SndFileError::LibraryNotFound` otherwise.
+///
+/// With the `runtime_linking` feature, libsndfile is loaded on first use from the path in the
+/// `SNDFILE_LIBRARY_PATH` environment variable, or from the usual library names of the platform.
+/// Call this first to enable audio features only when the library is present. If it can't be
+/// loaded:
+/// * opening files, `WriteOptions::validate` and `library_version` fail with
+///   `SndFileError::LibraryNotFound`,
+/// * `check_format`, `supports` and the supported format dicts report no supported format.
+///
+/// Without the feature, libsndfile is linked at build time and this always succeeds.
+pub fn load_library() -> Result<(), SndFileError> {
+  #[cfg(feature = "runtime_linking")]
+  if let Err(e) = load_api() {
+    return Err(SndFileError::LibraryNotFound(e.clone()));
+  }
+  Ok(())
+}
@@ -1,5 +1,11 @@
+use super::ffi::{self, load_library};
+use super::stats::Sample;
+#[cfg(not(feature = "system_linking"))]
+use super::sys as sndfile_sys;
+use super::SndFileError;
 use std::collections::HashMap;
 use std::os::raw::{c_int, c_void};
+use std::sync::OnceLock;
 
 #[derive(Debug)]
 pub struct MajorInfo {
@@ -12,104 +18,103 @@ pub struct SubtypeInfo {
   pub name: String,
 }
 
-lazy_static! {
-  static ref MAJOR_FORMAT_LIST: HashMap<MajorFormat, MajorInfo> = {
-    let mut n: c_int = 0;
+/// Query all container formats supported by the linked libsndfile.
+fn load_major_formats() -> HashMap<MajorFormat, MajorInfo> {
+  if load_library().is_err() {
+    return HashMap::new();
+  }
+  let mut n: c_int = 0;
+  unsafe {
+    ffi::sf_command(
+      std::ptr::null_mut(),
+      sndfile_sys::SFC_GET_FORMAT_MAJOR_COUNT,
+      &mut n as *mut c_int as *mut c_void,
+      std::mem::size_of::<c_int>() as c_int,
+    )
+  };
+  assert!(n >= 0);
+
+  let mut fmt_info = sndfile_sys::SF_FORMAT_INFO {
+    format: 0,
+    name: std::ptr::null(),
+    extension: std::ptr::null(),
+  };
+  let mut out = HashMap::new();
+  for i in 0..n {
+    fmt_info.format = i;
     unsafe {
-      sndfile_sys::sf_command(
+      ffi::sf_command(
         std::ptr::null_mut(),
-        sndfile_sys::SFC_GET_FORMAT_MAJOR_COUNT,
-        &mut n as *mut c_int as *mut c_void,
-        std::mem::size_of::<c_int>() as c_int,
+        sndfile_sys::SFC_GET_FORMAT_MAJOR,
+        &mut fmt_info as *mut sndfile_sys::SF_FORMAT_INFO as *mut c_void,
+        std::mem::size_of::<sndfile_sys::SF_FORMAT_INFO>() as c_int,
       )
     };
-    assert!(n >= 0);
+    let name = unsafe { std::ffi::CStr::from_ptr(fmt_info.name) }
+      .to_str()
+      .unwrap();
+    let extension = unsafe { std::ffi::CStr::from_ptr(fmt_info.extension) }
+      .to_str()
+      .unwrap();
+    out.insert(
+      flags_to_major_format(fmt_info.format),
+      MajorInfo {
+        name: name.to_string(),
+        extension: extension.to_string(),
+      },
+    );
+  }
+  out
+}
 
-    let mut fmt_info = sndfile_sys::SF_FORMAT_INFO {
-      format: 0,
-      name: std::ptr::null(),
-      extension: std::ptr::null(),
-    };
-    let mut out = HashMap::new();
-    for i in 0..n {
-      fmt_info.format = i;
-      unsafe {
-        sndfile_sys::sf_command(
-          std::ptr::null_mut(),
-          sndfile_sys::SFC_GET_FORMAT_MAJOR,
-          &mut fmt_info as *mut sndfile_sys::SF_FORMAT_INFO as *mut c_void,
-          std::mem::size_of::<sndfile_sys::SF_FORMAT_INFO>() as c_int,
-        )
-      };
-      match flags_to_major_format(fmt_info.format) {
-        Some(major_format) => {
-          let name = unsafe { std::ffi::CStr::from_ptr(fmt_info.name) }
-            .to_str()
-            .unwrap();
-          let extension = unsafe { std::ffi::CStr::from_ptr(fmt_info.extension) }
-            .to_str()
-            .unwrap();
-          out.insert(
-            major_format,
-            MajorInfo {
-              name: name.to_string(),
-              extension: extension.to_string(),
-            },
-          );
-        }
-        _ => {}
-      }
-    }
-    out
+/// Query all encoding formats supported by the linked libsndfile.
+fn load_subtype_formats() -> HashMap<SubtypeFormat, SubtypeInfo> {
+  if load_library().is_err() {
+    return HashMap::new();
+  }
+  let mut n: c_int = 0;
+  unsafe {
+    ffi::sf_command(
+      std::ptr::null_mut(),
+      sndfile_sys::SFC_GET_FORMAT_SUBTYPE_COUNT,
+      &mut n as *mut c_int as *mut c_void,
+      std::mem::size_of::<c_int>() as c_int,
+    )
   };
-  static ref SUBTYPE_FORMAT_LIST: HashMap<SubtypeFormat, SubtypeInfo> = {
-    let mut n: c_int = 0;
+  assert!(n >= 0);
+
+  let mut fmt_info = sndfile_sys::SF_FORMAT_INFO {
+    format: 0,
+    name: std::ptr::null(),
+    extension: std::ptr::null(),
+  };
+  let mut out = HashMap::new();
+  for i in 0..n {
+    fmt_info.format = i;
     unsafe {
-      sndfile_sys::sf_command(
+      ffi::sf_command(
         std::ptr::null_mut(),
-        sndfile_sys::SFC_GET_FORMAT_SUBTYPE_COUNT,
-        &mut n as *mut c_int as *mut c_void,
-        std::mem::size_of::<c_int>() as c_int,
+        sndfile_sys::SFC_GET_FORMAT_SUBTYPE,
+        &mut fmt_info as *mut sndfile_sys::SF_FORMAT_INFO as *mut c_void,
+        std::mem::size_of::<sndfile_sys::SF_FORMAT_INFO>() as c_int,
       )
     };
-    assert!(n >= 0);
-
-    let mut fmt_info = sndfile_sys::SF_FORMAT_INFO {
-      format: 0,
-      name: std::ptr::null(),
-      extension: std::ptr::null(),
-    };
-    let mut out = HashMap::new();
-    for i in 0..n {
-      fmt_info.format = i;
-      unsafe {
-        sndfile_sys::sf_command(
-          std::ptr::null_mut(),
-          sndfile_sys::SFC_GET_FORMAT_SUBTYPE,
-          &mut fmt_info as *mut sndfile_sys::SF_FORMAT_INFO as *mut c_void,
-          std::mem::size_of::<sndfile_sys::SF_FORMAT_INFO>() as c_int,
-        )
-      };
-      match flags_to_subtype_format(fmt_info.format) {
-        Some(subtype_format) => {
-          let name = unsafe { std::ffi::CStr::from_ptr(fmt_info.name) }
-            .to_str()
-            .unwrap();
-          out.insert(
-            subtype_format,
-            SubtypeInfo {
-              name: name.to_string(),
-            },
-          );
-        }
-        _ => {}
-      }
-    }
-    out
-  };
+    let name = unsafe { std::ffi::CStr::from_ptr(fmt_info.name) }
+      .to_str()
+      .unwrap();
+    out.insert(
+      flags_to_subtype_format(fmt_info.format),
+      SubtypeInfo {
+        name: name.to_string(),
+      },
+    );
+  }
+  out
 }
 
+#[non_exhaustive]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde_features", derive(serde::Serialize))]
 pub enum MajorFormat {
   WAV,
   AIFF,
@@ -136,10 +141,14 @@ pub enum MajorFormat {
   OGG,
   MPC2K,
   RF64,
+  /// A container format unknown to this crate, holds the raw libsndfile format flags.
+  Unknown(i32),
 }
 
 #[allow(non_camel_case_types)]
+#[non_exhaustive]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde_features", derive(serde::Serialize))]
 pub enum SubtypeFormat {
   PCM_S8,
   PCM_16,
@@ -168,9 +177,27 @@ pub enum SubtypeFormat {
   ALAC_20,
   ALAC_24,
   ALAC_32,
+  /// An encoding format unknown to this crate, holds the raw libsndfile format flags.
+  Unknown(i32),
+}
+
+/// Class of a combination of container and encoding formats, e.g. to badge files in a library or
+/// to refuse transcoding from lossy to lossy, see `FormatClass::of`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde_features", derive(serde::Serialize))]
+pub enum FormatClass {
+  /// Integer or floating point samples stored as is, e.g. WAV of `PCM_16`.
+  Uncompressed,
+  /// Compressed without loss, e.g. FLAC or ALAC.
+  Lossless,
+  /// Compressed with loss, e.g. Vorbis, Opus, MP3 or ADPCM.
+  Lossy,
+  /// An encoding format unknown to this crate.
+  Unknown,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde_features", derive(serde::Serialize))]
 pub enum Endian {
   File,
   Little,
@@ -178,7 +205,12 @@ pub enum Endian {
   CPU,
 }
 
-pub fn flags_to_major_format(flags: c_int) -> Option<MajorFormat> {
+pub fn flags_to_major_format(flags: c_int) -> MajorFormat {
+  major_format_from_known_flags(flags)
+    .unwrap_or_else(|| MajorFormat::Unknown(flags & sndfile_sys::SF_FORMAT_TYPEMASK))
+}
+
+fn major_format_from_known_flags(flags: c_int) -> Option<MajorFormat> {
   match flags & sndfile_sys::SF_FORMAT_TYPEMASK {
     sndfile_sys::SF_FORMAT_WAV => Some(MajorFormat::WAV),
     sndfile_sys::SF_FORMAT_AIFF => Some(MajorFormat::AIFF),
@@ -209,7 +241,12 @@ pub fn flags_to_major_format(flags: c_int) -> Option<MajorFormat> {
   }
 }
 
-pub fn flags_to_subtype_format(flags: c_int) -> Option<SubtypeFormat> {
+pub fn flags_to_subtype_format(flags: c_int) -> SubtypeFormat {
+  subtype_format_from_known_flags(flags)
+    .unwrap_or_else(|| SubtypeFormat::Unknown(flags & sndfile_sys::SF_FORMAT_SUBMASK))
+}
+
+fn subtype_format_from_known_flags(flags: c_int) -> Option<SubtypeFormat> {
   match flags & sndfile_sys::SF_FORMAT_SUBMASK {
     sndfile_sys::SF_FORMAT_PCM_S8 => Some(SubtypeFormat::PCM_S8),
     sndfile_sys::SF_FORMAT_PCM_16 => Some(SubtypeFormat::PCM_16),
@@ -279,6 +316,7 @@ pub fn major_format_to_flags(major_format: MajorFormat) -> c_int {
     MajorFormat::OGG => sndfile_sys::SF_FORMAT_OGG,
     MajorFormat::MPC2K => sndfile_sys::SF_FORMAT_MPC2K,
     MajorFormat::RF64 => sndfile_sys::SF_FORMAT_RF64,
+    MajorFormat::Unknown(x) => x & sndfile_sys::SF_FORMAT_TYPEMASK,
   }
 }
 
@@ -311,6 +349,7 @@ pub fn subtype_format_to_flags(subtype_format: SubtypeFormat) -> c_int {
     SubtypeFormat::ALAC_20 => sndfile_sys::SF_FORMAT_ALAC_20,
     SubtypeFormat::ALAC_24 => sndfile_sys::SF_FORMAT_ALAC_24,
     SubtypeFormat::ALAC_32 => sndfile_sys::SF_FORMAT_ALAC_32,
+    SubtypeFormat::Unknown(x) => x & sndfile_sys::SF_FORMAT_SUBMASK,
   }
 }
 
@@ -335,16 +374,24 @@ pub fn assembly_format_flags(
 }
 
 /// Get all supported audio container format
+///
+/// The dict is empty if libsndfile can't be loaded, see `load_library`.
 pub fn get_supported_major_format_dict() -> &'static HashMap<MajorFormat, MajorInfo> {
-  &*MAJOR_FORMAT_LIST
+  static MAJOR_FORMAT_LIST: OnceLock<HashMap<MajorFormat, MajorInfo>> = OnceLock::new();
+  MAJOR_FORMAT_LIST.get_or_init(load_major_formats)
 }
 
 /// Get all supported audio encoding format
+///
+/// The dict is empty if libsndfile can't be loaded, see `load_library`.
 pub fn get_supported_subtype_format_dict() -> &'static HashMap<SubtypeFormat, SubtypeInfo> {
-  &*SUBTYPE_FORMAT_LIST
+  static SUBTYPE_FORMAT_LIST: OnceLock<HashMap<SubtypeFormat, SubtypeInfo>> = OnceLock::new();
+  SUBTYPE_FORMAT_LIST.get_or_init(load_subtype_formats)
 }
 
 /// This function allows the caller to check if a set of parameters before opening a file in write mode.
+///
+/// Returns `false` if libsndfile can't be loaded, see `load_library`.
 pub fn check_format(
   channels: usize,
   samplerate: usize,
@@ -352,6 +399,9 @@ pub fn check_format(
   subtype_format: SubtypeFormat,
   endian: Endian,
 ) -> bool {
+  if load_library().is_err() {
+    return false;
+  }
   let info = sndfile_sys::SF_INFO {
     frames: 0,
     samplerate: samplerate as c_int,
@@ -360,12 +410,151 @@ pub fn check_format(
     sections: 0,
     seekable: 0,
   };
-  match unsafe { sndfile_sys::sf_format_check(&info as *const sndfile_sys::SF_INFO) } {
+  match unsafe { ffi::sf_format_check(&info as *const sndfile_sys::SF_INFO) } {
     sndfile_sys::SF_TRUE => true,
     _ => false,
   }
 }
 
+/// A format combination accepted by libsndfile, as suggested by `check_format_verbose`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FormatSuggestion {
+  pub channels: usize,
+  pub samplerate: usize,
+  pub major_format: MajorFormat,
+  pub subtype_format: SubtypeFormat,
+  pub endian: Endian,
+}
+
+/// Like `check_format`, but explains a rejected combination.
+///
+/// Returns `Ok(())` if the combination is valid. Otherwise nearby combinations are probed by changing
+/// one parameter at a time (endian first, then subtype, then channel count), and the valid ones are
+/// returned ordered from the smallest change to the largest.
+pub fn check_format_verbose(
+  channels: usize,
+  samplerate: usize,
+  major_format: MajorFormat,
+  subtype_format: SubtypeFormat,
+  endian: Endian,
+) -> Result<(), Vec<FormatSuggestion>> {
+  if check_format(channels, samplerate, major_format, subtype_format, endian) {
+    return Ok(());
+  }
+
+  let mut candidates = Vec::new();
+  for &e in [Endian::File, Endian::Little, Endian::Big, Endian::CPU].iter() {
+    if e != endian {
+      candidates.push((channels, subtype_format, e));
+    }
+  }
+  let mut subtypes: Vec<SubtypeFormat> = get_supported_subtype_format_dict()
+    .keys()
+    .copied()
+    .collect();
+  subtypes.sort_by_key(|x| subtype_format_to_flags(*x));
+  if let Some(x) = default_subtype(major_format) {
+    subtypes.retain(|y| *y != x);
+    subtypes.insert(0, x);
+  }
+  for &s in subtypes.iter() {
+    if s != subtype_format {
+      candidates.push((channels, s, endian));
+    }
+  }
+  for &c in [1, 2].iter() {
+    if c != channels {
+      candidates.push((c, subtype_format, endian));
+    }
+  }
+
+  Err(
+    candidates
+      .into_iter()
+      .filter(|&(c, s, e)| check_format(c, samplerate, major_format, s, e))
+      .map(|(c, s, e)| FormatSuggestion {
+        channels: c,
+        samplerate,
+        major_format,
+        subtype_format: s,
+        endian: e,
+      })
+      .collect(),
+  )
+}
+
+/// `SF_FORMAT_OPUS` of libsndfile 1.0.29.
+const SF_FORMAT_OPUS: c_int = 0x0064;
+
+/// Get the version string of the linked libsndfile, e.g. `libsndfile-1.2.2`.
+///
+/// With the `runtime_linking` feature, returns `SndFileError::LibraryNotFound` if libsndfile can't
+/// be loaded, see `load_library`.
+pub fn library_version() -> Result<&'static str, SndFileError> {
+  static VERSION: OnceLock<String> = OnceLock::new();
+  load_library()?;
+  Ok(VERSION.get_or_init(|| {
+    unsafe { std::ffi::CStr::from_ptr(ffi::sf_version_string()) }
+      .to_string_lossy()
+      .into_owned()
+  }))
+}
+
+/// Parse `(major, minor, patch)` of a version string like `libsndfile-1.0.31-exp`.
+fn parse_library_version(s: &str) -> Option<(u32, u32, u32)> {
+  let s = s.trim_start_matches(|c: char| !c.is_ascii_digit());
+  let mut it = s
+    .split(|c: char| !c.is_ascii_digit())
+    .map(|x| x.parse().ok());
+  Some((it.next()??, it.next()??, it.next().flatten().unwrap_or(0)))
+}
+
+/// The first libsndfile version able to write a format, for formats added after 1.0.
+fn min_library_version(
+  major_format: MajorFormat,
+  subtype_format: SubtypeFormat,
+) -> Option<(u32, u32, u32)> {
+  match (major_format, subtype_format) {
+    (MajorFormat::Unknown(SF_FORMAT_MPEG), _) => Some((1, 1, 0)),
+    // `SF_FORMAT_MPEG_LAYER_I` to `SF_FORMAT_MPEG_LAYER_III`.
+    (_, SubtypeFormat::Unknown(0x80..=0x82)) => Some((1, 1, 0)),
+    (_, SubtypeFormat::Unknown(SF_FORMAT_OPUS)) => Some((1, 0, 29)),
+    _ => None,
+  }
+}
+
+/// Check if the linked libsndfile can write `subtype_format` in `major_format`.
+///
+/// Unlike `check_format`, this also requires both formats to be compiled into the library and its
+/// version to be recent enough, e.g. MP3 (`MajorFormat::Unknown` of `SF_FORMAT_MPEG`) or Opus
+/// (`SubtypeFormat::Unknown` of `SF_FORMAT_OPUS`) are missing from builds without external
+/// libraries, so export options can be hidden instead of failing when the file is opened. Common
+/// channel counts and sample rates are tried, see `check_format` for a specific combination.
+pub fn supports(major_format: MajorFormat, subtype_format: SubtypeFormat) -> bool {
+  if !get_supported_major_format_dict().contains_key(&major_format)
+    || !get_supported_subtype_format_dict().contains_key(&subtype_format)
+  {
+    return false;
+  }
+  if let Some(min) = min_library_version(major_format, subtype_format) {
+    match library_version().ok().and_then(parse_library_version) {
+      Some(x) if x >= min => (),
+      _ => return false,
+    }
+  }
+  [48000, 44100, 8000].iter().any(|&samplerate| {
+    [1, 2].iter().any(|&channels| {
+      check_format(
+        channels,
+        samplerate,
+        major_format,
+        subtype_format,
+        Endian::File,
+      )
+    })
+  })
+}
+
 /// Returns default audio encoding format for given audio container format
 pub fn default_subtype(major_format: MajorFormat) -> Option<SubtypeFormat> {
   match major_format {
@@ -394,5 +583,531 @@ pub fn default_subtype(major_format: MajorFormat) -> Option<SubtypeFormat> {
     MajorFormat::OGG => Some(SubtypeFormat::VORBIS),
     MajorFormat::MPC2K => Some(SubtypeFormat::PCM_16),
     MajorFormat::RF64 => Some(SubtypeFormat::PCM_16),
+    MajorFormat::Unknown(_) => None,
+  }
+}
+
+/// Returns the subtype of `major_format` best preserving samples of type `T`, e.g. `FLOAT` for
+/// `f32` in WAV and `PCM_24` for `f32` in FLAC, or `None` if it has no PCM, ALAC or float subtype.
+/// Subtypes are tried in the order of `Sample::PREFERRED_SUBTYPES`.
+///
+/// Lossless subtypes are picked when the container and the linked libsndfile support one, see
+/// `supports`, otherwise the one with the highest precision.
+///
+/// ```ignore
+/// let subtype = best_subtype_for::<f32>(MajorFormat::WAV).unwrap();
+/// assert_eq!(subtype, SubtypeFormat::FLOAT);
+/// ```
+pub fn best_subtype_for<T: Sample>(major_format: MajorFormat) -> Option<SubtypeFormat> {
+  T::PREFERRED_SUBTYPES
+    .iter()
+    .copied()
+    .find(|&x| supports(major_format, x))
+}
+
+/// `SF_FORMAT_MPEG` of libsndfile 1.1, MPEG files are reported as `MajorFormat::Unknown` of it.
+pub(crate) const SF_FORMAT_MPEG: c_int = 0x23_0000;
+
+/// Guess the audio container format from the leading bytes of a file, without opening it.
+///
+/// `header` should hold at least the first 64 bytes of the file. An ID3v2 tag is skipped if
+/// `header` is long enough to hold it, MPEG audio is reported as `MajorFormat::Unknown` of the
+/// libsndfile flag `SF_FORMAT_MPEG`. Returns `None` if no known signature is found, which does not
+/// prove that libsndfile can't open it: some formats like `RAW` have no signature.
+pub fn detect_format(header: &[u8]) -> Option<MajorFormat> {
+  let tag = |offset: usize, magic: &[u8]| header.get(offset..offset + magic.len()) == Some(magic);
+  if tag(0, b"ID3") && header.len() >= 10 {
+    let size = header[6..10]
+      .iter()
+      .fold(0usize, |acc, &x| (acc << 7) | (x & 0x7f) as usize);
+    let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+    return match header.get(10 + size + footer..) {
+      Some(rest) if !rest.is_empty() => {
+        detect_format(rest).or(Some(MajorFormat::Unknown(SF_FORMAT_MPEG)))
+      }
+      _ => Some(MajorFormat::Unknown(SF_FORMAT_MPEG)),
+    };
+  }
+  if (tag(0, b"RIFF") || tag(0, b"RIFX")) && tag(8, b"WAVE") {
+    // WAVE_FORMAT_EXTENSIBLE in a leading `fmt ` chunk.
+    if tag(12, b"fmt ") && tag(20, &[0xfe, 0xff]) {
+      return Some(MajorFormat::WAVEX);
+    }
+    return Some(MajorFormat::WAV);
+  }
+  if (tag(0, b"RF64") || tag(0, b"BW64")) && tag(8, b"WAVE") {
+    return Some(MajorFormat::RF64);
+  }
+  if tag(0, b"riff") && tag(4, &[0x2e, 0x91, 0xcf, 0x11]) {
+    return Some(MajorFormat::W64);
+  }
+  if tag(0, b"FORM") {
+    if tag(8, b"AIFF") || tag(8, b"AIFC") {
+      return Some(MajorFormat::AIFF);
+    }
+    if tag(8, b"8SVX") || tag(8, b"16SV") {
+      return Some(MajorFormat::SVX);
+    }
+  }
+  let ircam = [
+    [0x64, 0xa3, 0x01, 0x00],
+    [0x64, 0xa3, 0x02, 0x00],
+    [0x64, 0xa3, 0x03, 0x00],
+    [0x64, 0xa3, 0x04, 0x00],
+    [0x00, 0x01, 0xa3, 0x64],
+    [0x00, 0x02, 0xa3, 0x64],
+    [0x00, 0x03, 0xa3, 0x64],
+    [0x00, 0x04, 0xa3, 0x64],
+  ];
+  if ircam.iter().any(|x| tag(0, x)) {
+    return Some(MajorFormat::IRCAM);
+  }
+  let signatures: [(&[u8], MajorFormat); 12] = [
+    (b"fLaC", MajorFormat::FLAC),
+    (b"OggS", MajorFormat::OGG),
+    (b"caff", MajorFormat::CAF),
+    (b".snd", MajorFormat::AU),
+    (b"dns.", MajorFormat::AU),
+    (b"NIST_1A", MajorFormat::NIST),
+    (b"Creative Voice File", MajorFormat::VOC),
+    (b" paf", MajorFormat::PAF),
+    (b"fap ", MajorFormat::PAF),
+    (b"2BIT", MajorFormat::AVR),
+    (b"PVF1\n", MajorFormat::PVF),
+    (b"Extended Instrument: ", MajorFormat::XI),
+  ];
+  if let Some((_, x)) = signatures.iter().find(|(magic, _)| tag(0, magic)) {
+    return Some(*x);
+  }
+  if tag(0, b"MATLAB 5.0 MAT-file") {
+    return Some(MajorFormat::MAT5);
+  }
+  if tag(0, b"ALawSoundFile**") {
+    return Some(MajorFormat::WVE);
+  }
+  // MPEG audio frame sync without ID3 tag.
+  if header.len() >= 2 && header[0] == 0xff && header[1] & 0xe0 == 0xe0 {
+    return Some(MajorFormat::Unknown(SF_FORMAT_MPEG));
+  }
+  None
+}
+
+/// Common file extensions of container formats, in addition to those of libsndfile format
+/// dictionaries. Formats sharing an extension are listed by priority for
+/// `MajorFormat::from_extension`.
+const EXTENSION_ALIASES: [(MajorFormat, &[&str]); 13] = [
+  (MajorFormat::WAV, &["wav", "wave"]),
+  (MajorFormat::AIFF, &["aiff", "aif", "aifc"]),
+  (MajorFormat::AU, &["au", "snd"]),
+  (MajorFormat::RAW, &["raw", "pcm"]),
+  (MajorFormat::FLAC, &["flac"]),
+  (MajorFormat::OGG, &["ogg", "oga", "opus"]),
+  (MajorFormat::CAF, &["caf"]),
+  (MajorFormat::W64, &["w64"]),
+  (MajorFormat::RF64, &["rf64"]),
+  (MajorFormat::SVX, &["iff", "svx", "8svx"]),
+  (MajorFormat::NIST, &["nist", "sph"]),
+  (MajorFormat::MAT5, &["mat"]),
+  (MajorFormat::IRCAM, &["sf", "ircam"]),
+];
+
+impl MajorFormat {
+  /// Returns the file extension given by libsndfile for this container format, e.g. `"flac"`.
+  ///
+  /// Returns `None` if the format is not supported by the linked libsndfile.
+  pub fn canonical_extension(self) -> Option<&'static str> {
+    get_supported_major_format_dict()
+      .get(&self)
+      .map(|x| x.extension.as_str())
+  }
+
+  /// Returns all known file extensions of this container format, starting with
+  /// `canonical_extension`.
+  pub fn extensions(self) -> Vec<&'static str> {
+    let mut out: Vec<&'static str> = self.canonical_extension().into_iter().collect();
+    for (_, exts) in EXTENSION_ALIASES.iter().filter(|(x, _)| *x == self) {
+      for ext in exts.iter() {
+        if !out.contains(ext) {
+          out.push(ext);
+        }
+      }
+    }
+    out
+  }
+
+  /// Returns the container format of a file extension, e.g. `"flac"` or `".WAV"`.
+  ///
+  /// The comparison is case-insensitive. If several formats share an extension, e.g. `"wav"` for
+  /// `WAV`, `WAVEX` and `NIST`, the most common one is returned.
+  pub fn from_extension(ext: &str) -> Option<MajorFormat> {
+    let ext = ext.trim_start_matches('.').to_ascii_lowercase();
+    if let Some((x, _)) = EXTENSION_ALIASES
+      .iter()
+      .find(|(_, exts)| exts.contains(&ext.as_str()))
+    {
+      return Some(*x);
+    }
+    get_supported_major_format_dict()
+      .iter()
+      .filter(|(_, info)| info.extension.eq_ignore_ascii_case(&ext))
+      .map(|(x, _)| *x)
+      .min_by_key(|x| major_format_to_flags(*x))
+  }
+
+  /// Returns `true` for containers of compressed encodings only, i.e. FLAC, OGG and MPEG.
+  pub fn is_compressed(self) -> bool {
+    matches!(
+      self,
+      MajorFormat::FLAC | MajorFormat::OGG | MajorFormat::Unknown(SF_FORMAT_MPEG)
+    )
+  }
+
+  /// Returns the MIME type commonly used for this container format, if any.
+  pub fn mime_type(self) -> Option<&'static str> {
+    match self {
+      MajorFormat::WAV | MajorFormat::WAVEX | MajorFormat::RF64 => Some("audio/wav"),
+      MajorFormat::AIFF => Some("audio/aiff"),
+      MajorFormat::AU => Some("audio/basic"),
+      MajorFormat::FLAC => Some("audio/flac"),
+      MajorFormat::OGG => Some("audio/ogg"),
+      MajorFormat::CAF => Some("audio/x-caf"),
+      MajorFormat::W64 => Some("audio/x-w64"),
+      MajorFormat::VOC => Some("audio/x-voc"),
+      MajorFormat::Unknown(SF_FORMAT_MPEG) => Some("audio/mpeg"),
+      _ => None,
+    }
+  }
+
+  /// Returns the container format of a MIME type, e.g. `"audio/x-wav"` gives `MajorFormat::WAV`.
+  ///
+  /// MIME parameters like `; codecs=opus` are ignored, and the comparison is case-insensitive.
+  pub fn from_mime_type(mime: &str) -> Option<MajorFormat> {
+    let essence = mime
+      .split(';')
+      .next()
+      .unwrap_or("")
+      .trim()
+      .to_ascii_lowercase();
+    match essence.as_str() {
+      "audio/wav" | "audio/wave" | "audio/x-wav" | "audio/vnd.wave" => Some(MajorFormat::WAV),
+      "audio/aiff" | "audio/x-aiff" => Some(MajorFormat::AIFF),
+      "audio/basic" | "audio/au" => Some(MajorFormat::AU),
+      "audio/flac" | "audio/x-flac" => Some(MajorFormat::FLAC),
+      "audio/ogg" | "audio/vorbis" | "audio/opus" | "application/ogg" => Some(MajorFormat::OGG),
+      "audio/x-caf" => Some(MajorFormat::CAF),
+      "audio/x-w64" => Some(MajorFormat::W64),
+      "audio/x-voc" => Some(MajorFormat::VOC),
+      "audio/mpeg" | "audio/mp3" => Some(MajorFormat::Unknown(SF_FORMAT_MPEG)),
+      _ => None,
+    }
+  }
+}
+
+impl SubtypeFormat {
+  /// Returns the number of bits used to code each sample, e.g. `24` for `PCM_24` and `4` for
+  /// `IMA_ADPCM`, or `None` if it is not fixed, e.g. for `VORBIS`.
+  pub fn bit_depth(self) -> Option<u32> {
+    match self {
+      SubtypeFormat::PCM_S8 | SubtypeFormat::PCM_U8 => Some(8),
+      SubtypeFormat::PCM_16 => Some(16),
+      SubtypeFormat::PCM_24 => Some(24),
+      SubtypeFormat::PCM_32 => Some(32),
+      SubtypeFormat::FLOAT => Some(32),
+      SubtypeFormat::DOUBLE => Some(64),
+      SubtypeFormat::ULAW | SubtypeFormat::ALAW => Some(8),
+      SubtypeFormat::IMA_ADPCM | SubtypeFormat::MS_ADPCM | SubtypeFormat::VOX_ADPCM => Some(4),
+      SubtypeFormat::G721_32 => Some(4),
+      SubtypeFormat::G723_24 => Some(3),
+      SubtypeFormat::G723_40 => Some(5),
+      SubtypeFormat::DWVW_12 => Some(12),
+      SubtypeFormat::DWVW_16 => Some(16),
+      SubtypeFormat::DWVW_24 => Some(24),
+      SubtypeFormat::DPCM_8 => Some(8),
+      SubtypeFormat::DPCM_16 => Some(16),
+      SubtypeFormat::ALAC_16 => Some(16),
+      SubtypeFormat::ALAC_20 => Some(20),
+      SubtypeFormat::ALAC_24 => Some(24),
+      SubtypeFormat::ALAC_32 => Some(32),
+      SubtypeFormat::GSM610
+      | SubtypeFormat::DWVW_N
+      | SubtypeFormat::VORBIS
+      | SubtypeFormat::Unknown(_) => None,
+    }
+  }
+
+  /// Returns `true` for floating point encodings, i.e. `FLOAT` and `DOUBLE`.
+  pub fn is_float(self) -> bool {
+    matches!(self, SubtypeFormat::FLOAT | SubtypeFormat::DOUBLE)
+  }
+
+  /// Returns `true` for ADPCM encodings, including G721 and G723.
+  pub fn is_adpcm(self) -> bool {
+    matches!(
+      self,
+      SubtypeFormat::IMA_ADPCM
+        | SubtypeFormat::MS_ADPCM
+        | SubtypeFormat::VOX_ADPCM
+        | SubtypeFormat::G721_32
+        | SubtypeFormat::G723_24
+        | SubtypeFormat::G723_40
+    )
+  }
+
+  /// Returns `true` if encoding samples of the same bit depth loses information, i.e. for
+  /// A-law, u-law, ADPCM, GSM 6.10 and Vorbis.
+  ///
+  /// Returns `false` for `Unknown` encodings.
+  pub fn is_lossy(self) -> bool {
+    self.is_adpcm()
+      || matches!(
+        self,
+        SubtypeFormat::ULAW | SubtypeFormat::ALAW | SubtypeFormat::GSM610 | SubtypeFormat::VORBIS
+      )
+  }
+
+  /// Returns `true` if samples of the same bit depth are decoded exactly as encoded, i.e. for
+  /// PCM, floating point, DWVW, DPCM and ALAC.
+  ///
+  /// Returns `false` for `Unknown` encodings.
+  pub fn is_lossless(self) -> bool {
+    !self.is_lossy() && !matches!(self, SubtypeFormat::Unknown(_))
+  }
+}
+
+impl FormatClass {
+  /// Classify `subtype_format` in `major_format`, e.g. `Lossless` for `PCM_16` in FLAC.
+  ///
+  /// Opus and MPEG encodings, reported as `SubtypeFormat::Unknown` of their libsndfile flags, are
+  /// `Lossy`.
+  pub fn of(major_format: MajorFormat, subtype_format: SubtypeFormat) -> FormatClass {
+    match subtype_format {
+      // `SF_FORMAT_MPEG_LAYER_I` to `SF_FORMAT_MPEG_LAYER_III`.
+      SubtypeFormat::Unknown(SF_FORMAT_OPUS) | SubtypeFormat::Unknown(0x80..=0x82) => {
+        FormatClass::Lossy
+      }
+      _ if major_format == MajorFormat::Unknown(SF_FORMAT_MPEG) => FormatClass::Lossy,
+      SubtypeFormat::Unknown(_) => FormatClass::Unknown,
+      x if x.is_lossy() => FormatClass::Lossy,
+      SubtypeFormat::DWVW_12
+      | SubtypeFormat::DWVW_16
+      | SubtypeFormat::DWVW_24
+      | SubtypeFormat::DWVW_N
+      | SubtypeFormat::ALAC_16
+      | SubtypeFormat::ALAC_20
+      | SubtypeFormat::ALAC_24
+      | SubtypeFormat::ALAC_32 => FormatClass::Lossless,
+      _ if major_format.is_compressed() => FormatClass::Lossless,
+      _ => FormatClass::Uncompressed,
+    }
+  }
+
+  /// Returns `true` for `Lossy`.
+  pub fn is_lossy(self) -> bool {
+    self == FormatClass::Lossy
+  }
+}
+
+/// Uses the name given by libsndfile, e.g. `WAV (Microsoft)`, or a built-in name if the format is
+/// not supported by the linked libsndfile.
+impl std::fmt::Display for MajorFormat {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if let Some(x) = get_supported_major_format_dict().get(self) {
+      return f.write_str(&x.name);
+    }
+    let name = match self {
+      MajorFormat::WAV => "WAV (Microsoft)",
+      MajorFormat::AIFF => "AIFF (Apple/SGI)",
+      MajorFormat::AU => "AU (Sun/NeXT)",
+      MajorFormat::RAW => "RAW (header-less)",
+      MajorFormat::PAF => "PAF (Ensoniq PARIS)",
+      MajorFormat::SVX => "IFF (Amiga IFF/SVX8/SV16)",
+      MajorFormat::NIST => "WAV (NIST Sphere)",
+      MajorFormat::VOC => "VOC (Creative Labs)",
+      MajorFormat::IRCAM => "SF (Berkeley/IRCAM/CARL)",
+      MajorFormat::W64 => "W64 (SoundFoundry WAVE 64)",
+      MajorFormat::MAT4 => "MAT4 (GNU Octave 2.0 / Matlab 4.2)",
+      MajorFormat::MAT5 => "MAT5 (GNU Octave 2.1 / Matlab 5.0)",
+      MajorFormat::PVF => "PVF (Portable Voice Format)",
+      MajorFormat::XI => "XI (FastTracker 2)",
+      MajorFormat::HTK => "HTK (HMM Tool Kit)",
+      MajorFormat::SDS => "SDS (Midi Sample Dump Standard)",
+      MajorFormat::AVR => "AVR (Audio Visual Research)",
+      MajorFormat::WAVEX => "WAVEX (Microsoft)",
+      MajorFormat::SD2 => "SD2 (Sound Designer II)",
+      MajorFormat::FLAC => "FLAC (Free Lossless Audio Codec)",
+      MajorFormat::CAF => "CAF (Apple Core Audio File)",
+      MajorFormat::WVE => "WVE (Psion Series 3)",
+      MajorFormat::OGG => "OGG (OGG Container format)",
+      MajorFormat::MPC2K => "MPC (Akai MPC 2k)",
+      MajorFormat::RF64 => "RF64 (RIFF 64)",
+      MajorFormat::Unknown(SF_FORMAT_MPEG) => "MPEG-1/2 Audio",
+      MajorFormat::Unknown(x) => return write!(f, "Unknown container (0x{:08X})", x),
+    };
+    f.write_str(name)
+  }
+}
+
+/// Uses the name given by libsndfile, e.g. `Signed 24 bit PCM`, or a built-in name if the format
+/// is not supported by the linked libsndfile.
+impl std::fmt::Display for SubtypeFormat {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if let Some(x) = get_supported_subtype_format_dict().get(self) {
+      return f.write_str(&x.name);
+    }
+    let name = match self {
+      SubtypeFormat::PCM_S8 => "Signed 8 bit PCM",
+      SubtypeFormat::PCM_16 => "Signed 16 bit PCM",
+      SubtypeFormat::PCM_24 => "Signed 24 bit PCM",
+      SubtypeFormat::PCM_32 => "Signed 32 bit PCM",
+      SubtypeFormat::PCM_U8 => "Unsigned 8 bit PCM",
+      SubtypeFormat::FLOAT => "32 bit float",
+      SubtypeFormat::DOUBLE => "64 bit float",
+      SubtypeFormat::ULAW => "U-Law",
+      SubtypeFormat::ALAW => "A-Law",
+      SubtypeFormat::IMA_ADPCM => "IMA ADPCM",
+      SubtypeFormat::MS_ADPCM => "Microsoft ADPCM",
+      SubtypeFormat::GSM610 => "GSM 6.10",
+      SubtypeFormat::VOX_ADPCM => "VOX ADPCM",
+      SubtypeFormat::G721_32 => "32kbs G721 ADPCM",
+      SubtypeFormat::G723_24 => "24kbs G723 ADPCM",
+      SubtypeFormat::G723_40 => "40kbs G723 ADPCM",
+      SubtypeFormat::DWVW_12 => "12 bit DWVW",
+      SubtypeFormat::DWVW_16 => "16 bit DWVW",
+      SubtypeFormat::DWVW_24 => "24 bit DWVW",
+      SubtypeFormat::DWVW_N => "N bit DWVW",
+      SubtypeFormat::DPCM_8 => "8 bit DPCM",
+      SubtypeFormat::DPCM_16 => "16 bit DPCM",
+      SubtypeFormat::VORBIS => "Vorbis",
+      SubtypeFormat::ALAC_16 => "16 bit ALAC",
+      SubtypeFormat::ALAC_20 => "20 bit ALAC",
+      SubtypeFormat::ALAC_24 => "24 bit ALAC",
+      SubtypeFormat::ALAC_32 => "32 bit ALAC",
+      SubtypeFormat::Unknown(x) => return write!(f, "Unknown encoding (0x{:04X})", x),
+    };
+    f.write_str(name)
+  }
+}
+
+impl std::fmt::Display for Endian {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      Endian::File => "File default endian",
+      Endian::Little => "Little endian",
+      Endian::Big => "Big endian",
+      Endian::CPU => "CPU endian",
+    })
+  }
+}
+
+/// Lowercase `s` and remove characters other than ASCII letters and digits.
+fn normalize_format_name(s: &str) -> String {
+  s.chars()
+    .filter(|c| c.is_ascii_alphanumeric())
+    .map(|c| c.to_ascii_lowercase())
+    .collect()
+}
+
+const MAJOR_FORMAT_NAMES: [(MajorFormat, &str); 27] = [
+  (MajorFormat::WAV, "wav"),
+  (MajorFormat::AIFF, "aiff"),
+  (MajorFormat::AU, "au"),
+  (MajorFormat::RAW, "raw"),
+  (MajorFormat::PAF, "paf"),
+  (MajorFormat::SVX, "svx"),
+  (MajorFormat::NIST, "nist"),
+  (MajorFormat::VOC, "voc"),
+  (MajorFormat::IRCAM, "ircam"),
+  (MajorFormat::W64, "w64"),
+  (MajorFormat::MAT4, "mat4"),
+  (MajorFormat::MAT5, "mat5"),
+  (MajorFormat::PVF, "pvf"),
+  (MajorFormat::XI, "xi"),
+  (MajorFormat::HTK, "htk"),
+  (MajorFormat::SDS, "sds"),
+  (MajorFormat::AVR, "avr"),
+  (MajorFormat::WAVEX, "wavex"),
+  (MajorFormat::SD2, "sd2"),
+  (MajorFormat::FLAC, "flac"),
+  (MajorFormat::CAF, "caf"),
+  (MajorFormat::WVE, "wve"),
+  (MajorFormat::OGG, "ogg"),
+  (MajorFormat::MPC2K, "mpc2k"),
+  (MajorFormat::RF64, "rf64"),
+  (MajorFormat::Unknown(SF_FORMAT_MPEG), "mpeg"),
+  (MajorFormat::Unknown(SF_FORMAT_MPEG), "mp3"),
+];
+
+const SUBTYPE_FORMAT_NAMES: [(SubtypeFormat, &[&str]); 27] = [
+  (SubtypeFormat::PCM_S8, &["pcms8", "pcm8", "s8"]),
+  (SubtypeFormat::PCM_16, &["pcm16", "s16", "i16", "int16"]),
+  (SubtypeFormat::PCM_24, &["pcm24", "s24", "i24", "int24"]),
+  (SubtypeFormat::PCM_32, &["pcm32", "s32", "i32", "int32"]),
+  (SubtypeFormat::PCM_U8, &["pcmu8", "u8", "uint8"]),
+  (SubtypeFormat::FLOAT, &["float", "f32", "float32"]),
+  (SubtypeFormat::DOUBLE, &["double", "f64", "float64"]),
+  (SubtypeFormat::ULAW, &["ulaw", "mulaw"]),
+  (SubtypeFormat::ALAW, &["alaw"]),
+  (SubtypeFormat::IMA_ADPCM, &["imaadpcm", "ima"]),
+  (SubtypeFormat::MS_ADPCM, &["msadpcm"]),
+  (SubtypeFormat::GSM610, &["gsm610", "gsm"]),
+  (SubtypeFormat::VOX_ADPCM, &["voxadpcm", "vox"]),
+  (SubtypeFormat::G721_32, &["g72132", "g721"]),
+  (SubtypeFormat::G723_24, &["g72324"]),
+  (SubtypeFormat::G723_40, &["g72340"]),
+  (SubtypeFormat::DWVW_12, &["dwvw12"]),
+  (SubtypeFormat::DWVW_16, &["dwvw16"]),
+  (SubtypeFormat::DWVW_24, &["dwvw24"]),
+  (SubtypeFormat::DWVW_N, &["dwvwn"]),
+  (SubtypeFormat::DPCM_8, &["dpcm8"]),
+  (SubtypeFormat::DPCM_16, &["dpcm16"]),
+  (SubtypeFormat::VORBIS, &["vorbis"]),
+  (SubtypeFormat::ALAC_16, &["alac16"]),
+  (SubtypeFormat::ALAC_20, &["alac20"]),
+  (SubtypeFormat::ALAC_24, &["alac24"]),
+  (SubtypeFormat::ALAC_32, &["alac32"]),
+];
+
+/// Parse a container format name case-insensitively.
+///
+/// Accepts enum names like `"WAVEX"`, file extensions like `"aif"` and `container/encoding` pairs
+/// like `"ogg/vorbis"`, in which case the encoding part is ignored.
+impl std::str::FromStr for MajorFormat {
+  type Err = SndFileError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let name = normalize_format_name(s.split('/').next().unwrap_or(""));
+    MAJOR_FORMAT_NAMES
+      .iter()
+      .find(|(_, x)| *x == name)
+      .map(|(x, _)| *x)
+      .or_else(|| MajorFormat::from_extension(&name))
+      .ok_or_else(|| {
+        SndFileError::InvalidParameter(format!(
+          "Got `{}`, expect a container format like `wav` or `flac`.",
+          s
+        ))
+      })
+  }
+}
+
+/// Parse an encoding format name case-insensitively, ignoring `_`, `-` and spaces.
+///
+/// Accepts enum names like `"PCM_16"`, aliases like `"pcm16"`, `"s24"` or `"f32"`, and
+/// `container/encoding` pairs like `"ogg/vorbis"`, in which case the container part is ignored.
+impl std::str::FromStr for SubtypeFormat {
+  type Err = SndFileError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let name = normalize_format_name(s.rsplit('/').next().unwrap_or(""));
+    SUBTYPE_FORMAT_NAMES
+      .iter()
+      .find(|(_, names)| names.contains(&name.as_str()))
+      .map(|(x, _)| *x)
+      .ok_or_else(|| {
+        SndFileError::InvalidParameter(format!(
+          "Got `{}`, expect an encoding format like `pcm16` or `float`.",
+          s
+        ))
+      })
   }
 }
@@ -0,0 +1,113 @@
+use super::SndFileError;
+use std::iter::StepBy;
+use std::ops::Index;
+use std::slice::{ChunksExact, Iter};
+
+/// A view of an interleaved buffer as frames, e.g. the buffer of `SndFile::read_all_to_vec`.
+///
+/// ```ignore
+/// let buf: Vec<f32> = snd.read_all_to_vec()?;
+/// let frames = Frames::new(&buf, snd.get_channels())?;
+/// let left_peak = frames.channel(0).fold(0.0f32, |acc, x| acc.max(x.abs()));
+/// for frame in frames.iter() {
+///   println!("{:?}", frame);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Frames<'a, T> {
+  data: &'a [T],
+  channels: usize,
+}
+
+impl<T> Clone for Frames<'_, T> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<T> Copy for Frames<'_, T> {}
+
+impl<'a, T> Frames<'a, T> {
+  /// Wrap `data` of `channels` interleaved channels, returns `SndFileError::InvalidParameter` if
+  /// `channels` is zero or `data.len()` is not a multiple of it.
+  pub fn new(data: &'a [T], channels: usize) -> Result<Self, SndFileError> {
+    if channels == 0 || data.len() % channels != 0 {
+      return Err(SndFileError::InvalidParameter(format!(
+        "Got buffer length {}, expect a multiple of channel count {}.",
+        data.len(),
+        channels
+      )));
+    }
+    Ok(Frames { data, channels })
+  }
+
+  /// Get the channel count.
+  pub fn channels(&self) -> usize {
+    self.channels
+  }
+
+  /// Get the number of frames.
+  pub fn len(&self) -> usize {
+    self.data.len() / self.channels
+  }
+
+  /// Check if there is no frame.
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  /// Get the samples of frame `i`, or `None` if out of bounds.
+  pub fn get(&self, i: usize) -> Option<&'a [T]> {
+    self.data.get(i * self.channels..(i + 1) * self.channels)
+  }
+
+  /// Get sample of `channel` in frame `i`, or `None` if out of bounds.
+  pub fn sample(&self, i: usize, channel: usize) -> Option<&'a T> {
+    if channel < self.channels {
+      self.data.get(i * self.channels + channel)
+    } else {
+      None
+    }
+  }
+
+  /// Iterate over frames, each one is a slice of `channels` samples.
+  pub fn iter(&self) -> ChunksExact<'a, T> {
+    self.data.chunks_exact(self.channels)
+  }
+
+  /// Iterate over the samples of `channel`.
+  ///
+  /// Panics if `channel` is not less than the channel count.
+  pub fn channel(&self, channel: usize) -> StepBy<Iter<'a, T>> {
+    assert!(channel < self.channels);
+    self
+      .data
+      .get(channel..)
+      .unwrap_or(&[])
+      .iter()
+      .step_by(self.channels)
+  }
+
+  /// Get the underlying interleaved buffer.
+  pub fn as_interleaved(&self) -> &'a [T] {
+    self.data
+  }
+}
+
+impl<T> Index<usize> for Frames<'_, T> {
+  type Output = [T];
+
+  /// Get the samples of frame `i`, panics if out of bounds.
+  fn index(&self, i: usize) -> &[T] {
+    &self.data[i * self.channels..(i + 1) * self.channels]
+  }
+}
+
+impl<'a, T> IntoIterator for Frames<'a, T> {
+  type Item = &'a [T];
+  type IntoIter = ChunksExact<'a, T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
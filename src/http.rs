@@ -0,0 +1,139 @@
+use super::ranged::{FetchRange, RangedReader};
+use super::{OpenOptions, SndFile, SndFileError, SourceKind, VirtualIo};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+
+/// A read only `VirtualIo` fetching a remote file with HTTP range requests, so seekable formats
+/// like WAV, FLAC or CAF can be inspected and partially decoded without downloading the whole file.
+///
+/// Fetched blocks are kept in a least recently used cache, see `HttpSource::with_cache`.
+///
+/// ```ignore
+/// let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto).from_url("https://example.com/a.wav")?;
+/// snd.seek(SeekFrom::Start(48000 * 60))?;
+/// let mut buf = vec![0.0f32; 4096 * snd.get_channels()];
+/// snd.read_to_slice(&mut buf)?;
+/// ```
+pub struct HttpSource(RangedReader<HttpFetcher>);
+
+struct HttpFetcher {
+  agent: ureq::Agent,
+  url: String,
+}
+
+impl std::fmt::Debug for HttpSource {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("HttpSource")
+      .field("url", &self.0.fetcher.url)
+      .field("len", &self.0.len())
+      .field("pos", &self.0.position())
+      .finish()
+  }
+}
+
+fn http_error(e: ureq::Error) -> io::Error {
+  io::Error::other(e.to_string())
+}
+
+impl FetchRange for HttpFetcher {
+  fn fetch(&mut self, range: Range<u64>) -> io::Result<Vec<u8>> {
+    let resp = self
+      .agent
+      .get(&self.url)
+      .set("Range", &format!("bytes={}-{}", range.start, range.end - 1))
+      .call()
+      .map_err(http_error)?;
+    if resp.status() != 206 {
+      return Err(io::Error::other(format!(
+        "Got HTTP status {}, expect 206.",
+        resp.status()
+      )));
+    }
+    let mut data = Vec::with_capacity((range.end - range.start) as usize);
+    resp.into_reader().read_to_end(&mut data)?;
+    Ok(data)
+  }
+}
+
+impl HttpSource {
+  /// Connect to `url`, returns `SndFileError::IOError` if the request fails or the server does not
+  /// support range requests.
+  ///
+  /// Uses blocks of 64 KiB and a cache of 32 blocks.
+  pub fn open(url: &str) -> Result<HttpSource, SndFileError> {
+    HttpSource::with_cache(url, 64 * 1024, 32)
+  }
+
+  /// Connect to `url`, fetching `block_size` bytes per request and caching up to `n_blocks`
+  /// blocks.
+  pub fn with_cache(
+    url: &str,
+    block_size: usize,
+    n_blocks: usize,
+  ) -> Result<HttpSource, SndFileError> {
+    if block_size == 0 || n_blocks == 0 {
+      return Err(SndFileError::InvalidParameter(format!(
+        "Got block size {} and {} blocks, expect positive numbers.",
+        block_size, n_blocks
+      )));
+    }
+    let agent = ureq::Agent::new();
+    let resp = agent
+      .get(url)
+      .set("Range", "bytes=0-0")
+      .call()
+      .map_err(|e| SndFileError::IOError(http_error(e)))?;
+    // `Content-Range: bytes 0-0/<len>`
+    let len = resp
+      .header("Content-Range")
+      .filter(|_| resp.status() == 206)
+      .and_then(|x| x.rsplit('/').next())
+      .and_then(|x| x.trim().parse::<u64>().ok())
+      .ok_or_else(|| {
+        SndFileError::IOError(io::Error::new(
+          io::ErrorKind::Unsupported,
+          format!("`{}` does not support range requests.", url),
+        ))
+      })?;
+    let fetcher = HttpFetcher {
+      agent,
+      url: url.to_string(),
+    };
+    Ok(HttpSource(RangedReader::new(
+      fetcher, len, block_size, n_blocks,
+    )))
+  }
+
+  /// URL of the remote file.
+  pub fn url(&self) -> &str {
+    &self.0.fetcher.url
+  }
+}
+
+impl Read for HttpSource {
+  fn read(&mut self, dst: &mut [u8]) -> io::Result<usize> {
+    self.0.read(dst)
+  }
+}
+
+impl Seek for HttpSource {
+  fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+    self.0.seek(pos)
+  }
+}
+
+impl VirtualIo for HttpSource {
+  fn byte_len(&self) -> io::Result<u64> {
+    Ok(self.0.len())
+  }
+}
+
+impl OpenOptions {
+  /// Open a remote audio file at `url` with `HttpSource`, only `OpenOptions::ReadOnly` is
+  /// supported.
+  pub fn from_url(&self, url: &str) -> Result<SndFile, SndFileError> {
+    self
+      .from_virtual_io(HttpSource::open(url)?)
+      .map(|x| x.with_source(SourceKind::Url, None))
+  }
+}
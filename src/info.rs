@@ -0,0 +1,115 @@
+use super::{
+  get_current_sf_info, Endian, MajorFormat, OpenOptions, ReadOptions, SndFile, SndFileError,
+  SubtypeFormat, TagType,
+};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Id and size of a raw chunk, see `SndFileInfo::chunks`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde_features", derive(serde::Serialize))]
+pub struct ChunkSummary {
+  pub id: String,
+  /// Size of chunk data in bytes.
+  pub size: u64,
+}
+
+/// Information of an audio file, see `SndFile::info` and `probe`.
+///
+/// With feature `serde_features`, it can be serialized, e.g. by `SndFileInfo::to_json`.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde_features", derive(serde::Serialize))]
+pub struct SndFileInfo {
+  pub samplerate: usize,
+  pub channels: usize,
+  /// Number of frames, `None` if unknown.
+  pub frames: Option<u64>,
+  /// Duration computed from `frames` and `samplerate`, `None` if unknown.
+  ///
+  /// Serialized as seconds.
+  #[cfg_attr(
+    feature = "serde_features",
+    serde(serialize_with = "serialize_duration")
+  )]
+  pub duration: Option<Duration>,
+  pub major_format: MajorFormat,
+  pub subtype_format: SubtypeFormat,
+  pub endian: Endian,
+  pub seekable: bool,
+  /// Number of sections reported by libsndfile.
+  pub sections: usize,
+  pub tags: HashMap<TagType, String>,
+  /// Raw chunks of a WAV or AIFF file, including chunks managed by libsndfile like `fmt `.
+  pub chunks: Vec<ChunkSummary>,
+}
+
+#[cfg(feature = "serde_features")]
+fn serialize_duration<S: serde::Serializer>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+  match d {
+    Some(x) => s.serialize_some(&x.as_secs_f64()),
+    None => s.serialize_none(),
+  }
+}
+
+#[cfg(feature = "serde_features")]
+impl SndFileInfo {
+  /// Serialize to a JSON object, with the same keys as the fields of `SndFileInfo`.
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(self).expect("SndFileInfo is always serializable.")
+  }
+}
+
+impl SndFile {
+  /// Collect information of this file into a `SndFileInfo`.
+  ///
+  /// This function does not affect the I/O cursor.
+  pub fn info(&self) -> SndFileInfo {
+    let sf_info = get_current_sf_info(self.unsafe_fields.sndfile_ptr);
+    let samplerate = self.get_samplerate();
+    let frames = if self.is_seekable() && sf_info.frames >= 0 {
+      Some(sf_info.frames as u64)
+    } else {
+      None
+    };
+    SndFileInfo {
+      samplerate,
+      channels: self.get_channels(),
+      frames,
+      duration: frames.map(|x| {
+        Duration::from_secs(x / samplerate as u64)
+          + Duration::from_nanos((x % samplerate as u64) * 1_000_000_000 / samplerate as u64)
+      }),
+      major_format: self.get_major_format(),
+      subtype_format: self.get_subtype_format(),
+      endian: self.get_endian(),
+      seekable: self.is_seekable(),
+      sections: sf_info.sections.max(0) as usize,
+      tags: self.get_tags(),
+      chunks: self
+        .get_chunk_sizes()
+        .into_iter()
+        .map(|(id, size)| ChunkSummary { id, size })
+        .collect(),
+    }
+  }
+
+  /// Reload the cached samplerate, channels, format and seekability from
+  /// `SFC_GET_CURRENT_SF_INFO`, returns the updated information if success.
+  ///
+  /// Useful in `ReadWrite` mode, where writing frames changes the file behind the cached fields.
+  /// Returns an error if the reloaded information is invalid.
+  pub fn refresh_info(&mut self) -> Result<SndFileInfo, SndFileError> {
+    self.sync_info()?;
+    Ok(self.info())
+  }
+}
+
+/// Open the audio file at `path`, collect its information and close it right away.
+///
+/// No sample is decoded, which makes it suitable for scanning a large number of files.
+pub fn probe<P: AsRef<Path>>(path: P) -> Result<SndFileInfo, SndFileError> {
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto).from_path(path)?;
+  Ok(snd.info())
+}
@@ -0,0 +1,248 @@
+use super::edit::BLOCK_FRAMES;
+use super::{SndFile, SndFileError, SndFileIO};
+use std::io::SeekFrom;
+
+/// Signal levels measured by `SndFile::measure_levels`.
+///
+/// Levels are linear amplitudes of normalized samples, i.e. `1.0` is full scale.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Levels {
+  /// Peak absolute sample value of each channel.
+  pub peak: Vec<f64>,
+  /// RMS of each channel.
+  pub rms: Vec<f64>,
+  /// EBU R128 integrated loudness in LUFS, `None` if the file is too short or entirely below the
+  /// absolute gate.
+  #[cfg(feature = "loudness")]
+  pub integrated_lufs: Option<f64>,
+}
+
+impl SndFile {
+  /// Measure per-channel peak and RMS by streaming through the whole file.
+  ///
+  /// With feature `loudness`, EBU R128 integrated loudness is measured as well.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn measure_levels(&mut self) -> Result<Levels, SndFileError> {
+    let channels = self.get_channels();
+    let mut peak = vec![0.0f64; channels];
+    let mut sum_sq = vec![0.0f64; channels];
+    let mut n_frames = 0u64;
+    #[cfg(feature = "loudness")]
+    let mut meter = loudness::Meter::new(self.get_samplerate(), channels);
+
+    let mut buf = vec![0.0f64; BLOCK_FRAMES * channels];
+    self.seek(SeekFrom::Start(0))?;
+    loop {
+      let n = self.read_to_slice(&mut buf)?;
+      if n == 0 {
+        break;
+      }
+      for frame in buf[..n * channels].chunks(channels) {
+        for (i, x) in frame.iter().enumerate() {
+          peak[i] = peak[i].max(x.abs());
+          sum_sq[i] += x * x;
+        }
+        #[cfg(feature = "loudness")]
+        meter.process(frame);
+      }
+      n_frames += n as u64;
+    }
+
+    let rms = sum_sq
+      .iter()
+      .map(|x| {
+        if n_frames > 0 {
+          (x / n_frames as f64).sqrt()
+        } else {
+          0.0
+        }
+      })
+      .collect();
+    Ok(Levels {
+      peak,
+      rms,
+      #[cfg(feature = "loudness")]
+      integrated_lufs: meter.integrated(),
+    })
+  }
+}
+
+impl SndFile {
+  /// Compute min/max pairs of `pixels` equally sized buckets for waveform display by streaming
+  /// through the whole file, returns one `Vec` of length `pixels` per channel.
+  ///
+  /// Buckets without any frame, e.g. when the file has fewer frames than `pixels`, are `(0.0, 0.0)`.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn compute_peaks(&mut self, pixels: usize) -> Result<Vec<Vec<(f32, f32)>>, SndFileError> {
+    let channels = self.get_channels();
+    let len = self.len()?;
+    let mut peaks = vec![vec![(f32::INFINITY, f32::NEG_INFINITY); pixels]; channels];
+    if pixels > 0 {
+      let mut buf = vec![0.0f32; BLOCK_FRAMES * channels];
+      let mut pos = 0u64;
+      self.seek(SeekFrom::Start(0))?;
+      loop {
+        let n = self.read_to_slice(&mut buf)?;
+        if n == 0 {
+          break;
+        }
+        for frame in buf[..n * channels].chunks(channels) {
+          let bucket =
+            ((pos as u128 * pixels as u128 / len.max(1) as u128) as usize).min(pixels - 1);
+          for (ch, x) in frame.iter().enumerate() {
+            let (min, max) = &mut peaks[ch][bucket];
+            *min = min.min(*x);
+            *max = max.max(*x);
+          }
+          pos += 1;
+        }
+      }
+    }
+    for (min, max) in peaks.iter_mut().flatten() {
+      if min > max {
+        *min = 0.0;
+        *max = 0.0;
+      }
+    }
+    Ok(peaks)
+  }
+}
+
+#[cfg(feature = "loudness")]
+mod loudness {
+  use std::f64::consts::PI;
+
+  /// Biquad filter in transposed direct form II.
+  #[derive(Clone)]
+  struct Biquad {
+    b: [f64; 3],
+    a: [f64; 3],
+    z: [f64; 2],
+  }
+
+  impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+      let y = self.b[0] * x + self.z[0];
+      self.z[0] = self.b[1] * x - self.a[1] * y + self.z[1];
+      self.z[1] = self.b[2] * x - self.a[2] * y;
+      y
+    }
+  }
+
+  /// K-weighting filter of ITU-R BS.1770 for given samplerate, as a high shelf and a high pass.
+  fn k_weighting(samplerate: f64) -> (Biquad, Biquad) {
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (PI * f0 / samplerate).tan();
+    let vh = 10.0f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+      b: [
+        (vh + vb * k / q + k * k) / a0,
+        2.0 * (k * k - vh) / a0,
+        (vh - vb * k / q + k * k) / a0,
+      ],
+      a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+      z: [0.0; 2],
+    };
+
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (PI * f0 / samplerate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+      b: [1.0, -2.0, 1.0],
+      a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+      z: [0.0; 2],
+    };
+    (shelf, high_pass)
+  }
+
+  /// Channel weights of ITU-R BS.1770, assuming the channel order of WAV.
+  fn channel_weight(channels: usize, i: usize) -> f64 {
+    match (channels, i) {
+      (4, 2..=3) | (5, 3..=4) => 1.41,
+      // LFE, then the back and side surround channels of 5.1 and larger layouts, e.g. 7.1.
+      (c, 3) if c >= 6 => 0.0,
+      (c, i) if c >= 6 && i >= 4 => 1.41,
+      _ => 1.0,
+    }
+  }
+
+  fn to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+  }
+
+  /// Gated loudness meter of EBU R128, with 400 ms blocks overlapping by 75%.
+  pub(super) struct Meter {
+    filters: Vec<(Biquad, Biquad)>,
+    weights: Vec<f64>,
+    sub_block_len: usize,
+    sub_block_pos: usize,
+    sub_block_sum: f64,
+    sub_blocks: [f64; 4],
+    n_sub_blocks: usize,
+    blocks: Vec<f64>,
+  }
+
+  impl Meter {
+    pub(super) fn new(samplerate: usize, channels: usize) -> Self {
+      Meter {
+        filters: vec![k_weighting(samplerate as f64); channels],
+        weights: (0..channels).map(|i| channel_weight(channels, i)).collect(),
+        sub_block_len: (samplerate / 10).max(1),
+        sub_block_pos: 0,
+        sub_block_sum: 0.0,
+        sub_blocks: [0.0; 4],
+        n_sub_blocks: 0,
+        blocks: Vec::new(),
+      }
+    }
+
+    pub(super) fn process(&mut self, frame: &[f64]) {
+      for ((x, (shelf, high_pass)), w) in frame
+        .iter()
+        .zip(self.filters.iter_mut())
+        .zip(self.weights.iter())
+      {
+        let y = high_pass.process(shelf.process(*x));
+        self.sub_block_sum += w * y * y;
+      }
+      self.sub_block_pos += 1;
+      if self.sub_block_pos == self.sub_block_len {
+        self.sub_blocks[self.n_sub_blocks % 4] = self.sub_block_sum;
+        self.n_sub_blocks += 1;
+        self.sub_block_pos = 0;
+        self.sub_block_sum = 0.0;
+        if self.n_sub_blocks >= 4 {
+          let sum: f64 = self.sub_blocks.iter().sum();
+          self.blocks.push(sum / (4 * self.sub_block_len) as f64);
+        }
+      }
+    }
+
+    pub(super) fn integrated(&self) -> Option<f64> {
+      let mean = |blocks: &[f64]| blocks.iter().sum::<f64>() / blocks.len() as f64;
+      let above_abs: Vec<f64> = self
+        .blocks
+        .iter()
+        .cloned()
+        .filter(|x| to_lufs(*x) > -70.0)
+        .collect();
+      if above_abs.is_empty() {
+        return None;
+      }
+      let relative_gate = to_lufs(mean(&above_abs)) - 10.0;
+      let above_rel: Vec<f64> = above_abs
+        .into_iter()
+        .filter(|x| to_lufs(*x) > relative_gate)
+        .collect();
+      Some(to_lufs(mean(&above_rel)))
+    }
+  }
+}
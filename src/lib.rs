@@ -57,44 +57,178 @@ Loaded song `Loow`:
 
 */
 
-#[macro_use]
-extern crate lazy_static;
+// `SndFile` is only deprecated for users, the crate is built on it.
+#![allow(deprecated)]
+
+#[cfg(not(any(
+  feature = "system_linking",
+  feature = "runtime_linking",
+  feature = "vendored"
+)))]
+compile_error!(
+  "libsndfile is not linked, enable the `system_linking`, `runtime_linking` or `vendored` feature."
+);
 
 use sndfile_sys::sf_count_t;
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::SeekFrom;
 use std::iter::FromIterator;
+use std::mem::MaybeUninit;
 use std::os::raw::{c_int, c_void};
-use std::path::Path;
-use std::sync::Mutex;
-
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+#[cfg(not(feature = "system_linking"))]
+use sys as sndfile_sys;
+use trace::OpTimer;
+use vio::VIOStream;
+
+#[cfg(feature = "audio_features")]
+mod audio_core_support;
+mod audio_io;
+mod batch;
+mod bulk;
+#[cfg(feature = "bytemuck_features")]
+mod bytes_io;
+mod channels;
+mod command;
+mod concat_reader;
+mod edit;
+mod ffi;
 mod format;
+mod frames;
+#[cfg(feature = "http_features")]
+mod http;
+mod info;
+mod levels;
+mod looping;
+mod metadata;
+mod meter;
+mod mode;
+mod mpeg;
+#[cfg(feature = "object_store_features")]
+mod object_store_support;
+mod ogg;
+mod playlist;
+mod pool;
+#[cfg(any(feature = "http_features", feature = "object_store_features"))]
+mod ranged;
+#[cfg(feature = "replaygain")]
+mod replaygain;
+mod ring;
+mod rt;
+mod scan;
+pub mod signal;
+mod stats;
+mod stereo;
+mod stream;
+#[cfg(not(feature = "system_linking"))]
+mod sys;
+mod timecode;
+mod trace;
+mod trim;
+mod typed;
+mod uri;
+mod verify;
+mod vio;
+#[cfg(all(target_arch = "wasm32", feature = "wasm_features"))]
+mod web_audio;
+#[cfg(feature = "zip_features")]
+mod zip_support;
 
 #[cfg(test)]
 mod test;
 
+pub use audio_io::{AudioInfo, AudioRead, AudioWrite};
+pub use batch::{retag, TagConflict, TagSet};
+pub use bulk::{default_read_limit, set_default_read_limit, ProgressControl};
+pub use channels::{ChannelMask, ChannelPosition};
+pub use command::{Command, CommandOutput};
+pub use concat_reader::ConcatReader;
+pub use edit::{
+  concat, copy_frames, copy_with_metadata, split, transcode, transcode_pipelined,
+  transcode_with_progress,
+};
+
+pub use ffi::load_library;
 pub use format::{
-  check_format, default_subtype, get_supported_major_format_dict,
-  get_supported_subtype_format_dict, Endian, MajorFormat, MajorInfo, SubtypeFormat, SubtypeInfo,
+  best_subtype_for, check_format, check_format_verbose, default_subtype, detect_format,
+  get_supported_major_format_dict, get_supported_subtype_format_dict, library_version, supports,
+  Endian, FormatClass, FormatSuggestion, MajorFormat, MajorInfo, SubtypeFormat, SubtypeInfo,
 };
+pub use frames::Frames;
+#[cfg(feature = "http_features")]
+pub use http::HttpSource;
+pub use info::{probe, ChunkSummary, SndFileInfo};
+pub use levels::Levels;
+pub use looping::LoopingReader;
+pub use metadata::{BroadcastInfo, Chunk, CuePoint, Marker};
+pub use meter::{MeteredWriter, PeakMeter, WriteMeter};
+pub use mode::{ClippedChannel, SndReadWriter, SndReader, SndWriter, WriteSummary, WriterStats};
+pub use mpeg::GaplessInfo;
+#[cfg(feature = "object_store_features")]
+pub use object_store_support::ObjectStoreSource;
+pub use ogg::write_vorbis_comments;
+pub use playlist::{Playlist, PlaylistEvent};
+pub use pool::{PooledSndFile, SndFilePool};
+#[cfg(feature = "replaygain")]
+pub use replaygain::{apply_replaygain, write_replaygain_tags, ReplayGain};
+pub use rt::{RtError, RtReader, RtSample};
+pub use scan::{scan, Scan, ScanError, ScanOptions};
+pub use stats::{Sample, Stats};
+pub use stereo::{MonoReader, StereoReader};
+pub use stream::{CaptureInput, CaptureWriter, StreamBridge, StreamConsumer, StreamProducer};
+pub use timecode::FrameRate;
+pub use trim::TrimOptions;
+pub use typed::TypedSndFile;
+pub use uri::open_uri;
+pub use verify::{compare, Comparison, MetadataDifference, Verification};
+pub use vio::VirtualIo;
+#[cfg(all(target_arch = "wasm32", feature = "wasm_features"))]
+pub use web_audio::channel_data_from_interleaved;
 
 #[cfg(feature = "ndarray_features")]
 mod ndarray_support;
 #[cfg(feature = "ndarray_features")]
 pub use ndarray_support::*;
 
-lazy_static! {
-  static ref SF_GLOBAL_LOCK: Mutex<()> = Mutex::new(());
+static SF_GLOBAL_LOCK: Mutex<()> = Mutex::new(());
+
+/// Lock `SF_GLOBAL_LOCK`, ignoring poisoning since it guards no data, so a panic while holding it
+/// does not disable the protection for later opens.
+fn lock_sf_global() -> MutexGuard<'static, ()> {
+  SF_GLOBAL_LOCK
+    .lock()
+    .unwrap_or_else(PoisonError::into_inner)
 }
 
 #[derive(Debug)]
 pub struct VIOFile {
-  f: File,
+  f: VIOStream,
+  /// Open mode of libsndfile, kept with the stream so it survives `SndFile::into_raw`.
+  mode: c_int,
+}
+
+impl VIOFile {
+  /// Length of the underlying stream in bytes.
+  fn byte_len(&self) -> std::io::Result<u64> {
+    self.f.get_ref().byte_len()
+  }
+
+  /// Returns `false` if the underlying stream can only be read forward.
+  fn is_seekable(&self) -> bool {
+    self.f.get_ref().is_seekable()
+  }
+
+  /// The underlying `File`, or an error if opened from a `VirtualIo`.
+  fn file(&self) -> Result<&File, SndFileError> {
+    self.f.file()
+  }
 }
 
 extern "C" fn vio_get_filelen(user_data: *mut c_void) -> sf_count_t {
   let vio_file = unsafe { (user_data as *mut VIOFile).as_mut().unwrap() };
-  vio_file.f.metadata().unwrap().len() as sf_count_t
+  vio_file.byte_len().map_or(-1, |x| x as sf_count_t)
 }
 
 extern "C" fn vio_seek(offset: sf_count_t, whence: c_int, user_data: *mut c_void) -> sf_count_t {
@@ -105,13 +239,30 @@ extern "C" fn vio_seek(offset: sf_count_t, whence: c_int, user_data: *mut c_void
     sndfile_sys::SF_SEEK_END => SeekFrom::End(offset),
     _ => unreachable!(),
   };
-  vio_file.f.seek(seek_from).unwrap() as sf_count_t
+  vio_file
+    .f
+    .get_mut()
+    .seek(seek_from)
+    .map_or(-1, |x| x as sf_count_t)
 }
 
 extern "C" fn vio_read(dst: *mut c_void, count: sf_count_t, user_data: *mut c_void) -> sf_count_t {
   let vio_file = unsafe { (user_data as *mut VIOFile).as_mut().unwrap() };
   let dst_buf = unsafe { std::slice::from_raw_parts_mut(dst as *mut u8, count as usize) };
-  vio_file.f.read(dst_buf).unwrap() as sf_count_t
+  // libsndfile takes a short read for the end of file, so read until `count` bytes or the end,
+  // e.g. readers returning one cached block at a time. Must not panic, it is on the realtime path
+  // of `RtReader`. A failed read is a short read.
+  let f = vio_file.f.get_mut();
+  let mut done = 0;
+  while done < dst_buf.len() {
+    match f.read(&mut dst_buf[done..]) {
+      Ok(0) => break,
+      Ok(n) => done += n,
+      Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+      Err(_) => break,
+    }
+  }
+  done as sf_count_t
 }
 
 extern "C" fn vio_write(
@@ -121,14 +272,28 @@ extern "C" fn vio_write(
 ) -> sf_count_t {
   let vio_file = unsafe { (user_data as *mut VIOFile).as_mut().unwrap() };
   let src_buf = unsafe { std::slice::from_raw_parts(src as *const u8, count as usize) };
-  vio_file.f.write(src_buf).unwrap() as sf_count_t
+  // A failed write is a short write, reported by libsndfile.
+  vio_file.f.get_mut().write_bytes(src_buf).unwrap_or(0) as sf_count_t
 }
 
 extern "C" fn vio_tell(user_data: *mut c_void) -> sf_count_t {
   let vio_file = unsafe { (user_data as *mut VIOFile).as_mut().unwrap() };
-  vio_file.f.seek(SeekFrom::Current(0)).unwrap() as sf_count_t
+  vio_file
+    .f
+    .get_mut()
+    .stream_position()
+    .map_or(-1, |x| x as sf_count_t)
 }
 
+/// Virtual I/O callbacks shared by all files, the file of each handle is passed as user data.
+static VIRTUAL_IO: sndfile_sys::SF_VIRTUAL_IO = sndfile_sys::SF_VIRTUAL_IO {
+  get_filelen: vio_get_filelen,
+  seek: vio_seek,
+  read: vio_read,
+  write: vio_write,
+  tell: vio_tell,
+};
+
 /// Options for reading audio files.
 #[derive(Debug)]
 pub enum ReadOptions {
@@ -146,6 +311,8 @@ pub struct WriteOptions {
   endian: format::Endian,
   samplerate: usize,
   channels: usize,
+  tags: Vec<(TagType, String)>,
+  channel_mask: Option<ChannelMask>,
 }
 
 impl WriteOptions {
@@ -156,6 +323,8 @@ impl WriteOptions {
   /// * `endian`: Usually `Endian::File`  
   /// * `samplerate`: A positive number  
   /// * `channels`: A positive number  
+  ///
+  /// Panics if `samplerate` or `channels` is zero, use `WriteOptions::try_new` for untrusted values.
   pub fn new(
     major_format: format::MajorFormat,
     subtype_format: format::SubtypeFormat,
@@ -171,24 +340,207 @@ impl WriteOptions {
       endian,
       samplerate,
       channels,
+      tags: Vec::new(),
+      channel_mask: None,
     }
   }
 
+  /// Create new WriteOptions, returns `SndFileError::InvalidParameter` instead of panicking if
+  /// `samplerate` or `channels` is zero.
+  pub fn try_new(
+    major_format: format::MajorFormat,
+    subtype_format: format::SubtypeFormat,
+    endian: format::Endian,
+    samplerate: usize,
+    channels: usize,
+  ) -> Result<Self, SndFileError> {
+    if samplerate == 0 {
+      Err(SndFileError::InvalidParameter(
+        "Got invalid samplerate, expect a positive number.".to_string(),
+      ))
+    } else if channels == 0 {
+      Err(SndFileError::InvalidParameter(
+        "Got invalid channels, expect a positive number.".to_string(),
+      ))
+    } else {
+      Ok(Self::new(
+        major_format,
+        subtype_format,
+        endian,
+        samplerate,
+        channels,
+      ))
+    }
+  }
+
+  /// Create new WriteOptions for writing to `path`, with the container format inferred from its
+  /// extension, the encoding format given by `default_subtype` and `Endian::File`.
+  ///
+  /// Returns `SndFileError::InvalidParameter` if the extension is missing or unknown, or if the
+  /// container format has no default encoding format, e.g. `raw`.
+  pub fn for_path<P: AsRef<Path>>(
+    path: P,
+    samplerate: usize,
+    channels: usize,
+  ) -> Result<Self, SndFileError> {
+    let ext = path
+      .as_ref()
+      .extension()
+      .and_then(|x| x.to_str())
+      .unwrap_or("");
+    let major_format = format::MajorFormat::from_extension(ext).ok_or_else(|| {
+      SndFileError::InvalidParameter(format!(
+        "Got file extension `{}`, expect an audio file extension like `wav` or `flac`.",
+        ext
+      ))
+    })?;
+    let subtype_format = default_subtype(major_format).ok_or_else(|| {
+      SndFileError::InvalidParameter(format!(
+        "Got container format {:?}, expect a format with a default encoding format.",
+        major_format
+      ))
+    })?;
+    Self::try_new(
+      major_format,
+      subtype_format,
+      format::Endian::File,
+      samplerate,
+      channels,
+    )
+  }
+
+  /// Set tag `t` right after the file is opened, before any frame is written.
+  ///
+  /// Some containers only keep strings written before the audio data, so tags set this way are
+  /// guaranteed to end up in the file, unlike `SndFile::set_tag` after writing frames. A NUL
+  /// character in `v` makes opening the file fail with `SndFileError::InvalidParameter`.
+  pub fn with_tag(mut self, t: TagType, v: &str) -> Self {
+    self.tags.push((t, v.to_string()));
+    self
+  }
+
+  /// Set multiple tags like `with_tag`.
+  pub fn with_tags<'a, I>(mut self, tags: I) -> Self
+  where
+    I: IntoIterator<Item = (TagType, &'a str)>,
+  {
+    self
+      .tags
+      .extend(tags.into_iter().map(|(t, v)| (t, v.to_string())));
+    self
+  }
+
+  /// Set the speaker positions of the channels right after the file is opened, e.g.
+  /// `ChannelMask::SURROUND_5_1` for a 6 channel WAVEX file, see `SndFile::set_channel_mask`.
+  ///
+  /// Opening the file fails with `SndFileError::InvalidParameter` if the mask does not have one bit
+  /// per channel.
+  pub fn with_channel_mask(mut self, mask: ChannelMask) -> Self {
+    self.channel_mask = Some(mask);
+    self
+  }
+
+  /// Set the encoding format to the PCM encoding of `bits` bits supported by the container format,
+  /// e.g. `PCM_24` for `24`, or to the ALAC one if there is no PCM one, e.g. `ALAC_20` for `20` in
+  /// CAF files. See `supports`.
+  ///
+  /// Returns `SndFileError::InvalidParameter` if the container format has no such encoding.
+  pub fn with_bit_depth(mut self, bits: u32) -> Result<Self, SndFileError> {
+    let candidates: &[format::SubtypeFormat] = match bits {
+      8 => &[format::SubtypeFormat::PCM_S8, format::SubtypeFormat::PCM_U8],
+      16 => &[
+        format::SubtypeFormat::PCM_16,
+        format::SubtypeFormat::ALAC_16,
+      ],
+      20 => &[format::SubtypeFormat::ALAC_20],
+      24 => &[
+        format::SubtypeFormat::PCM_24,
+        format::SubtypeFormat::ALAC_24,
+      ],
+      32 => &[
+        format::SubtypeFormat::PCM_32,
+        format::SubtypeFormat::ALAC_32,
+      ],
+      _ => &[],
+    };
+    self.subtype_format = candidates
+      .iter()
+      .copied()
+      .find(|&x| supports(self.major_format, x))
+      .ok_or_else(|| {
+        SndFileError::InvalidParameter(format!(
+          "Got bit depth {}, expect a bit depth supported by container format {:?}.",
+          bits, self.major_format
+        ))
+      })?;
+    Ok(self)
+  }
+
   /// This function allows the caller to check if a set of parameters in the WriteOptions is valid.
   ///
-  /// Returns `Some(Self)` if the parameters are valid and `None` otherwise.
-  pub fn validate(self) -> Option<Self> {
-    if check_format(
+  /// Returns `Ok(Self)` if the parameters are valid, otherwise a `SndFileError::InvalidParameter`
+  /// describing the combination rejected by libsndfile and naming the parameter to change, the
+  /// first one of endian, subtype and channel count whose change makes the format valid, with its
+  /// accepted values, see `check_format_verbose`.
+  pub fn validate(self) -> Result<Self, SndFileError> {
+    load_library()?;
+    let suggestions = match check_format_verbose(
       self.channels,
       self.samplerate,
       self.major_format,
       self.subtype_format,
       self.endian,
     ) {
-      Some(self)
-    } else {
-      None
-    }
+      Ok(()) => return Ok(self),
+      Err(x) => x,
+    };
+    let combination = format!(
+      "Rejected format combination: major_format = {:?}, subtype_format = {:?}, endian = {:?}, samplerate = {}, channels = {}.",
+      self.major_format, self.subtype_format, self.endian, self.samplerate, self.channels
+    );
+    let hint = match suggestions.first() {
+      Some(x) if x.endian != self.endian => Some((
+        "endian",
+        format!("{:?}", self.endian),
+        suggestions
+          .iter()
+          .filter(|y| y.endian != self.endian)
+          .map(|y| format!("{:?}", y.endian))
+          .collect::<Vec<_>>(),
+      )),
+      Some(x) if x.subtype_format != self.subtype_format => Some((
+        "subtype_format",
+        format!("{:?}", self.subtype_format),
+        suggestions
+          .iter()
+          .filter(|y| y.subtype_format != self.subtype_format)
+          .map(|y| format!("{:?}", y.subtype_format))
+          .collect(),
+      )),
+      Some(_) => Some((
+        "channels",
+        self.channels.to_string(),
+        suggestions
+          .iter()
+          .filter(|y| y.channels != self.channels)
+          .map(|y| y.channels.to_string())
+          .collect(),
+      )),
+      None => None,
+    };
+    Err(SndFileError::InvalidParameter(match hint {
+      Some((name, got, expect)) => format!(
+        "{} Got {} {}, expect one of {}.",
+        combination,
+        name,
+        got,
+        expect.join(", ")
+      ),
+      None => format!(
+        "{} No single change of endian, subtype_format or channels is accepted.",
+        combination
+      ),
+    }))
   }
 }
 
@@ -215,12 +567,17 @@ pub enum OpenOptions {
 /// This struct is unstable.
 #[derive(Debug)]
 pub struct UnsafeSndFile {
-  pub vio_ptr: *mut sndfile_sys::SF_VIRTUAL_IO,
   pub vio_user_ptr: *mut VIOFile,
   pub sndfile_ptr: *mut sndfile_sys::SNDFILE,
 }
 
 /// Main struct of this crate.
+///
+/// It does not track the open mode, `SndReader`, `SndWriter` and `SndReadWriter` wrap it to catch
+/// reading a write only file or writing a read only file at compile time.
+#[deprecated(
+  note = "`SndFile` does not check the open mode, use `SndReader`, `SndWriter` or `SndReadWriter`."
+)]
 #[derive(Debug)]
 pub struct SndFile {
   unsafe_fields: UnsafeSndFile,
@@ -230,6 +587,26 @@ pub struct SndFile {
   subtype_format: SubtypeFormat,
   endian: Endian,
   seekable: bool,
+  source: SourceKind,
+  path: Option<PathBuf>,
+}
+
+/// How a `SndFile` was opened, see `SndFile::source`.
+#[non_exhaustive]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum SourceKind {
+  /// `OpenOptions::from_path`.
+  Path,
+  /// `OpenOptions::from_file`.
+  File,
+  /// `OpenOptions::from_virtual_io`.
+  VirtualIo,
+  /// `OpenOptions::from_url`.
+  Url,
+  /// `OpenOptions::from_object_store`.
+  ObjectStore,
+  /// `OpenOptions::from_zip_entry`.
+  ZipEntry,
 }
 
 /// Do I/O operation on slice or iterator.
@@ -239,21 +616,56 @@ where
 {
   /// Read frames from current I/O cursor, returns the number of frames read if success.
   ///
+  /// `dst.len()` must be a multiple of the channel count, otherwise `SndFileError::InvalidParameter`
+  /// is returned.
+  ///
   /// This function may affect the I/O cursor.
-  fn read_to_slice(&mut self, dst: &mut [T]) -> Result<usize, ()>;
+  fn read_to_slice(&mut self, dst: &mut [T]) -> Result<usize, SndFileError>;
   /// Read frames from file, returns the number of frames written if success.
   ///
+  /// `src.len()` must be a multiple of the channel count, otherwise `SndFileError::InvalidParameter`
+  /// is returned.
+  ///
   /// This function may affect the I/O cursor.
-  fn write_from_slice(&mut self, src: &[T]) -> Result<usize, ()>;
-  /// Read all frames into a `Vec<_>` if success.
+  fn write_from_slice(&mut self, src: &[T]) -> Result<usize, SndFileError>;
+  /// Read items (samples) from current I/O cursor, returns the number of items read if success.
+  ///
+  /// Unlike `read_to_slice`, `dst.len()` need not be a multiple of the channel count, so a read may
+  /// end in the middle of a frame.
+  ///
+  /// This function may affect the I/O cursor.
+  fn read_items_to_slice(&mut self, dst: &mut [T]) -> Result<usize, SndFileError>;
+  /// Write items (samples), returns the number of items written if success.
+  ///
+  /// Unlike `write_from_slice`, `src.len()` need not be a multiple of the channel count.
+  ///
+  /// This function may affect the I/O cursor.
+  fn write_items_from_slice(&mut self, src: &[T]) -> Result<usize, SndFileError>;
+  /// Read frames from current I/O cursor into a possibly uninitialized buffer, returns the
+  /// initialized part of `dst` if success.
+  ///
+  /// This avoids zero-filling large buffers that are overwritten anyway. `dst.len()` must be a
+  /// multiple of the channel count, otherwise `SndFileError::InvalidParameter` is returned.
   ///
   /// This function may affect the I/O cursor.
-  fn read_all_to_vec(&mut self) -> Result<Vec<T>, ()>;
+  fn read_to_uninit_slice<'a>(
+    &mut self,
+    dst: &'a mut [MaybeUninit<T>],
+  ) -> Result<&'a mut [T], SndFileError>;
+  /// Read all frames into a `Vec<_>` if success.
+  ///
+  /// Returns `SndFileError::MemoryLimitExceeded` if the limit set by `set_default_read_limit` is
+  /// exceeded. If fewer frames than the file declares can be decoded, e.g. the file is truncated,
+  /// the `Vec<_>` only holds the frames decoded, see `SndFile::verify`.
+  ///
+  /// The I/O cursor is left unchanged. Files which are not seekable, e.g. read from a pipe, are
+  /// decoded from the I/O cursor until EOF instead.
+  fn read_all_to_vec(&mut self) -> Result<Vec<T>, SndFileError>;
 
   /// Read frames from current I/O cursor, returns the number of frames read if success.
   ///
   /// This function may affect the I/O cursor.
-  fn read_to_iter<'a, I>(&mut self, dst: I) -> Result<usize, ()>
+  fn read_to_iter<'a, I>(&mut self, dst: I) -> Result<usize, SndFileError>
   where
     I: ExactSizeIterator<Item = &'a mut T>,
   {
@@ -267,7 +679,7 @@ where
   /// Read frames from file, returns the number of frames written if success.
   ///
   /// This function may affect the I/O cursor.
-  fn write_from_iter<'a, I>(&mut self, src: I) -> Result<usize, ()>
+  fn write_from_iter<'a, I>(&mut self, src: I) -> Result<usize, SndFileError>
   where
     I: ExactSizeIterator<Item = T>,
   {
@@ -285,9 +697,89 @@ pub enum SndFileError {
   InvalidParameter(String),
   InternalError(String),
   IOError(std::io::Error),
+  /// The operation was cancelled by a progress callback.
+  Cancelled,
+  /// Reading the whole file would take `required` bytes, above the `limit`.
+  MemoryLimitExceeded {
+    required: u64,
+    limit: u64,
+  },
+  /// Any other libsndfile error, with its raw error code.
+  LibraryError {
+    code: i32,
+    message: String,
+  },
+  /// libsndfile could not be loaded at runtime, see `load_library`.
+  LibraryNotFound(String),
+  /// The file is not seekable, e.g. read from a pipe. `len_hint` is the number of frames declared
+  /// by the header if any, see `SndFile::len_hint`.
+  Unseekable {
+    len_hint: Option<u64>,
+  },
 }
 
-#[derive(Debug)]
+/// Category of a `SndFileError`, see `SndFileError::kind`.
+#[non_exhaustive]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum ErrorKind {
+  /// The container or encoding is not recognised or not supported.
+  Unsupported,
+  /// The file is malformed.
+  Malformed,
+  /// The operating system or the underlying stream failed.
+  Io,
+  /// An argument is invalid.
+  InvalidInput,
+  /// The operation was cancelled.
+  Cancelled,
+  /// A configured limit was exceeded.
+  LimitExceeded,
+  /// Any other error.
+  Other,
+}
+
+impl SndFileError {
+  /// Get the category of this error.
+  pub fn kind(&self) -> ErrorKind {
+    match self {
+      SndFileError::UnrecognisedFormat(_)
+      | SndFileError::UnsupportedEncoding(_)
+      | SndFileError::LibraryNotFound(_)
+      | SndFileError::Unseekable { .. } => ErrorKind::Unsupported,
+      SndFileError::MalformedFile(_) => ErrorKind::Malformed,
+      SndFileError::SystemError(_) | SndFileError::IOError(_) => ErrorKind::Io,
+      SndFileError::InvalidParameter(_) => ErrorKind::InvalidInput,
+      SndFileError::Cancelled => ErrorKind::Cancelled,
+      SndFileError::MemoryLimitExceeded { .. } => ErrorKind::LimitExceeded,
+      SndFileError::InternalError(_) | SndFileError::LibraryError { .. } => ErrorKind::Other,
+    }
+  }
+
+  /// Get the raw libsndfile error code, `None` if the error does not come from libsndfile.
+  pub fn code(&self) -> Option<i32> {
+    match self {
+      SndFileError::UnrecognisedFormat(_) => Some(sndfile_sys::SF_ERR_UNRECOGNISED_FORMAT),
+      SndFileError::SystemError(_) => Some(sndfile_sys::SF_ERR_SYSTEM),
+      SndFileError::MalformedFile(_) => Some(sndfile_sys::SF_ERR_MALFORMED_FILE),
+      SndFileError::UnsupportedEncoding(_) => Some(sndfile_sys::SF_ERR_UNSUPPORTED_ENCODING),
+      SndFileError::LibraryError { code, .. } => Some(*code),
+      _ => None,
+    }
+  }
+
+  /// Get the number of frames declared by the header of an unseekable file, see
+  /// `SndFileError::Unseekable`.
+  pub fn len_hint(&self) -> Option<u64> {
+    match self {
+      SndFileError::Unseekable { len_hint } => *len_hint,
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde_features", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde_features", serde(rename_all = "lowercase"))]
 /// Type of tags
 pub enum TagType {
   Title,
@@ -302,6 +794,22 @@ pub enum TagType {
   Genre,
 }
 
+impl TagType {
+  /// All tag types.
+  pub const ALL: [TagType; 10] = [
+    TagType::Title,
+    TagType::Copyright,
+    TagType::Software,
+    TagType::Artist,
+    TagType::Comment,
+    TagType::Date,
+    TagType::Album,
+    TagType::License,
+    TagType::Tracknumber,
+    TagType::Genre,
+  ];
+}
+
 /// Lock it before interacting with a few raw `libsndfile` functions in multithread context.
 ///
 /// Affected functions:
@@ -315,29 +823,34 @@ pub fn get_sf_global_lock() -> &'static Mutex<()> {
 }
 
 fn sf_err_code_to_enum(err_code: c_int) -> SndFileError {
+  sf_err_to_enum(err_code, unsafe { ffi::sf_error_number(err_code) })
+}
+
+/// Build a `SndFileError` from an error code and its message, e.g. from `sf_strerror`.
+fn sf_err_to_enum(err_code: c_int, err_msg: *const std::os::raw::c_char) -> SndFileError {
+  let err_msg = unsafe { std::ffi::CStr::from_ptr(err_msg) }
+    .to_string_lossy()
+    .into_owned();
   match err_code {
     sndfile_sys::SF_ERR_NO_ERROR => panic!("Errrrrrr"),
-    _ => {
-      let err_msg = unsafe {
-        std::ffi::CStr::from_ptr(sndfile_sys::sf_error_number(err_code))
-          .to_str()
-          .unwrap()
-      }
-      .to_string();
-      match err_code {
-        sndfile_sys::SF_ERR_UNRECOGNISED_FORMAT => SndFileError::UnrecognisedFormat(err_msg),
-        sndfile_sys::SF_ERR_SYSTEM => SndFileError::SystemError(err_msg),
-        sndfile_sys::SF_ERR_MALFORMED_FILE => SndFileError::MalformedFile(err_msg),
-        sndfile_sys::SF_ERR_UNSUPPORTED_ENCODING => SndFileError::UnsupportedEncoding(err_msg),
-        _ => SndFileError::InternalError(err_msg),
-      }
-    }
+    sndfile_sys::SF_ERR_UNRECOGNISED_FORMAT => SndFileError::UnrecognisedFormat(err_msg),
+    sndfile_sys::SF_ERR_SYSTEM => SndFileError::SystemError(err_msg),
+    sndfile_sys::SF_ERR_MALFORMED_FILE => SndFileError::MalformedFile(err_msg),
+    sndfile_sys::SF_ERR_UNSUPPORTED_ENCODING => SndFileError::UnsupportedEncoding(err_msg),
+    _ => SndFileError::LibraryError {
+      code: err_code,
+      message: err_msg,
+    },
   }
 }
 
 impl OpenOptions {
   /// Open from path
+  ///
+  /// On Windows, the file is opened by libsndfile itself with `sf_wchar_open`, which handles
+  /// Unicode paths natively and bypasses the virtual I/O callbacks.
   pub fn from_path<P: AsRef<Path>>(&self, path: P) -> Result<SndFile, SndFileError> {
+    let path = path.as_ref();
     let file_obj = match self {
       Self::ReadOnly(_) => std::fs::OpenOptions::new().read(true).open(path),
       Self::WriteOnly(_) => std::fs::OpenOptions::new()
@@ -356,11 +869,48 @@ impl OpenOptions {
         .open(path),
     }
     .map_err(|e| SndFileError::IOError(e))?;
-    self.from_file(file_obj)
+    #[cfg(windows)]
+    let snd = {
+      use std::os::windows::ffi::OsStrExt;
+      let wpath: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+      // `file_obj` is kept for reading metadata libsndfile does not expose, e.g. Vorbis comments.
+      self.open_with(VIOStream::File(file_obj), |mode, sf_info, _, _| unsafe {
+        ffi::sf_wchar_open(wpath.as_ptr(), mode, sf_info)
+      })
+    };
+    #[cfg(not(windows))]
+    let snd = self.from_file(file_obj);
+    snd.map(|x| x.with_source(SourceKind::Path, Some(path)))
   }
 
   /// Open from file
   pub fn from_file(&self, f: File) -> Result<SndFile, SndFileError> {
+    self.open_stream(VIOStream::File(f))
+  }
+
+  /// Open a `SndFile` backed by `f` through the virtual I/O callbacks.
+  fn open_stream(&self, f: VIOStream) -> Result<SndFile, SndFileError> {
+    self.open_with(f, |mode, sf_info, vio_ptr, vio_user_ptr| unsafe {
+      ffi::sf_open_virtual(vio_ptr, mode, sf_info, vio_user_ptr as *mut c_void)
+    })
+  }
+
+  /// Open a `SndFile` backed by `f`, the libsndfile handle is created by
+  /// `open(mode, sf_info, vio_ptr, vio_user_ptr)`.
+  fn open_with<F>(&self, f: VIOStream, open: F) -> Result<SndFile, SndFileError>
+  where
+    F: FnOnce(
+      c_int,
+      *mut sndfile_sys::SF_INFO,
+      *mut sndfile_sys::SF_VIRTUAL_IO,
+      *mut VIOFile,
+    ) -> *mut sndfile_sys::SNDFILE,
+  {
+    load_library()?;
     let sf_open_mode = match self {
       Self::ReadOnly(_) => sndfile_sys::SFM_READ,
       Self::WriteOnly(_) => sndfile_sys::SFM_WRITE,
@@ -395,102 +945,112 @@ impl OpenOptions {
         seekable: 0,
       },
     };
-    let vio_ptr = Box::into_raw(Box::new(sndfile_sys::SF_VIRTUAL_IO {
-      get_filelen: vio_get_filelen,
-      seek: vio_seek,
-      read: vio_read,
-      write: vio_write,
-      tell: vio_tell,
+    // libsndfile copies the callbacks and never writes through this pointer.
+    let vio_ptr = &VIRTUAL_IO as *const sndfile_sys::SF_VIRTUAL_IO as *mut _;
+    let vio_user_ptr = Box::into_raw(Box::new(VIOFile {
+      f,
+      mode: sf_open_mode,
     }));
-    let vio_user_ptr = Box::into_raw(Box::new(VIOFile { f }));
     {
-      let _sf_global_lock_guard = SF_GLOBAL_LOCK.lock();
-      let sndfile_ptr = unsafe {
-        sndfile_sys::sf_open_virtual(
-          vio_ptr,
+      let _sf_global_lock_guard = lock_sf_global();
+      let sndfile_ptr = {
+        let mut timer = OpTimer::new("open", std::ptr::null_mut());
+        let sndfile_ptr = open(
           sf_open_mode,
           &mut sf_info as *mut sndfile_sys::SF_INFO,
-          vio_user_ptr as *mut c_void,
-        )
+          vio_ptr,
+          vio_user_ptr,
+        );
+        timer.set_file(sndfile_ptr);
+        sndfile_ptr
       };
       if sndfile_ptr.is_null() {
-        unsafe {
-          Box::from_raw(vio_user_ptr);
-          Box::from_raw(vio_ptr);
-        }
-        Err(sf_err_code_to_enum(unsafe {
-          sndfile_sys::sf_error(sndfile_ptr)
-        }))
+        drop(unsafe { Box::from_raw(vio_user_ptr) });
+        // `sf_error(NULL)` and `sf_strerror(NULL)` are guarded by the global lock.
+        Err(unsafe { sf_err_to_enum(ffi::sf_error(sndfile_ptr), ffi::sf_strerror(sndfile_ptr)) })
       } else {
         let u = UnsafeSndFile {
-          vio_ptr,
           vio_user_ptr,
           sndfile_ptr,
         };
-
-        if sf_info.frames < 0 {
-          Err(SndFileError::InvalidParameter(
-            "Got invalid frame count, expect a non-negative number.".to_string(),
-          ))
-        } else if sf_info.samplerate <= 0 {
-          Err(SndFileError::InvalidParameter(
-            "Got invalid samplerate, expect a positive number.".to_string(),
-          ))
-        } else if sf_info.channels <= 0 {
-          Err(SndFileError::InvalidParameter(
-            "Got invalid channels, expect a positive number.".to_string(),
-          ))
-        } else {
-          let major_format = format::flags_to_major_format(sf_info.format);
-          let subtype_format = format::flags_to_subtype_format(sf_info.format);
-          let endian_format = format::flags_to_endian(sf_info.format);
-          if major_format.is_none() || subtype_format.is_none() || endian_format.is_none() {
-            Err(SndFileError::InvalidParameter(
-              "Got invalid format flags.".to_string(),
-            ))
-          } else {
-            unsafe {
-              sndfile_sys::sf_command(
-                u.sndfile_ptr,
-                sndfile_sys::SFC_SET_SCALE_FLOAT_INT_READ,
-                std::ptr::null_mut(),
-                sndfile_sys::SF_TRUE,
-              )
-            };
-            unsafe {
-              sndfile_sys::sf_command(
-                u.sndfile_ptr,
-                sndfile_sys::SFC_SET_SCALE_INT_FLOAT_WRITE,
-                std::ptr::null_mut(),
-                sndfile_sys::SF_TRUE,
-              )
-            };
-            Ok(SndFile {
-              unsafe_fields: u,
-              samplerate: sf_info.samplerate as usize,
-              channels: sf_info.channels as usize,
-              major_format: major_format.unwrap(),
-              subtype_format: subtype_format.unwrap(),
-              endian: endian_format.unwrap(),
-              seekable: sf_info.seekable != sndfile_sys::SF_FALSE,
-            })
+        let mut snd = SndFile::from_parts(u, &sf_info)?;
+        unsafe {
+          ffi::sf_command(
+            snd.unsafe_fields.sndfile_ptr,
+            sndfile_sys::SFC_SET_SCALE_FLOAT_INT_READ,
+            std::ptr::null_mut(),
+            sndfile_sys::SF_TRUE,
+          )
+        };
+        unsafe {
+          ffi::sf_command(
+            snd.unsafe_fields.sndfile_ptr,
+            sndfile_sys::SFC_SET_SCALE_INT_FLOAT_WRITE,
+            std::ptr::null_mut(),
+            sndfile_sys::SF_TRUE,
+          )
+        };
+        if let OpenOptions::WriteOnly(x) | OpenOptions::WriteRead(x) = self {
+          if let Some(mask) = x.channel_mask {
+            snd.set_channel_mask(mask)?;
           }
+          snd.set_tags(x.tags.iter().map(|(t, v)| (*t, v.as_str())))?;
         }
+        Ok(snd)
       }
     }
   }
 }
 
+fn check_sf_info(sf_info: &sndfile_sys::SF_INFO) -> Result<Endian, SndFileError> {
+  if sf_info.frames < 0 {
+    Err(SndFileError::InvalidParameter(
+      "Got invalid frame count, expect a non-negative number.".to_string(),
+    ))
+  } else if sf_info.samplerate <= 0 {
+    Err(SndFileError::InvalidParameter(
+      "Got invalid samplerate, expect a positive number.".to_string(),
+    ))
+  } else if sf_info.channels <= 0 {
+    Err(SndFileError::InvalidParameter(
+      "Got invalid channels, expect a positive number.".to_string(),
+    ))
+  } else {
+    format::flags_to_endian(sf_info.format)
+      .ok_or_else(|| SndFileError::InvalidParameter("Got invalid format flags.".to_string()))
+  }
+}
+
+fn get_current_sf_info(sndfile_ptr: *mut sndfile_sys::SNDFILE) -> sndfile_sys::SF_INFO {
+  let mut sf_info = sndfile_sys::SF_INFO {
+    frames: 0,
+    samplerate: 0,
+    channels: 0,
+    format: 0,
+    sections: 0,
+    seekable: 0,
+  };
+  unsafe {
+    ffi::sf_command(
+      sndfile_ptr,
+      sndfile_sys::SFC_GET_CURRENT_SF_INFO,
+      &mut sf_info as *mut sndfile_sys::SF_INFO as *mut c_void,
+      std::mem::size_of::<sndfile_sys::SF_INFO>() as c_int,
+    )
+  };
+  sf_info
+}
+
 impl Drop for UnsafeSndFile {
   fn drop(&mut self) {
-    let err_code = unsafe { sndfile_sys::sf_close(self.sndfile_ptr) };
-    unsafe {
-      Box::from_raw(self.vio_user_ptr);
-      Box::from_raw(self.vio_ptr);
-    }
+    let err_code = {
+      let _timer = OpTimer::new("close", self.sndfile_ptr);
+      unsafe { ffi::sf_close(self.sndfile_ptr) }
+    };
+    drop(unsafe { Box::from_raw(self.vio_user_ptr) });
     if err_code != 0 {
       let err_msg = unsafe {
-        std::ffi::CStr::from_ptr(sndfile_sys::sf_error_number(err_code))
+        std::ffi::CStr::from_ptr(ffi::sf_error_number(err_code))
           .to_str()
           .unwrap()
       };
@@ -499,14 +1059,20 @@ impl Drop for UnsafeSndFile {
   }
 }
 
+/// View the first `len` items of `dst` as initialized.
+///
+/// # Safety
+/// The first `len` items of `dst` must have been initialized.
+unsafe fn assume_init_prefix<T>(dst: &mut [MaybeUninit<T>], len: usize) -> &mut [T] {
+  std::slice::from_raw_parts_mut(dst.as_mut_ptr() as *mut T, len)
+}
+
 impl SndFileIO<i16> for SndFile {
-  fn read_to_slice(&mut self, dst: &mut [i16]) -> Result<usize, ()> {
-    let len = dst.len();
-    let n_ch = self.channels as usize;
-    let n_elem = len / n_ch;
-    assert!(len % n_ch == 0);
+  fn read_to_slice(&mut self, dst: &mut [i16]) -> Result<usize, SndFileError> {
+    let n_elem = self.check_buffer_len(dst.len())?;
+    let _timer = OpTimer::io("read", self.unsafe_fields.sndfile_ptr, n_elem);
     let n = unsafe {
-      sndfile_sys::sf_readf_short(
+      ffi::sf_readf_short(
         self.unsafe_fields.sndfile_ptr,
         dst.as_mut_ptr(),
         n_elem as sf_count_t,
@@ -515,16 +1081,15 @@ impl SndFileIO<i16> for SndFile {
     if n >= 0 {
       Ok(n as usize)
     } else {
-      Err(())
+      Err(self.get_last_error())
     }
   }
 
-  fn write_from_slice(&mut self, src: &[i16]) -> Result<usize, ()> {
-    let len = src.len();
-    let n_ch = self.channels as usize;
-    let n_elem = len / n_ch;
+  fn write_from_slice(&mut self, src: &[i16]) -> Result<usize, SndFileError> {
+    let n_elem = self.check_buffer_len(src.len())?;
+    let _timer = OpTimer::io("write", self.unsafe_fields.sndfile_ptr, n_elem);
     let n = unsafe {
-      sndfile_sys::sf_writef_short(
+      ffi::sf_writef_short(
         self.unsafe_fields.sndfile_ptr,
         src.as_ptr(),
         n_elem as sf_count_t,
@@ -533,25 +1098,82 @@ impl SndFileIO<i16> for SndFile {
     if n >= 0 {
       Ok(n as usize)
     } else {
-      Err(())
+      Err(self.get_last_error())
+    }
+  }
+
+  fn read_items_to_slice(&mut self, dst: &mut [i16]) -> Result<usize, SndFileError> {
+    let n = unsafe {
+      ffi::sf_read_short(
+        self.unsafe_fields.sndfile_ptr,
+        dst.as_mut_ptr(),
+        dst.len() as sf_count_t,
+      )
+    };
+    if n >= 0 {
+      Ok(n as usize)
+    } else {
+      Err(self.get_last_error())
+    }
+  }
+
+  fn write_items_from_slice(&mut self, src: &[i16]) -> Result<usize, SndFileError> {
+    let n = unsafe {
+      ffi::sf_write_short(
+        self.unsafe_fields.sndfile_ptr,
+        src.as_ptr(),
+        src.len() as sf_count_t,
+      )
+    };
+    if n >= 0 {
+      Ok(n as usize)
+    } else {
+      Err(self.get_last_error())
+    }
+  }
+
+  fn read_to_uninit_slice<'a>(
+    &mut self,
+    dst: &'a mut [MaybeUninit<i16>],
+  ) -> Result<&'a mut [i16], SndFileError> {
+    let n_elem = self.check_buffer_len(dst.len())?;
+    let n = unsafe {
+      ffi::sf_readf_short(
+        self.unsafe_fields.sndfile_ptr,
+        dst.as_mut_ptr() as *mut i16,
+        n_elem as sf_count_t,
+      )
+    };
+    if n >= 0 {
+      Ok(unsafe { assume_init_prefix(dst, n as usize * self.channels) })
+    } else {
+      Err(self.get_last_error())
     }
   }
 
-  fn read_all_to_vec(&mut self) -> Result<Vec<i16>, ()> {
-    let n = self.len()? as usize * self.channels;
-    self.seek(SeekFrom::Start(0))?;
-    let mut buf = vec![0; n];
-    self.read_to_slice(&mut buf).map(|_| buf)
+  fn read_all_to_vec(&mut self) -> Result<Vec<i16>, SndFileError> {
+    if !self.is_seekable() {
+      return self.read_to_end(default_read_limit());
+    }
+    self.preserving_cursor(|snd| {
+      let n = snd.check_read_all_len::<i16>(default_read_limit())?;
+      snd.seek(SeekFrom::Start(0))?;
+      let mut buf = Vec::with_capacity(n);
+      let n_read = snd
+        .read_to_uninit_slice(&mut buf.spare_capacity_mut()[..n])?
+        .len();
+      unsafe { buf.set_len(n_read) };
+      Ok(buf)
+    })
   }
 }
 
 impl SndFileIO<i32> for SndFile {
-  fn read_to_slice(&mut self, dst: &mut [i32]) -> Result<usize, ()> {
-    let len = dst.len();
-    let n_ch = self.channels as usize;
-    let n_elem = len / n_ch;
+  fn read_to_slice(&mut self, dst: &mut [i32]) -> Result<usize, SndFileError> {
+    let n_elem = self.check_buffer_len(dst.len())?;
+    let _timer = OpTimer::io("read", self.unsafe_fields.sndfile_ptr, n_elem);
     let n = unsafe {
-      sndfile_sys::sf_readf_int(
+      ffi::sf_readf_int(
         self.unsafe_fields.sndfile_ptr,
         dst.as_mut_ptr(),
         n_elem as sf_count_t,
@@ -560,16 +1182,15 @@ impl SndFileIO<i32> for SndFile {
     if n >= 0 {
       Ok(n as usize)
     } else {
-      Err(())
+      Err(self.get_last_error())
     }
   }
 
-  fn write_from_slice(&mut self, src: &[i32]) -> Result<usize, ()> {
-    let len = src.len();
-    let n_ch = self.channels as usize;
-    let n_elem = len / n_ch;
+  fn write_from_slice(&mut self, src: &[i32]) -> Result<usize, SndFileError> {
+    let n_elem = self.check_buffer_len(src.len())?;
+    let _timer = OpTimer::io("write", self.unsafe_fields.sndfile_ptr, n_elem);
     let n = unsafe {
-      sndfile_sys::sf_writef_int(
+      ffi::sf_writef_int(
         self.unsafe_fields.sndfile_ptr,
         src.as_ptr(),
         n_elem as sf_count_t,
@@ -578,25 +1199,82 @@ impl SndFileIO<i32> for SndFile {
     if n >= 0 {
       Ok(n as usize)
     } else {
-      Err(())
+      Err(self.get_last_error())
+    }
+  }
+
+  fn read_items_to_slice(&mut self, dst: &mut [i32]) -> Result<usize, SndFileError> {
+    let n = unsafe {
+      ffi::sf_read_int(
+        self.unsafe_fields.sndfile_ptr,
+        dst.as_mut_ptr(),
+        dst.len() as sf_count_t,
+      )
+    };
+    if n >= 0 {
+      Ok(n as usize)
+    } else {
+      Err(self.get_last_error())
+    }
+  }
+
+  fn write_items_from_slice(&mut self, src: &[i32]) -> Result<usize, SndFileError> {
+    let n = unsafe {
+      ffi::sf_write_int(
+        self.unsafe_fields.sndfile_ptr,
+        src.as_ptr(),
+        src.len() as sf_count_t,
+      )
+    };
+    if n >= 0 {
+      Ok(n as usize)
+    } else {
+      Err(self.get_last_error())
+    }
+  }
+
+  fn read_to_uninit_slice<'a>(
+    &mut self,
+    dst: &'a mut [MaybeUninit<i32>],
+  ) -> Result<&'a mut [i32], SndFileError> {
+    let n_elem = self.check_buffer_len(dst.len())?;
+    let n = unsafe {
+      ffi::sf_readf_int(
+        self.unsafe_fields.sndfile_ptr,
+        dst.as_mut_ptr() as *mut i32,
+        n_elem as sf_count_t,
+      )
+    };
+    if n >= 0 {
+      Ok(unsafe { assume_init_prefix(dst, n as usize * self.channels) })
+    } else {
+      Err(self.get_last_error())
     }
   }
 
-  fn read_all_to_vec(&mut self) -> Result<Vec<i32>, ()> {
-    let n = self.len()? as usize * self.channels;
-    self.seek(SeekFrom::Start(0))?;
-    let mut buf = vec![0; n];
-    self.read_to_slice(&mut buf).map(|_| buf)
+  fn read_all_to_vec(&mut self) -> Result<Vec<i32>, SndFileError> {
+    if !self.is_seekable() {
+      return self.read_to_end(default_read_limit());
+    }
+    self.preserving_cursor(|snd| {
+      let n = snd.check_read_all_len::<i32>(default_read_limit())?;
+      snd.seek(SeekFrom::Start(0))?;
+      let mut buf = Vec::with_capacity(n);
+      let n_read = snd
+        .read_to_uninit_slice(&mut buf.spare_capacity_mut()[..n])?
+        .len();
+      unsafe { buf.set_len(n_read) };
+      Ok(buf)
+    })
   }
 }
 
 impl SndFileIO<f32> for SndFile {
-  fn read_to_slice(&mut self, dst: &mut [f32]) -> Result<usize, ()> {
-    let len = dst.len();
-    let n_ch = self.channels as usize;
-    let n_elem = len / n_ch;
+  fn read_to_slice(&mut self, dst: &mut [f32]) -> Result<usize, SndFileError> {
+    let n_elem = self.check_buffer_len(dst.len())?;
+    let _timer = OpTimer::io("read", self.unsafe_fields.sndfile_ptr, n_elem);
     let n = unsafe {
-      sndfile_sys::sf_readf_float(
+      ffi::sf_readf_float(
         self.unsafe_fields.sndfile_ptr,
         dst.as_mut_ptr(),
         n_elem as sf_count_t,
@@ -605,16 +1283,15 @@ impl SndFileIO<f32> for SndFile {
     if n >= 0 {
       Ok(n as usize)
     } else {
-      Err(())
+      Err(self.get_last_error())
     }
   }
 
-  fn write_from_slice(&mut self, src: &[f32]) -> Result<usize, ()> {
-    let len = src.len();
-    let n_ch = self.channels as usize;
-    let n_elem = len / n_ch;
+  fn write_from_slice(&mut self, src: &[f32]) -> Result<usize, SndFileError> {
+    let n_elem = self.check_buffer_len(src.len())?;
+    let _timer = OpTimer::io("write", self.unsafe_fields.sndfile_ptr, n_elem);
     let n = unsafe {
-      sndfile_sys::sf_writef_float(
+      ffi::sf_writef_float(
         self.unsafe_fields.sndfile_ptr,
         src.as_ptr(),
         n_elem as sf_count_t,
@@ -623,25 +1300,82 @@ impl SndFileIO<f32> for SndFile {
     if n >= 0 {
       Ok(n as usize)
     } else {
-      Err(())
+      Err(self.get_last_error())
+    }
+  }
+
+  fn read_items_to_slice(&mut self, dst: &mut [f32]) -> Result<usize, SndFileError> {
+    let n = unsafe {
+      ffi::sf_read_float(
+        self.unsafe_fields.sndfile_ptr,
+        dst.as_mut_ptr(),
+        dst.len() as sf_count_t,
+      )
+    };
+    if n >= 0 {
+      Ok(n as usize)
+    } else {
+      Err(self.get_last_error())
     }
   }
 
-  fn read_all_to_vec(&mut self) -> Result<Vec<f32>, ()> {
-    let n = self.len()? as usize * self.channels;
-    self.seek(SeekFrom::Start(0))?;
-    let mut buf = vec![0.0; n];
-    self.read_to_slice(&mut buf).map(|_| buf)
+  fn write_items_from_slice(&mut self, src: &[f32]) -> Result<usize, SndFileError> {
+    let n = unsafe {
+      ffi::sf_write_float(
+        self.unsafe_fields.sndfile_ptr,
+        src.as_ptr(),
+        src.len() as sf_count_t,
+      )
+    };
+    if n >= 0 {
+      Ok(n as usize)
+    } else {
+      Err(self.get_last_error())
+    }
+  }
+
+  fn read_to_uninit_slice<'a>(
+    &mut self,
+    dst: &'a mut [MaybeUninit<f32>],
+  ) -> Result<&'a mut [f32], SndFileError> {
+    let n_elem = self.check_buffer_len(dst.len())?;
+    let n = unsafe {
+      ffi::sf_readf_float(
+        self.unsafe_fields.sndfile_ptr,
+        dst.as_mut_ptr() as *mut f32,
+        n_elem as sf_count_t,
+      )
+    };
+    if n >= 0 {
+      Ok(unsafe { assume_init_prefix(dst, n as usize * self.channels) })
+    } else {
+      Err(self.get_last_error())
+    }
+  }
+
+  fn read_all_to_vec(&mut self) -> Result<Vec<f32>, SndFileError> {
+    if !self.is_seekable() {
+      return self.read_to_end(default_read_limit());
+    }
+    self.preserving_cursor(|snd| {
+      let n = snd.check_read_all_len::<f32>(default_read_limit())?;
+      snd.seek(SeekFrom::Start(0))?;
+      let mut buf = Vec::with_capacity(n);
+      let n_read = snd
+        .read_to_uninit_slice(&mut buf.spare_capacity_mut()[..n])?
+        .len();
+      unsafe { buf.set_len(n_read) };
+      Ok(buf)
+    })
   }
 }
 
 impl SndFileIO<f64> for SndFile {
-  fn read_to_slice(&mut self, dst: &mut [f64]) -> Result<usize, ()> {
-    let len = dst.len();
-    let n_ch = self.channels as usize;
-    let n_elem = len / n_ch;
+  fn read_to_slice(&mut self, dst: &mut [f64]) -> Result<usize, SndFileError> {
+    let n_elem = self.check_buffer_len(dst.len())?;
+    let _timer = OpTimer::io("read", self.unsafe_fields.sndfile_ptr, n_elem);
     let n = unsafe {
-      sndfile_sys::sf_readf_double(
+      ffi::sf_readf_double(
         self.unsafe_fields.sndfile_ptr,
         dst.as_mut_ptr(),
         n_elem as sf_count_t,
@@ -650,16 +1384,15 @@ impl SndFileIO<f64> for SndFile {
     if n >= 0 {
       Ok(n as usize)
     } else {
-      Err(())
+      Err(self.get_last_error())
     }
   }
 
-  fn write_from_slice(&mut self, src: &[f64]) -> Result<usize, ()> {
-    let len = src.len();
-    let n_ch = self.channels as usize;
-    let n_elem = len / n_ch;
+  fn write_from_slice(&mut self, src: &[f64]) -> Result<usize, SndFileError> {
+    let n_elem = self.check_buffer_len(src.len())?;
+    let _timer = OpTimer::io("write", self.unsafe_fields.sndfile_ptr, n_elem);
     let n = unsafe {
-      sndfile_sys::sf_writef_double(
+      ffi::sf_writef_double(
         self.unsafe_fields.sndfile_ptr,
         src.as_ptr(),
         n_elem as sf_count_t,
@@ -668,15 +1401,73 @@ impl SndFileIO<f64> for SndFile {
     if n >= 0 {
       Ok(n as usize)
     } else {
-      Err(())
+      Err(self.get_last_error())
+    }
+  }
+
+  fn read_items_to_slice(&mut self, dst: &mut [f64]) -> Result<usize, SndFileError> {
+    let n = unsafe {
+      ffi::sf_read_double(
+        self.unsafe_fields.sndfile_ptr,
+        dst.as_mut_ptr(),
+        dst.len() as sf_count_t,
+      )
+    };
+    if n >= 0 {
+      Ok(n as usize)
+    } else {
+      Err(self.get_last_error())
+    }
+  }
+
+  fn write_items_from_slice(&mut self, src: &[f64]) -> Result<usize, SndFileError> {
+    let n = unsafe {
+      ffi::sf_write_double(
+        self.unsafe_fields.sndfile_ptr,
+        src.as_ptr(),
+        src.len() as sf_count_t,
+      )
+    };
+    if n >= 0 {
+      Ok(n as usize)
+    } else {
+      Err(self.get_last_error())
     }
   }
 
-  fn read_all_to_vec(&mut self) -> Result<Vec<f64>, ()> {
-    let n = self.len()? as usize * self.channels;
-    self.seek(SeekFrom::Start(0))?;
-    let mut buf = vec![0.0; n];
-    self.read_to_slice(&mut buf).map(|_| buf)
+  fn read_to_uninit_slice<'a>(
+    &mut self,
+    dst: &'a mut [MaybeUninit<f64>],
+  ) -> Result<&'a mut [f64], SndFileError> {
+    let n_elem = self.check_buffer_len(dst.len())?;
+    let n = unsafe {
+      ffi::sf_readf_double(
+        self.unsafe_fields.sndfile_ptr,
+        dst.as_mut_ptr() as *mut f64,
+        n_elem as sf_count_t,
+      )
+    };
+    if n >= 0 {
+      Ok(unsafe { assume_init_prefix(dst, n as usize * self.channels) })
+    } else {
+      Err(self.get_last_error())
+    }
+  }
+
+  fn read_all_to_vec(&mut self) -> Result<Vec<f64>, SndFileError> {
+    if !self.is_seekable() {
+      return self.read_to_end(default_read_limit());
+    }
+    self.preserving_cursor(|snd| {
+      let n = snd.check_read_all_len::<f64>(default_read_limit())?;
+      snd.seek(SeekFrom::Start(0))?;
+      let mut buf = Vec::with_capacity(n);
+      let n_read = snd
+        .read_to_uninit_slice(&mut buf.spare_capacity_mut()[..n])?
+        .len();
+      unsafe { buf.set_len(n_read) };
+      Ok(buf)
+    })
   }
 }
 
@@ -696,6 +1487,67 @@ fn tag_type_to_flags(t: TagType) -> c_int {
 }
 
 impl SndFile {
+  /// Returns the number of frames in a buffer of `len` interleaved samples.
+  fn check_buffer_len(&self, len: usize) -> Result<usize, SndFileError> {
+    if len % self.channels == 0 {
+      Ok(len / self.channels)
+    } else {
+      Err(SndFileError::InvalidParameter(format!(
+        "Got buffer length {}, expect a multiple of channel count {}.",
+        len, self.channels
+      )))
+    }
+  }
+
+  fn from_parts(u: UnsafeSndFile, sf_info: &sndfile_sys::SF_INFO) -> Result<SndFile, SndFileError> {
+    let endian = check_sf_info(sf_info)?;
+    Ok(SndFile {
+      samplerate: sf_info.samplerate as usize,
+      channels: sf_info.channels as usize,
+      major_format: format::flags_to_major_format(sf_info.format),
+      subtype_format: format::flags_to_subtype_format(sf_info.format),
+      endian,
+      seekable: sf_info.seekable != sndfile_sys::SF_FALSE
+        && unsafe { &*u.vio_user_ptr }.is_seekable(),
+      source: match unsafe { &(*u.vio_user_ptr).f } {
+        VIOStream::File(_) => SourceKind::File,
+        VIOStream::Custom(_) => SourceKind::VirtualIo,
+      },
+      path: None,
+      unsafe_fields: u,
+    })
+  }
+
+  /// Record how this file was opened, for `OpenOptions` wrapping another way to open.
+  pub(crate) fn with_source(mut self, source: SourceKind, path: Option<&Path>) -> SndFile {
+    self.source = source;
+    self.path = path.map(Path::to_path_buf);
+    self
+  }
+
+  /// Update the cached fields from `SFC_GET_CURRENT_SF_INFO`.
+  fn sync_info(&mut self) -> Result<sndfile_sys::SF_INFO, SndFileError> {
+    let sf_info = get_current_sf_info(self.unsafe_fields.sndfile_ptr);
+    self.endian = check_sf_info(&sf_info)?;
+    self.samplerate = sf_info.samplerate as usize;
+    self.channels = sf_info.channels as usize;
+    self.major_format = format::flags_to_major_format(sf_info.format);
+    self.subtype_format = format::flags_to_subtype_format(sf_info.format);
+    self.seekable = sf_info.seekable != sndfile_sys::SF_FALSE
+      && unsafe { &*self.unsafe_fields.vio_user_ptr }.is_seekable();
+    Ok(sf_info)
+  }
+
+  fn get_last_error(&self) -> SndFileError {
+    match unsafe { ffi::sf_error(self.unsafe_fields.sndfile_ptr) } {
+      sndfile_sys::SF_ERR_NO_ERROR => SndFileError::InternalError("Unknown error.".to_string()),
+      // `sf_strerror` gives details logged by the handle, e.g. the message of a system error.
+      err_code => sf_err_to_enum(err_code, unsafe {
+        ffi::sf_strerror(self.unsafe_fields.sndfile_ptr)
+      }),
+    }
+  }
+
   /// Get sample rate.
   ///
   /// Return values should be greater than zero.
@@ -727,6 +1579,27 @@ impl SndFile {
     self.endian
   }
 
+  /// Get the actual byte order of the samples in the file, i.e. `Endian::Little` or `Endian::Big`,
+  /// e.g. `Endian::Big` for AIFF files opened with `Endian::File`.
+  ///
+  /// Useful to export raw sample data for other tools, only meaningful for PCM and floating point
+  /// encodings of more than 8 bits.
+  pub fn resolved_endian(&self) -> Endian {
+    let swapped = unsafe {
+      ffi::sf_command(
+        self.unsafe_fields.sndfile_ptr,
+        sndfile_sys::SFC_RAW_DATA_NEEDS_ENDSWAP,
+        std::ptr::null_mut(),
+        0,
+      )
+    } != sndfile_sys::SF_FALSE;
+    if cfg!(target_endian = "little") != swapped {
+      Endian::Little
+    } else {
+      Endian::Big
+    }
+  }
+
   /// Check if this file seekable
   ///
   /// If not, many functions like `len` or `read_all_to_vec` will return an error.
@@ -734,31 +1607,122 @@ impl SndFile {
     self.seekable
   }
 
+  /// Get the path this file was opened from, e.g. for error messages or cache keys.
+  ///
+  /// Returns `None` unless it was opened by `OpenOptions::from_path`, or
+  /// `OpenOptions::from_zip_entry` which gives the path of the archive.
+  pub fn path(&self) -> Option<&Path> {
+    self.path.as_deref()
+  }
+
+  /// Get how this file was opened.
+  pub fn source(&self) -> SourceKind {
+    self.source
+  }
+
   /// Useful if you want to do something unsafe.
   pub fn get_raw_struct(&self) -> &UnsafeSndFile {
     &self.unsafe_fields
   }
 
+  /// Temporarily borrow the raw `SNDFILE*`.
+  ///
+  /// The closure must not close the handle. After the closure returns, cached information like
+  /// samplerate, channels and format are reloaded from libsndfile, so commands changing them are
+  /// reflected by this struct. Returns an error if the reloaded information is invalid.
+  pub fn with_raw<F, R>(&mut self, f: F) -> Result<R, SndFileError>
+  where
+    F: FnOnce(*mut sndfile_sys::SNDFILE) -> R,
+  {
+    let r = f(self.unsafe_fields.sndfile_ptr);
+    self.sync_info().map(|_| r)
+  }
+
+  /// Give up the ownership of underlying handles.
+  ///
+  /// Dropping the returned `UnsafeSndFile` still closes the file.
+  pub fn into_raw(self) -> UnsafeSndFile {
+    self.unsafe_fields
+  }
+
+  /// Close the libsndfile handle and give back the underlying `File`, e.g. to hash the raw bytes
+  /// without reopening the file.
+  ///
+  /// Headers of written files are finalized by closing. The I/O cursor of the returned `File` is
+  /// unspecified, seek before using it. If `sf_close` fails, the `File` is dropped and the error is
+  /// returned, whereas dropping a `SndFile` panics.
+  ///
+  /// Files opened by `OpenOptions::from_virtual_io` are closed, then
+  /// `SndFileError::InvalidParameter` is returned.
+  pub fn close_into_inner(self) -> Result<File, SndFileError> {
+    self.close_into_stream()?.into_file()
+  }
+
+  /// Close the libsndfile handle and give back the underlying stream.
+  pub(crate) fn close_into_stream(self) -> Result<VIOStream, SndFileError> {
+    let u = std::mem::ManuallyDrop::new(self.unsafe_fields);
+    let err_code = {
+      let _timer = OpTimer::new("close", u.sndfile_ptr);
+      unsafe { ffi::sf_close(u.sndfile_ptr) }
+    };
+    let vio_user = unsafe { Box::from_raw(u.vio_user_ptr) };
+    if err_code != 0 {
+      return Err(sf_err_code_to_enum(err_code));
+    }
+    Ok(vio_user.f)
+  }
+
+  /// Rebuild a `SndFile` from handles returned by `SndFile::into_raw`.
+  ///
+  /// Information like samplerate, channels and format are loaded via `SFC_GET_CURRENT_SF_INFO`.
+  ///
+  /// # Safety
+  /// * `sndfile_ptr` must be an open handle created by `sf_open_virtual` with the virtual I/O
+  ///   callbacks of this crate and `vio_user_ptr` as user data, or by `sf_wchar_open` on Windows.
+  /// * `vio_user_ptr` must be allocated by `Box::into_raw`, and not be used elsewhere.
+  ///
+  /// These invariants are always satisfied by the value returned by `SndFile::into_raw`.
+  pub unsafe fn from_raw(u: UnsafeSndFile) -> Result<SndFile, SndFileError> {
+    let sf_info = get_current_sf_info(u.sndfile_ptr);
+    SndFile::from_parts(u, &sf_info)
+  }
+
   /// Get tag string, e.g., artist, album, etc.
+  ///
+  /// Bytes that are not valid UTF-8 are replaced with `U+FFFD`, see `get_tag_raw` for tags with
+  /// legacy encodings.
   pub fn get_tag(&self, t: TagType) -> Option<String> {
-    let s_ptr =
-      unsafe { sndfile_sys::sf_get_string(self.unsafe_fields.sndfile_ptr, tag_type_to_flags(t)) };
-
-    unsafe {
-        if let Some(ptr) = s_ptr.as_ref() {
-            let c_str = std::ffi::CStr::from_ptr(ptr);
-            Some(c_str.to_string_lossy().into_owned())
-        } else {
-            None
-        }
+    self
+      .get_tag_raw(t)
+      .map(|v| String::from_utf8_lossy(&v).into_owned())
+  }
+
+  /// Get tag string as bytes, as stored in the file without any decoding.
+  pub fn get_tag_raw(&self, t: TagType) -> Option<Vec<u8>> {
+    let s_ptr = unsafe { ffi::sf_get_string(self.unsafe_fields.sndfile_ptr, tag_type_to_flags(t)) };
+    if s_ptr.is_null() {
+      None
+    } else {
+      Some(
+        unsafe { std::ffi::CStr::from_ptr(s_ptr) }
+          .to_bytes()
+          .to_vec(),
+      )
     }
   }
 
   /// Set tag string
+  ///
+  /// Returns `SndFileError::InvalidParameter` if `v` contains a NUL character.
   pub fn set_tag(&mut self, t: TagType, v: &str) -> Result<(), SndFileError> {
-    let c_str = std::ffi::CString::new(v).unwrap();
+    let c_str = std::ffi::CString::new(v).map_err(|e| {
+      SndFileError::InvalidParameter(format!(
+        "Got NUL at byte {} of tag value, expect a string without NUL.",
+        e.nul_position()
+      ))
+    })?;
     let ret_code = unsafe {
-      sndfile_sys::sf_set_string(
+      ffi::sf_set_string(
         self.unsafe_fields.sndfile_ptr,
         tag_type_to_flags(t),
         c_str.as_ptr(),
@@ -771,22 +1735,42 @@ impl SndFile {
     }
   }
 
+  /// Get all tag strings set in this file.
+  pub fn get_tags(&self) -> HashMap<TagType, String> {
+    TagType::ALL
+      .iter()
+      .filter_map(|&t| self.get_tag(t).map(|v| (t, v)))
+      .collect()
+  }
+
+  /// Set multiple tag strings, stops at the first failure.
+  pub fn set_tags<'a, I>(&mut self, tags: I) -> Result<(), SndFileError>
+  where
+    I: IntoIterator<Item = (TagType, &'a str)>,
+  {
+    for (t, v) in tags {
+      self.set_tag(t, v)?;
+    }
+    Ok(())
+  }
+
   /// Modify the I/O cursor.
-  pub fn seek(&mut self, pos: SeekFrom) -> Result<u64, ()> {
+  pub fn seek(&mut self, pos: SeekFrom) -> Result<u64, SndFileError> {
     if self.is_seekable() {
+      let _timer = OpTimer::new("seek", self.unsafe_fields.sndfile_ptr);
       let r = unsafe {
         match pos {
-          SeekFrom::Start(x) => sndfile_sys::sf_seek(
+          SeekFrom::Start(x) => ffi::sf_seek(
             self.unsafe_fields.sndfile_ptr,
             x as sf_count_t,
             sndfile_sys::SF_SEEK_SET,
           ),
-          SeekFrom::Current(x) => sndfile_sys::sf_seek(
+          SeekFrom::Current(x) => ffi::sf_seek(
             self.unsafe_fields.sndfile_ptr,
             x as sf_count_t,
             sndfile_sys::SF_SEEK_CUR,
           ),
-          SeekFrom::End(x) => sndfile_sys::sf_seek(
+          SeekFrom::End(x) => ffi::sf_seek(
             self.unsafe_fields.sndfile_ptr,
             x as sf_count_t,
             sndfile_sys::SF_SEEK_END,
@@ -796,18 +1780,146 @@ impl SndFile {
       if r >= 0 {
         Ok(r as u64)
       } else {
-        Err(())
+        Err(self.get_last_error())
+      }
+    } else {
+      Err(self.unseekable())
+    }
+  }
+
+  /// Get the current frame position of the I/O cursor.
+  ///
+  /// In read-write mode, the position of the write cursor is returned and the read cursor is left
+  /// unchanged.
+  pub fn tell(&self) -> Result<u64, SndFileError> {
+    self.cursor_position(sndfile_sys::SFM_WRITE)
+  }
+
+  /// Get the position of the cursor of `mode` (`SFM_READ` or `SFM_WRITE`) in read-write mode, or
+  /// of the only cursor otherwise, without seeking.
+  fn cursor_position(&self, mode: c_int) -> Result<u64, SndFileError> {
+    if !self.is_seekable() {
+      return Err(self.unseekable());
+    }
+    // A plain `SF_SEEK_CUR` moves both cursors to the write cursor in read-write mode.
+    let whence = match unsafe { (*self.unsafe_fields.vio_user_ptr).mode } {
+      sndfile_sys::SFM_RDWR => mode | sndfile_sys::SF_SEEK_CUR,
+      _ => sndfile_sys::SF_SEEK_CUR,
+    };
+    let r = unsafe { ffi::sf_seek(self.unsafe_fields.sndfile_ptr, 0, whence) };
+    if r >= 0 {
+      Ok(r as u64)
+    } else {
+      Err(self.get_last_error())
+    }
+  }
+
+  /// Positions of the write cursor and, in read-write mode, of the read cursor, see
+  /// `restore_cursors`.
+  pub(crate) fn cursors(&self) -> Result<(u64, Option<u64>), SndFileError> {
+    let read = match unsafe { (*self.unsafe_fields.vio_user_ptr).mode } {
+      sndfile_sys::SFM_RDWR => Some(self.cursor_position(sndfile_sys::SFM_READ)?),
+      _ => None,
+    };
+    Ok((self.tell()?, read))
+  }
+
+  /// Seek back to the positions returned by `cursors`.
+  pub(crate) fn restore_cursors(
+    &mut self,
+    cursors: (u64, Option<u64>),
+  ) -> Result<(), SndFileError> {
+    self.seek(SeekFrom::Start(cursors.0))?;
+    if let Some(x) = cursors.1 {
+      let whence = sndfile_sys::SFM_READ | sndfile_sys::SF_SEEK_SET;
+      if unsafe { ffi::sf_seek(self.unsafe_fields.sndfile_ptr, x as sf_count_t, whence) } < 0 {
+        return Err(self.get_last_error());
       }
+    }
+    Ok(())
+  }
+
+  /// Get the length of the underlying file in bytes, including headers and metadata, e.g. to
+  /// compute a compression ratio.
+  pub fn byte_len(&self) -> Result<u64, SndFileError> {
+    unsafe { &*self.unsafe_fields.vio_user_ptr }
+      .byte_len()
+      .map_err(SndFileError::IOError)
+  }
+
+  /// Get the number of frames declared by the header, `None` if unknown, e.g. for a WAV file
+  /// streamed without its final length.
+  ///
+  /// Unlike `len`, it also works for files which are not seekable, but the actual number of frames
+  /// may differ.
+  pub fn len_hint(&self) -> Option<u64> {
+    let frames = get_current_sf_info(self.unsafe_fields.sndfile_ptr).frames;
+    // libsndfile reports `SF_COUNT_MAX` if the length is unknown.
+    if (0..sf_count_t::MAX).contains(&frames) {
+      Some(frames as u64)
     } else {
-      Err(())
+      None
+    }
+  }
+
+  fn unseekable(&self) -> SndFileError {
+    SndFileError::Unseekable {
+      len_hint: self.len_hint(),
     }
   }
 
   /// Get the length of audio file.
   ///
+  /// The I/O cursors are left unchanged, frames written in write or read-write mode are counted.
+  /// Returns `SndFileError::Unseekable` if the file is not seekable.
+  pub fn len(&mut self) -> Result<u64, SndFileError> {
+    if !self.is_seekable() {
+      return Err(self.unseekable());
+    }
+    // The end libsndfile seeks to for `SF_SEEK_END`.
+    Ok(get_current_sf_info(self.unsafe_fields.sndfile_ptr).frames as u64)
+  }
+
+  /// Read encoded audio data from current I/O cursor without decoding, returns the number of bytes
+  /// read if success.
+  ///
+  /// `dst.len()` must be a multiple of the block size of the encoding, e.g. `channels * 2` for
+  /// `SubtypeFormat::PCM_16`, otherwise an error is returned by libsndfile.
+  ///
   /// This function may affect the I/O cursor.
-  pub fn len(&mut self) -> Result<u64, ()> {
-    self.seek(SeekFrom::End(0))
+  pub fn read_raw(&mut self, dst: &mut [u8]) -> Result<usize, SndFileError> {
+    let n = unsafe {
+      ffi::sf_read_raw(
+        self.unsafe_fields.sndfile_ptr,
+        dst.as_mut_ptr() as *mut c_void,
+        dst.len() as sf_count_t,
+      )
+    };
+    if n > 0 || unsafe { ffi::sf_error(self.unsafe_fields.sndfile_ptr) } == 0 {
+      Ok(n as usize)
+    } else {
+      Err(self.get_last_error())
+    }
+  }
+
+  /// Write encoded audio data without encoding, returns the number of bytes written if success.
+  ///
+  /// `src.len()` must be a multiple of the block size of the encoding, see `SndFile::read_raw`.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn write_raw(&mut self, src: &[u8]) -> Result<usize, SndFileError> {
+    let n = unsafe {
+      ffi::sf_write_raw(
+        self.unsafe_fields.sndfile_ptr,
+        src.as_ptr() as *const c_void,
+        src.len() as sf_count_t,
+      )
+    };
+    if n > 0 || unsafe { ffi::sf_error(self.unsafe_fields.sndfile_ptr) } == 0 {
+      Ok(n as usize)
+    } else {
+      Err(self.get_last_error())
+    }
   }
 }
 
@@ -0,0 +1,102 @@
+use super::{SndFile, SndFileError, SndFileIO};
+use std::io::SeekFrom;
+use std::ops::Range;
+
+/// A `SndFile` whose reads wrap seamlessly from the end of a loop to its start, for samplers and
+/// game audio.
+///
+/// Frames before the loop start are read once as an intro, frames after the loop end are never
+/// read.
+#[derive(Debug)]
+pub struct LoopingReader {
+  snd: SndFile,
+  loop_range: Range<u64>,
+  pos: u64,
+}
+
+impl LoopingReader {
+  /// Loop `snd` over the frame range `loop_range`, reading from its current I/O cursor.
+  ///
+  /// `snd` must be seekable and `loop_range` must be a non-empty range within the file.
+  pub fn new(mut snd: SndFile, loop_range: Range<u64>) -> Result<LoopingReader, SndFileError> {
+    // Before `len`, which may move the cursor.
+    let mut pos = snd.tell()?;
+    let len = snd.len()?;
+    if loop_range.start >= loop_range.end || loop_range.end > len {
+      return Err(SndFileError::InvalidParameter(format!(
+        "Got loop range {}..{}, expect a non-empty range within {} frames.",
+        loop_range.start, loop_range.end, len
+      )));
+    }
+    if pos >= loop_range.end {
+      pos = snd.seek(SeekFrom::Start(loop_range.start))?;
+    }
+    Ok(LoopingReader {
+      snd,
+      loop_range,
+      pos,
+    })
+  }
+
+  /// Loop `snd` over the first loop of its instrument chunk (`smpl` of WAV, `INST` of AIFF).
+  ///
+  /// Returns `SndFileError::InvalidParameter` if the file does not have one.
+  pub fn from_instrument_loop(snd: SndFile) -> Result<LoopingReader, SndFileError> {
+    match snd.get_instrument_loop() {
+      Some(loop_range) => LoopingReader::new(snd, loop_range),
+      None => Err(SndFileError::InvalidParameter(
+        "Got a file without loop, expect an instrument chunk with a loop.".to_string(),
+      )),
+    }
+  }
+
+  /// Borrow the underlying `SndFile`.
+  pub fn get_ref(&self) -> &SndFile {
+    &self.snd
+  }
+
+  /// Unwrap the underlying `SndFile`.
+  pub fn into_inner(self) -> SndFile {
+    self.snd
+  }
+
+  /// The looped frame range.
+  pub fn loop_range(&self) -> Range<u64> {
+    self.loop_range.clone()
+  }
+
+  /// Current frame position in the file.
+  pub fn position(&self) -> u64 {
+    self.pos
+  }
+
+  /// Read frames, wrapping at the loop end, returns the number of frames read if success.
+  ///
+  /// `dst` is filled entirely unless the file turns out shorter than the loop end. `dst.len()`
+  /// must be a multiple of the channel count, otherwise `SndFileError::InvalidParameter` is
+  /// returned.
+  pub fn read_to_slice<T>(&mut self, dst: &mut [T]) -> Result<usize, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+  {
+    let channels = self.snd.get_channels();
+    let n_frames = self.snd.check_buffer_len(dst.len())?;
+    let mut done = 0;
+    while done < n_frames {
+      let n_want = ((n_frames - done) as u64).min(self.loop_range.end - self.pos) as usize;
+      let n = self
+        .snd
+        .read_to_slice(&mut dst[done * channels..(done + n_want) * channels])?;
+      if n == 0 {
+        break;
+      }
+      done += n;
+      self.pos += n as u64;
+      if self.pos >= self.loop_range.end {
+        self.pos = self.snd.seek(SeekFrom::Start(self.loop_range.start))?;
+      }
+    }
+    Ok(done)
+  }
+}
@@ -0,0 +1,556 @@
+use super::ffi;
+#[cfg(not(feature = "system_linking"))]
+use super::sys as sndfile_sys;
+use super::{sf_err_code_to_enum, MajorFormat, SndFile, SndFileError};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::os::raw::{c_char, c_int, c_void};
+
+/// Capacity of the coding history, same as `SF_BROADCAST_INFO_16K` of libsndfile.
+const CODING_HISTORY_LEN: usize = 16 * 1024;
+/// Capacity of `SF_CUES`.
+const MAX_CUE_POINTS: usize = 100;
+
+// Layouts from `sndfile.h`, declared here since they are variable sized or opaque in C.
+
+#[repr(C)]
+struct SfBroadcastInfo {
+  description: [c_char; 256],
+  originator: [c_char; 32],
+  originator_reference: [c_char; 32],
+  origination_date: [c_char; 10],
+  origination_time: [c_char; 8],
+  time_reference_low: u32,
+  time_reference_high: u32,
+  version: i16,
+  umid: [c_char; 64],
+  loudness_value: i16,
+  loudness_range: i16,
+  max_true_peak_level: i16,
+  max_momentary_loudness: i16,
+  max_shortterm_loudness: i16,
+  reserved: [c_char; 180],
+  coding_history_size: u32,
+  coding_history: [c_char; CODING_HISTORY_LEN],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SfCuePoint {
+  indx: i32,
+  position: u32,
+  fcc_chunk: i32,
+  chunk_start: i32,
+  block_start: i32,
+  sample_offset: u32,
+  name: [c_char; 256],
+}
+
+#[repr(C)]
+struct SfCues {
+  cue_count: u32,
+  cue_points: [SfCuePoint; MAX_CUE_POINTS],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SfLoop {
+  mode: c_int,
+  start: u32,
+  end: u32,
+  count: u32,
+}
+
+#[repr(C)]
+struct SfInstrument {
+  gain: c_int,
+  basenote: c_char,
+  detune: c_char,
+  velocity_lo: c_char,
+  velocity_hi: c_char,
+  key_lo: c_char,
+  key_hi: c_char,
+  loop_count: c_int,
+  loops: [SfLoop; 16],
+}
+
+/// `SF_LOOP_NONE` of libsndfile, other modes are `SF_LOOP_FORWARD`, `SF_LOOP_BACKWARD` and
+/// `SF_LOOP_ALTERNATING`.
+const SF_LOOP_NONE: c_int = 800;
+
+#[repr(C)]
+pub(crate) struct SfChunkInfo {
+  id: [c_char; 64],
+  id_size: u32,
+  datalen: u32,
+  data: *mut c_void,
+}
+
+pub(crate) enum SfChunkIterator {}
+
+/// Chunk ids written by libsndfile itself, which are skipped by `SndFile::get_chunks`.
+const MANAGED_CHUNK_IDS: [&str; 30] = [
+  "RIFF", "RIFX", "RF64", "WAVE", "fmt ", "data", "fact", "PEAK", "bext", "LIST", "cue ", "smpl",
+  "inst", "acid", "cart", "JUNK", "PAD ", "ds64", "FORM", "AIFF", "AIFC", "COMM", "SSND", "MARK",
+  "INST", "APPL", "NAME", "AUTH", "ANNO", "FVER",
+];
+
+/// Returns `true` if libsndfile can write raw chunks to files of `major_format`.
+pub(crate) fn supports_chunks(major_format: MajorFormat) -> bool {
+  matches!(
+    major_format,
+    MajorFormat::WAV | MajorFormat::WAVEX | MajorFormat::AIFF
+  )
+}
+
+/// Broadcast extension (`bext` chunk) of WAV and RF64 files.
+///
+/// See EBU Tech 3285 for the meaning of each field.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BroadcastInfo {
+  /// At most 256 bytes.
+  pub description: String,
+  /// At most 32 bytes.
+  pub originator: String,
+  /// At most 32 bytes.
+  pub originator_reference: String,
+  /// `yyyy-mm-dd`.
+  pub origination_date: String,
+  /// `hh:mm:ss`.
+  pub origination_time: String,
+  /// Sample count since midnight of the first sample.
+  pub time_reference: u64,
+  pub version: i16,
+  /// At most 64 bytes, trailing zero bytes are dropped when reading.
+  pub umid: Vec<u8>,
+  pub loudness_value: i16,
+  pub loudness_range: i16,
+  pub max_true_peak_level: i16,
+  pub max_momentary_loudness: i16,
+  pub max_shortterm_loudness: i16,
+  pub coding_history: String,
+}
+
+/// Cue point of a `cue ` chunk.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CuePoint {
+  pub id: i32,
+  /// Position in frames.
+  pub position: u32,
+  pub fcc_chunk: i32,
+  pub chunk_start: i32,
+  pub block_start: i32,
+  pub sample_offset: u32,
+  /// At most 255 bytes.
+  pub name: String,
+}
+
+/// Marker of a WAV file, see `SndFile::markers`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Marker {
+  /// Position in frames.
+  pub position: u64,
+  /// Label of the `labl` chunk, empty if not set.
+  pub label: String,
+  /// Comment of the `note` chunk, empty if not set.
+  pub comment: String,
+}
+
+/// Raw chunk of a WAV or AIFF file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+  /// Chunk id, e.g. `iXML`, at most 64 bytes.
+  pub id: String,
+  pub data: Vec<u8>,
+}
+
+fn fixed_to_string(src: &[c_char]) -> String {
+  let bytes: Vec<u8> = src
+    .iter()
+    .take_while(|x| **x != 0)
+    .map(|x| *x as u8)
+    .collect();
+  String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn fill_fixed(dst: &mut [c_char], src: &[u8], name: &str) -> Result<(), SndFileError> {
+  if src.len() > dst.len() {
+    return Err(SndFileError::InvalidParameter(format!(
+      "Got {} bytes for `{}`, expect at most {} bytes.",
+      src.len(),
+      name,
+      dst.len()
+    )));
+  }
+  for (x, y) in dst.iter_mut().zip(src.iter()) {
+    *x = *y as c_char;
+  }
+  Ok(())
+}
+
+impl SndFile {
+  /// Get the broadcast extension, returns `None` if the file does not have one.
+  pub fn get_broadcast_info(&self) -> Option<BroadcastInfo> {
+    let mut raw: Box<SfBroadcastInfo> = Box::new(unsafe { std::mem::zeroed() });
+    let r = unsafe {
+      ffi::sf_command(
+        self.unsafe_fields.sndfile_ptr,
+        sndfile_sys::SFC_GET_BROADCAST_INFO,
+        &mut *raw as *mut SfBroadcastInfo as *mut c_void,
+        std::mem::size_of::<SfBroadcastInfo>() as c_int,
+      )
+    };
+    if r != sndfile_sys::SF_TRUE {
+      return None;
+    }
+    let history_len = (raw.coding_history_size as usize).min(CODING_HISTORY_LEN);
+    Some(BroadcastInfo {
+      description: fixed_to_string(&raw.description),
+      originator: fixed_to_string(&raw.originator),
+      originator_reference: fixed_to_string(&raw.originator_reference),
+      origination_date: fixed_to_string(&raw.origination_date),
+      origination_time: fixed_to_string(&raw.origination_time),
+      time_reference: (raw.time_reference_high as u64) << 32 | raw.time_reference_low as u64,
+      version: raw.version,
+      umid: {
+        let len = raw.umid.iter().rposition(|x| *x != 0).map_or(0, |i| i + 1);
+        raw.umid[..len].iter().map(|x| *x as u8).collect()
+      },
+      loudness_value: raw.loudness_value,
+      loudness_range: raw.loudness_range,
+      max_true_peak_level: raw.max_true_peak_level,
+      max_momentary_loudness: raw.max_momentary_loudness,
+      max_shortterm_loudness: raw.max_shortterm_loudness,
+      coding_history: fixed_to_string(&raw.coding_history[..history_len]),
+    })
+  }
+
+  /// Set the broadcast extension, only supported by WAV and RF64 files opened for writing.
+  pub fn set_broadcast_info(&mut self, info: &BroadcastInfo) -> Result<(), SndFileError> {
+    let mut raw: Box<SfBroadcastInfo> = Box::new(unsafe { std::mem::zeroed() });
+    fill_fixed(
+      &mut raw.description,
+      info.description.as_bytes(),
+      "description",
+    )?;
+    fill_fixed(
+      &mut raw.originator,
+      info.originator.as_bytes(),
+      "originator",
+    )?;
+    fill_fixed(
+      &mut raw.originator_reference,
+      info.originator_reference.as_bytes(),
+      "originator_reference",
+    )?;
+    fill_fixed(
+      &mut raw.origination_date,
+      info.origination_date.as_bytes(),
+      "origination_date",
+    )?;
+    fill_fixed(
+      &mut raw.origination_time,
+      info.origination_time.as_bytes(),
+      "origination_time",
+    )?;
+    fill_fixed(&mut raw.umid, &info.umid, "umid")?;
+    // libsndfile rejects a coding history filling the whole buffer.
+    fill_fixed(
+      &mut raw.coding_history[..CODING_HISTORY_LEN - 1],
+      info.coding_history.as_bytes(),
+      "coding_history",
+    )?;
+    raw.time_reference_low = info.time_reference as u32;
+    raw.time_reference_high = (info.time_reference >> 32) as u32;
+    raw.version = info.version;
+    raw.loudness_value = info.loudness_value;
+    raw.loudness_range = info.loudness_range;
+    raw.max_true_peak_level = info.max_true_peak_level;
+    raw.max_momentary_loudness = info.max_momentary_loudness;
+    raw.max_shortterm_loudness = info.max_shortterm_loudness;
+    raw.coding_history_size = info.coding_history.len() as u32;
+
+    let size = std::mem::size_of::<SfBroadcastInfo>() - CODING_HISTORY_LEN
+      + raw.coding_history_size as usize;
+    let r = unsafe {
+      ffi::sf_command(
+        self.unsafe_fields.sndfile_ptr,
+        sndfile_sys::SFC_SET_BROADCAST_INFO,
+        &mut *raw as *mut SfBroadcastInfo as *mut c_void,
+        size as c_int,
+      )
+    };
+    if r == sndfile_sys::SF_TRUE {
+      Ok(())
+    } else {
+      Err(SndFileError::InvalidParameter(
+        "Broadcast info is rejected by libsndfile.".to_string(),
+      ))
+    }
+  }
+
+  /// Get all cue points, returns an empty `Vec` if the file does not have any.
+  pub fn get_cues(&self) -> Vec<CuePoint> {
+    let mut raw: Box<SfCues> = Box::new(unsafe { std::mem::zeroed() });
+    let r = unsafe {
+      ffi::sf_command(
+        self.unsafe_fields.sndfile_ptr,
+        sndfile_sys::SFC_GET_CUE,
+        &mut *raw as *mut SfCues as *mut c_void,
+        std::mem::size_of::<SfCues>() as c_int,
+      )
+    };
+    if r != sndfile_sys::SF_TRUE {
+      return Vec::new();
+    }
+    raw.cue_points[..(raw.cue_count as usize).min(MAX_CUE_POINTS)]
+      .iter()
+      .map(|x| CuePoint {
+        id: x.indx,
+        position: x.position,
+        fcc_chunk: x.fcc_chunk,
+        chunk_start: x.chunk_start,
+        block_start: x.block_start,
+        sample_offset: x.sample_offset,
+        name: fixed_to_string(&x.name),
+      })
+      .collect()
+  }
+
+  /// Set all cue points, at most 100 cue points are supported.
+  ///
+  /// Cue points must be set before writing any frame.
+  pub fn set_cues(&mut self, cues: &[CuePoint]) -> Result<(), SndFileError> {
+    if cues.len() > MAX_CUE_POINTS {
+      return Err(SndFileError::InvalidParameter(format!(
+        "Got {} cue points, expect at most {}.",
+        cues.len(),
+        MAX_CUE_POINTS
+      )));
+    }
+    let mut raw: Box<SfCues> = Box::new(unsafe { std::mem::zeroed() });
+    raw.cue_count = cues.len() as u32;
+    for (dst, src) in raw.cue_points.iter_mut().zip(cues.iter()) {
+      dst.indx = src.id;
+      dst.position = src.position;
+      dst.fcc_chunk = src.fcc_chunk;
+      dst.chunk_start = src.chunk_start;
+      dst.block_start = src.block_start;
+      dst.sample_offset = src.sample_offset;
+      // Keep the name NUL terminated.
+      fill_fixed(&mut dst.name[..255], src.name.as_bytes(), "name")?;
+    }
+    let r = unsafe {
+      ffi::sf_command(
+        self.unsafe_fields.sndfile_ptr,
+        sndfile_sys::SFC_SET_CUE,
+        &mut *raw as *mut SfCues as *mut c_void,
+        std::mem::size_of::<SfCues>() as c_int,
+      )
+    };
+    if r == sndfile_sys::SF_TRUE {
+      Ok(())
+    } else {
+      Err(self.get_last_error())
+    }
+  }
+
+  /// Load all chunks whose id satisfies `filter`.
+  fn read_chunks<F: Fn(&str) -> bool>(&self, filter: F) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut it =
+      unsafe { ffi::sf_get_chunk_iterator(self.unsafe_fields.sndfile_ptr, std::ptr::null()) };
+    while !it.is_null() {
+      let mut dummy = 0u8;
+      let mut info = SfChunkInfo {
+        id: [0; 64],
+        id_size: 0,
+        datalen: 0,
+        data: &mut dummy as *mut u8 as *mut c_void,
+      };
+      // `sf_get_chunk_size` does not report the id, read zero bytes of data to get it first.
+      let r = unsafe { ffi::sf_get_chunk_data(it, &mut info) };
+      let id = fixed_to_string(&info.id[..(info.id_size as usize).min(64)]);
+      if r == 0 && filter(&id) {
+        let r = unsafe { ffi::sf_get_chunk_size(it, &mut info) };
+        if r == 0 {
+          let mut data = vec![0u8; info.datalen as usize];
+          info.data = data.as_mut_ptr() as *mut c_void;
+          if unsafe { ffi::sf_get_chunk_data(it, &mut info) } == 0 {
+            chunks.push(Chunk { id, data });
+          }
+        }
+      }
+      it = unsafe { ffi::sf_next_chunk_iterator(it) };
+    }
+    chunks
+  }
+
+  /// List ids and sizes in bytes of all chunks, without loading them.
+  pub(crate) fn get_chunk_sizes(&self) -> Vec<(String, u64)> {
+    let mut out = Vec::new();
+    let mut it =
+      unsafe { ffi::sf_get_chunk_iterator(self.unsafe_fields.sndfile_ptr, std::ptr::null()) };
+    while !it.is_null() {
+      let mut dummy = 0u8;
+      let mut info = SfChunkInfo {
+        id: [0; 64],
+        id_size: 0,
+        datalen: 0,
+        data: &mut dummy as *mut u8 as *mut c_void,
+      };
+      if unsafe { ffi::sf_get_chunk_data(it, &mut info) } == 0 {
+        let id = fixed_to_string(&info.id[..(info.id_size as usize).min(64)]);
+        if unsafe { ffi::sf_get_chunk_size(it, &mut info) } == 0 {
+          out.push((id, info.datalen as u64));
+        }
+      }
+      it = unsafe { ffi::sf_next_chunk_iterator(it) };
+    }
+    out
+  }
+
+  /// Get the first loop of the instrument chunk (`smpl` of WAV, `INST` of AIFF) as a frame range,
+  /// returns `None` if the file does not have one.
+  pub(crate) fn get_instrument_loop(&self) -> Option<Range<u64>> {
+    let mut raw: SfInstrument = unsafe { std::mem::zeroed() };
+    let r = unsafe {
+      ffi::sf_command(
+        self.unsafe_fields.sndfile_ptr,
+        sndfile_sys::SFC_GET_INSTRUMENT,
+        &mut raw as *mut SfInstrument as *mut c_void,
+        std::mem::size_of::<SfInstrument>() as c_int,
+      )
+    };
+    if r != sndfile_sys::SF_TRUE {
+      return None;
+    }
+    raw.loops[..(raw.loop_count.max(0) as usize).min(raw.loops.len())]
+      .iter()
+      .find(|x| x.mode != SF_LOOP_NONE && x.start < x.end)
+      .map(|x| x.start as u64..x.end as u64)
+  }
+
+  /// Get all raw chunks of a WAV or AIFF file, except chunks managed by libsndfile itself like
+  /// `fmt `, `data` or `bext`.
+  ///
+  /// Chunks are loaded into memory.
+  pub fn get_chunks(&self) -> Vec<Chunk> {
+    self.read_chunks(|id| !MANAGED_CHUNK_IDS.contains(&id))
+  }
+
+  /// Get the `LIST` chunks of type `adtl`, which hold labels of cue points.
+  pub(crate) fn get_adtl_chunks(&self) -> Vec<Chunk> {
+    let mut chunks = self.read_chunks(|id| id == "LIST");
+    chunks.retain(|x| x.data.starts_with(b"adtl"));
+    chunks
+  }
+
+  /// Get all markers of a WAV file, combining cue points with their labels and notes.
+  pub fn markers(&self) -> Vec<Marker> {
+    let mut labels = HashMap::new();
+    let mut notes = HashMap::new();
+    for chunk in self.get_adtl_chunks() {
+      let mut rest = &chunk.data[4..];
+      while rest.len() >= 8 {
+        let size = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+        let body = &rest[8..(8 + size).min(rest.len())];
+        if body.len() >= 4 {
+          let id = i32::from_le_bytes([body[0], body[1], body[2], body[3]]);
+          let text = body[4..]
+            .iter()
+            .take_while(|x| **x != 0)
+            .cloned()
+            .collect::<Vec<u8>>();
+          let text = String::from_utf8_lossy(&text).into_owned();
+          match &rest[..4] {
+            b"labl" => {
+              labels.insert(id, text);
+            }
+            b"note" => {
+              notes.insert(id, text);
+            }
+            _ => (),
+          }
+        }
+        rest = &rest[(8 + size + (size & 1)).min(rest.len())..];
+      }
+    }
+    self
+      .get_cues()
+      .into_iter()
+      .map(|x| Marker {
+        position: x.position as u64,
+        label: labels.remove(&x.id).unwrap_or_default(),
+        comment: notes.remove(&x.id).unwrap_or_default(),
+      })
+      .collect()
+  }
+
+  /// Set all markers of a WAV file opened for writing, writing both cue points and an `adtl` list
+  /// with their labels and notes. At most 100 markers are supported.
+  ///
+  /// Markers must be set before writing any frame.
+  pub fn set_markers(&mut self, markers: &[Marker]) -> Result<(), SndFileError> {
+    let mut cues = Vec::with_capacity(markers.len());
+    let mut adtl = b"adtl".to_vec();
+    for (i, marker) in markers.iter().enumerate() {
+      if marker.position > u32::MAX as u64 {
+        return Err(SndFileError::InvalidParameter(format!(
+          "Got marker position {}, expect at most {}.",
+          marker.position,
+          u32::MAX
+        )));
+      }
+      let id = i as i32 + 1;
+      cues.push(CuePoint {
+        id,
+        position: marker.position as u32,
+        fcc_chunk: i32::from_le_bytes(*b"data"),
+        sample_offset: marker.position as u32,
+        ..Default::default()
+      });
+      for (tag, text) in [(b"labl", &marker.label), (b"note", &marker.comment)].iter() {
+        if text.is_empty() {
+          continue;
+        }
+        let size = 4 + text.len() + 1;
+        adtl.extend_from_slice(*tag);
+        adtl.extend_from_slice(&(size as u32).to_le_bytes());
+        adtl.extend_from_slice(&id.to_le_bytes());
+        adtl.extend_from_slice(text.as_bytes());
+        adtl.push(0);
+        if size & 1 == 1 {
+          adtl.push(0);
+        }
+      }
+    }
+    self.set_cues(&cues)?;
+    if adtl.len() > 4 {
+      self.add_chunk(&Chunk {
+        id: "LIST".to_string(),
+        data: adtl,
+      })?;
+    }
+    Ok(())
+  }
+
+  /// Add a raw chunk to a WAV or AIFF file opened for writing.
+  ///
+  /// Chunks must be added before writing any frame.
+  pub fn add_chunk(&mut self, chunk: &Chunk) -> Result<(), SndFileError> {
+    let mut info = SfChunkInfo {
+      id: [0; 64],
+      id_size: chunk.id.len() as u32,
+      datalen: chunk.data.len() as u32,
+      data: chunk.data.as_ptr() as *mut c_void,
+    };
+    fill_fixed(&mut info.id, chunk.id.as_bytes(), "id")?;
+    let r = unsafe { ffi::sf_set_chunk(self.unsafe_fields.sndfile_ptr, &info) };
+    if r == 0 {
+      Ok(())
+    } else {
+      Err(sf_err_code_to_enum(r))
+    }
+  }
+}
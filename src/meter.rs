@@ -0,0 +1,163 @@
+use super::{Levels, Sample, SndFile, SndFileError, SndFileIO};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Receives the levels of each block written through a `MeteredWriter`.
+///
+/// Implemented by `PeakMeter` and by closures `FnMut(frames, peak, rms)`.
+pub trait WriteMeter {
+  /// Called after `frames` frames are written, with the peak absolute value and RMS of each
+  /// channel of those frames, normalized like `Sample`.
+  fn update(&mut self, frames: u64, peak: &[f64], rms: &[f64]);
+}
+
+impl<F: FnMut(u64, &[f64], &[f64])> WriteMeter for F {
+  fn update(&mut self, frames: u64, peak: &[f64], rms: &[f64]) {
+    self(frames, peak, rms)
+  }
+}
+
+#[derive(Debug)]
+struct PeakState {
+  peak: Vec<f64>,
+  sum_sq: Vec<f64>,
+  frames: u64,
+}
+
+/// A `WriteMeter` accumulating levels until they are taken, e.g. by a UI thread refreshing input
+/// meters. Clones share the same levels.
+#[derive(Debug, Clone)]
+pub struct PeakMeter(Arc<Mutex<PeakState>>);
+
+impl PeakMeter {
+  /// Create a meter of `channels` channels.
+  pub fn new(channels: usize) -> PeakMeter {
+    PeakMeter(Arc::new(Mutex::new(PeakState {
+      peak: vec![0.0; channels],
+      sum_sq: vec![0.0; channels],
+      frames: 0,
+    })))
+  }
+
+  /// Take peak and RMS of each channel of all frames written since the last call, and reset them.
+  pub fn take(&self) -> Levels {
+    let mut state = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+    let frames = state.frames;
+    let rms = state
+      .sum_sq
+      .iter()
+      .map(|x| {
+        if frames > 0 {
+          (x / frames as f64).sqrt()
+        } else {
+          0.0
+        }
+      })
+      .collect();
+    let peak = state.peak.clone();
+    state.peak.iter_mut().for_each(|x| *x = 0.0);
+    state.sum_sq.iter_mut().for_each(|x| *x = 0.0);
+    state.frames = 0;
+    Levels {
+      peak,
+      rms,
+      #[cfg(feature = "loudness")]
+      integrated_lufs: None,
+    }
+  }
+}
+
+impl WriteMeter for PeakMeter {
+  fn update(&mut self, frames: u64, peak: &[f64], rms: &[f64]) {
+    let mut state = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+    let state = &mut *state;
+    for (x, y) in state.peak.iter_mut().zip(peak.iter()) {
+      *x = x.max(*y);
+    }
+    for (x, y) in state.sum_sq.iter_mut().zip(rms.iter()) {
+      *x += y * y * frames as f64;
+    }
+    state.frames += frames;
+  }
+}
+
+/// A `SndFile` opened for writing, reporting the levels of written frames to a `WriteMeter`.
+///
+/// Levels are computed from the frames libsndfile accepted, so meters reflect exactly what is
+/// written to disk.
+///
+/// ```ignore
+/// let meter = PeakMeter::new(2);
+/// let mut writer = MeteredWriter::new(snd, meter.clone());
+/// writer.write_from_slice(&captured)?;
+/// // In the UI thread:
+/// let levels = meter.take();
+/// ```
+#[derive(Debug)]
+pub struct MeteredWriter<M> {
+  snd: SndFile,
+  meter: M,
+  peak: Vec<f64>,
+  rms: Vec<f64>,
+}
+
+impl<M: WriteMeter> MeteredWriter<M> {
+  /// Wrap `snd`, which should be opened for writing.
+  pub fn new(snd: SndFile, meter: M) -> MeteredWriter<M> {
+    let channels = snd.get_channels();
+    MeteredWriter {
+      snd,
+      meter,
+      peak: vec![0.0; channels],
+      rms: vec![0.0; channels],
+    }
+  }
+
+  /// Borrow the underlying `SndFile`.
+  pub fn get_ref(&self) -> &SndFile {
+    &self.snd
+  }
+
+  /// Borrow the meter.
+  pub fn meter(&self) -> &M {
+    &self.meter
+  }
+
+  /// Mutably borrow the meter.
+  pub fn meter_mut(&mut self) -> &mut M {
+    &mut self.meter
+  }
+
+  /// Unwrap the underlying `SndFile` and the meter.
+  pub fn into_inner(self) -> (SndFile, M) {
+    (self.snd, self.meter)
+  }
+
+  /// Write frames and update the meter, returns the number of frames written if success.
+  ///
+  /// See `SndFileIO::write_from_slice`.
+  pub fn write_from_slice<T>(&mut self, src: &[T]) -> Result<usize, SndFileError>
+  where
+    T: 'static + Default + Sample,
+    SndFile: SndFileIO<T>,
+  {
+    let n = self.snd.write_from_slice(src)?;
+    if n > 0 {
+      let channels = self.peak.len();
+      self.peak.iter_mut().for_each(|x| *x = 0.0);
+      self.rms.iter_mut().for_each(|x| *x = 0.0);
+      for frame in src[..n * channels].chunks_exact(channels) {
+        for (i, x) in frame.iter().enumerate() {
+          let x = x.to_normalized();
+          self.peak[i] = self.peak[i].max(x.abs());
+          self.rms[i] += x * x;
+        }
+      }
+      self
+        .rms
+        .iter_mut()
+        .for_each(|x| *x = (*x / n as f64).sqrt());
+      self.meter.update(n as u64, &self.peak, &self.rms);
+    }
+    Ok(n)
+  }
+}
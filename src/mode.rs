@@ -0,0 +1,372 @@
+use super::{
+  Command, OpenOptions, ReadOptions, Sample, SndFile, SndFileError, SndFileIO, TagType,
+  WriteOptions,
+};
+use std::fs::File;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Frames, peak level and timing of the frames written so far.
+#[derive(Debug, Default)]
+struct WriteTally {
+  frames: u64,
+  peak: f64,
+  elapsed: Duration,
+  /// Clipped samples of each channel, if counted.
+  clipping: Option<Vec<ClippedChannel>>,
+  /// Whether the subtype clips samples out of `[-1.0, 1.0]`.
+  clips: bool,
+  /// Whether writing samples which would be clipped is an error.
+  strict: bool,
+}
+
+impl WriteTally {
+  /// Check that no sample of `src` would be clipped, in strict mode.
+  fn check_range<T: Sample>(&self, src: &[T], channels: usize) -> Result<(), SndFileError> {
+    if !(self.strict && self.clips) {
+      return Ok(());
+    }
+    match src.iter().position(|x| x.to_normalized().abs() > 1.0) {
+      Some(i) => Err(SndFileError::InvalidParameter(format!(
+        "Got sample {} in frame {}, expect samples in [-1.0, 1.0].",
+        src[i].to_normalized(),
+        self.frames + (i / channels) as u64
+      ))),
+      None => Ok(()),
+    }
+  }
+
+  /// Account `n_frames` frames written from `written` in `elapsed`.
+  fn record<T: Sample>(&mut self, written: &[T], n_frames: u64, elapsed: Duration) {
+    let offset = self.frames;
+    self.frames += n_frames;
+    self.elapsed += elapsed;
+    for x in written {
+      self.peak = self.peak.max(x.to_normalized().abs());
+    }
+    match self.clipping.as_mut() {
+      Some(clipping) if self.clips => {
+        let channels = clipping.len();
+        for (i, x) in written.iter().enumerate() {
+          if x.to_normalized().abs() > 1.0 {
+            let c = &mut clipping[i % channels];
+            c.count += 1;
+            c.first_frame.get_or_insert(offset + (i / channels) as u64);
+          }
+        }
+      }
+      _ => (),
+    }
+  }
+}
+
+macro_rules! impl_common {
+  ($t:ident) => {
+    #[allow(clippy::len_without_is_empty)]
+    impl $t {
+      /// Borrow the underlying `SndFile`, e.g. to get its format or tags.
+      pub fn get_ref(&self) -> &SndFile {
+        &self.0
+      }
+
+      /// Unwrap the underlying `SndFile`, giving up the compile time check of the open mode.
+      pub fn into_inner(self) -> SndFile {
+        self.0
+      }
+
+      /// See `SndFile::seek`.
+      pub fn seek(&mut self, pos: SeekFrom) -> Result<u64, SndFileError> {
+        self.0.seek(pos)
+      }
+
+      /// See `SndFile::len`.
+      pub fn len(&mut self) -> Result<u64, SndFileError> {
+        self.0.len()
+      }
+    }
+  };
+}
+
+macro_rules! impl_read {
+  ($t:ident) => {
+    impl $t {
+      /// See `SndFileIO::read_to_slice`.
+      pub fn read_to_slice<T>(&mut self, dst: &mut [T]) -> Result<usize, SndFileError>
+      where
+        T: 'static + Default + Copy,
+        SndFile: SndFileIO<T>,
+      {
+        self.0.read_to_slice(dst)
+      }
+
+      /// See `SndFileIO::read_to_iter`.
+      pub fn read_to_iter<'a, T, I>(&mut self, dst: I) -> Result<usize, SndFileError>
+      where
+        T: 'static + Default + Copy,
+        SndFile: SndFileIO<T>,
+        I: ExactSizeIterator<Item = &'a mut T>,
+      {
+        self.0.read_to_iter(dst)
+      }
+
+      /// See `SndFileIO::read_to_uninit_slice`.
+      pub fn read_to_uninit_slice<'a, T>(
+        &mut self,
+        dst: &'a mut [std::mem::MaybeUninit<T>],
+      ) -> Result<&'a mut [T], SndFileError>
+      where
+        T: 'static + Default + Copy,
+        SndFile: SndFileIO<T>,
+      {
+        self.0.read_to_uninit_slice(dst)
+      }
+
+      /// See `SndFileIO::read_all_to_vec`.
+      pub fn read_all_to_vec<T>(&mut self) -> Result<Vec<T>, SndFileError>
+      where
+        T: 'static + Default + Copy,
+        SndFile: SndFileIO<T>,
+      {
+        self.0.read_all_to_vec()
+      }
+    }
+  };
+}
+
+macro_rules! impl_write {
+  ($t:ident) => {
+    impl $t {
+      /// See `SndFileIO::write_from_slice`.
+      pub fn write_from_slice<T>(&mut self, src: &[T]) -> Result<usize, SndFileError>
+      where
+        T: 'static + Default + Sample,
+        SndFile: SndFileIO<T>,
+      {
+        self.1.check_range(src, self.0.get_channels())?;
+        let start = Instant::now();
+        let n = self.0.write_from_slice(src)?;
+        let written = &src[..(n * self.0.get_channels()).min(src.len())];
+        self.1.record(written, n as u64, start.elapsed());
+        Ok(n)
+      }
+
+      /// See `SndFileIO::write_from_iter`.
+      pub fn write_from_iter<T, I>(&mut self, src: I) -> Result<usize, SndFileError>
+      where
+        T: 'static + Default + Sample,
+        SndFile: SndFileIO<T>,
+        I: ExactSizeIterator<Item = T>,
+      {
+        let buf: Vec<T> = src.collect();
+        self.write_from_slice(&buf[..])
+      }
+
+      /// See `SndFile::write_silence`.
+      pub fn write_silence(&mut self, n_frames: u64) -> Result<u64, SndFileError> {
+        let start = Instant::now();
+        let n = self.0.write_silence(n_frames)?;
+        self.1.record::<f64>(&[], n, start.elapsed());
+        Ok(n)
+      }
+
+      /// Clip samples of float input out of `[-1.0, 1.0]` from now on, and count them, see
+      /// `stats`.
+      ///
+      /// This enables `Command::SetClipping`, without it libsndfile wraps these samples around
+      /// when converting them to integers. `FLOAT` and `DOUBLE` store them as is, for which
+      /// nothing is counted.
+      pub fn with_clip_counting(mut self) -> Self {
+        let channels = self.0.get_channels();
+        // `SFC_SET_CLIPPING` only reports the new state, it can not fail.
+        let _ = self.0.command(Command::SetClipping(true));
+        self.1.clips = !self.0.get_subtype_format().is_float();
+        self
+          .1
+          .clipping
+          .get_or_insert_with(|| vec![ClippedChannel::default(); channels]);
+        self
+      }
+
+      /// Return `SndFileError::InvalidParameter` instead of writing samples which would be clipped,
+      /// with the index of the offending frame counted from the first frame written.
+      ///
+      /// Like `with_clip_counting`, samples out of `[-1.0, 1.0]` are accepted by `FLOAT` and
+      /// `DOUBLE`. Nothing of a rejected slice is written.
+      pub fn with_strict_range(mut self) -> Self {
+        self.1.clips = !self.0.get_subtype_format().is_float();
+        self.1.strict = true;
+        self
+      }
+
+      /// Get the metrics of the frames written so far.
+      pub fn stats(&self) -> WriterStats {
+        WriterStats {
+          frames: self.1.frames,
+          elapsed: self.1.elapsed,
+          clipped: self
+            .1
+            .clipping
+            .as_ref()
+            .map(|x| x.iter().map(|c| c.count).sum()),
+          clipped_channels: self.1.clipping.clone(),
+        }
+      }
+
+      /// Close the file and report what was written, returns the summary if success.
+      ///
+      /// Unlike dropping, which panics if `sf_close` fails, errors of finalizing the header and
+      /// flushing buffered frames are returned, so a successful summary means the file is complete.
+      pub fn finalize(self) -> Result<WriteSummary, SndFileError> {
+        let $t(snd, tally) = self;
+        let samplerate = snd.get_samplerate();
+        let stream = snd.close_into_stream()?;
+        let bytes = stream.get_ref().byte_len().map_err(SndFileError::IOError)?;
+        Ok(WriteSummary {
+          frames: tally.frames,
+          bytes,
+          duration: Duration::from_secs_f64(tally.frames as f64 / samplerate as f64),
+          peak: tally.peak,
+        })
+      }
+
+      /// See `SndFile::set_tag`.
+      pub fn set_tag(&mut self, t: TagType, v: &str) -> Result<(), SndFileError> {
+        self.0.set_tag(t, v)
+      }
+
+      /// See `SndFile::set_tags`.
+      pub fn set_tags<'a, I>(&mut self, tags: I) -> Result<(), SndFileError>
+      where
+        I: IntoIterator<Item = (TagType, &'a str)>,
+      {
+        self.0.set_tags(tags)
+      }
+    }
+  };
+}
+
+/// An audio file opened for reading only, see `OpenOptions::ReadOnly`.
+///
+/// Unlike `SndFile`, it has no write method, so writing to a read only file fails to compile.
+#[derive(Debug)]
+pub struct SndReader(SndFile);
+
+/// An audio file opened for writing only, see `OpenOptions::WriteOnly`.
+///
+/// Unlike `SndFile`, it has no read method, so reading from a write only file fails to compile.
+#[derive(Debug)]
+pub struct SndWriter(SndFile, WriteTally);
+
+/// An audio file opened for reading and writing, see `OpenOptions::ReadWrite` and
+/// `OpenOptions::WriteRead`.
+#[derive(Debug)]
+pub struct SndReadWriter(SndFile, WriteTally);
+
+/// Completion record of a file written by a `SndWriter` or a `SndReadWriter`, see
+/// `SndWriter::finalize`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WriteSummary {
+  /// Number of frames written.
+  pub frames: u64,
+  /// Size of the closed file in bytes, headers included.
+  pub bytes: u64,
+  /// Duration of the frames written.
+  pub duration: Duration,
+  /// Peak absolute value of all channels, normalized like `Sample`.
+  pub peak: f64,
+}
+
+/// Samples of a channel clipped while writing, see `WriterStats::clipped_channels`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ClippedChannel {
+  /// Number of clipped samples.
+  pub count: u64,
+  /// Index of the first frame with a clipped sample, counted from the first frame written.
+  pub first_frame: Option<u64>,
+}
+
+/// Health metrics of a `SndWriter` or a `SndReadWriter`, see `SndWriter::stats`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriterStats {
+  /// Number of frames written.
+  pub frames: u64,
+  /// Wall-clock time spent in libsndfile writing them, i.e. encoding and I/O.
+  pub elapsed: Duration,
+  /// Number of clipped samples of all channels, `None` unless enabled by
+  /// `SndWriter::with_clip_counting`.
+  pub clipped: Option<u64>,
+  /// Clipped samples of each channel, `None` unless enabled by `SndWriter::with_clip_counting`.
+  pub clipped_channels: Option<Vec<ClippedChannel>>,
+}
+
+impl WriterStats {
+  /// Frames written per second of `elapsed`, `0.0` if nothing is written.
+  pub fn frames_per_second(&self) -> f64 {
+    if self.elapsed.is_zero() {
+      0.0
+    } else {
+      self.frames as f64 / self.elapsed.as_secs_f64()
+    }
+  }
+}
+
+impl SndReader {
+  /// Open the file at `path` for reading.
+  pub fn open<P: AsRef<Path>>(path: P, options: ReadOptions) -> Result<Self, SndFileError> {
+    OpenOptions::ReadOnly(options)
+      .from_path(path)
+      .map(SndReader)
+  }
+
+  /// Open `f` for reading.
+  pub fn from_file(f: File, options: ReadOptions) -> Result<Self, SndFileError> {
+    OpenOptions::ReadOnly(options).from_file(f).map(SndReader)
+  }
+}
+
+impl SndWriter {
+  fn wrap(snd: SndFile) -> Self {
+    SndWriter(snd, WriteTally::default())
+  }
+  /// Create or truncate the file at `path` for writing.
+  pub fn create<P: AsRef<Path>>(path: P, options: WriteOptions) -> Result<Self, SndFileError> {
+    OpenOptions::WriteOnly(options)
+      .from_path(path)
+      .map(SndWriter::wrap)
+  }
+
+  /// Open `f` for writing.
+  pub fn from_file(f: File, options: WriteOptions) -> Result<Self, SndFileError> {
+    OpenOptions::WriteOnly(options)
+      .from_file(f)
+      .map(SndWriter::wrap)
+  }
+}
+
+impl SndReadWriter {
+  fn wrap(snd: SndFile) -> Self {
+    SndReadWriter(snd, WriteTally::default())
+  }
+  /// Open the existing file at `path` for reading and writing.
+  pub fn open<P: AsRef<Path>>(path: P, options: ReadOptions) -> Result<Self, SndFileError> {
+    OpenOptions::ReadWrite(options)
+      .from_path(path)
+      .map(SndReadWriter::wrap)
+  }
+
+  /// Open the file at `path` for reading and writing, it is created if it does not exist yet.
+  pub fn create<P: AsRef<Path>>(path: P, options: WriteOptions) -> Result<Self, SndFileError> {
+    OpenOptions::WriteRead(options)
+      .from_path(path)
+      .map(SndReadWriter::wrap)
+  }
+}
+
+impl_common!(SndReader);
+impl_common!(SndWriter);
+impl_common!(SndReadWriter);
+impl_read!(SndReader);
+impl_read!(SndReadWriter);
+impl_write!(SndWriter);
+impl_write!(SndReadWriter);
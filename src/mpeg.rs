@@ -0,0 +1,159 @@
+use super::format::SF_FORMAT_MPEG;
+use super::{MajorFormat, SndFile, SndFileError, SndFileIO, VirtualIo};
+use std::io::{Read, SeekFrom};
+
+/// Decoder delay of MPEG Layer III decoders, not included in the encoder delay of the LAME tag.
+const DECODER_DELAY: u64 = 529;
+
+/// Bytes searched for the first MPEG frame after the ID3v2 tag.
+const SEARCH_LEN: usize = 4096;
+
+/// Gapless playback information of an MP3 file, from the Xing/Info and LAME tags written by LAME
+/// and most other encoders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GaplessInfo {
+  /// Frames of silence added by the encoder at the start.
+  pub encoder_delay: u32,
+  /// Frames of silence added by the encoder at the end.
+  pub padding: u32,
+  /// Number of MPEG frames, excluding the Xing/Info frame.
+  pub mpeg_frames: Option<u32>,
+  /// Audio frames per MPEG frame, e.g. `1152` for MPEG-1 Layer III.
+  pub samples_per_frame: u32,
+}
+
+impl GaplessInfo {
+  /// Number of audio frames of the original audio, without delay and padding.
+  ///
+  /// Returns `None` if the Xing/Info tag has no frame count.
+  pub fn duration_frames(&self) -> Option<u64> {
+    let total = self.mpeg_frames? as u64 * self.samples_per_frame as u64;
+    Some(total.saturating_sub(self.encoder_delay as u64 + self.padding as u64))
+  }
+}
+
+/// Parse the first MPEG frame of `data`, which must hold a Xing/Info tag followed by a LAME tag.
+fn parse_gapless(data: &[u8]) -> Option<GaplessInfo> {
+  let start = (0..data.len().saturating_sub(4))
+    .find(|&i| data[i] == 0xff && data[i + 1] & 0xe0 == 0xe0 && data[i + 1] & 0x06 != 0)?;
+  let header = &data[start..];
+  // `3` is MPEG-1, `2` MPEG-2 and `0` MPEG-2.5.
+  let version = (header[1] >> 3) & 0x03;
+  // `3` is Layer I, `2` Layer II and `1` Layer III.
+  let layer = (header[1] >> 1) & 0x03;
+  let mono = header[3] >> 6 == 3;
+  let samples_per_frame = match (layer, version) {
+    (3, _) => 384,
+    (2, _) | (1, 3) => 1152,
+    _ => 576,
+  };
+  let side_info = match (layer, version == 3, mono) {
+    (1, true, true) => 17,
+    (1, true, false) => 32,
+    (1, false, true) => 9,
+    (1, false, false) => 17,
+    _ => 0,
+  };
+  let crc = if header[1] & 0x01 == 0 { 2 } else { 0 };
+  // Encoders disagree on counting the CRC, so both offsets are tried.
+  let xing = [4 + side_info, 4 + side_info + crc].iter().find_map(|&x| {
+    let tag = header.get(x..x + 4)?;
+    if tag == b"Xing" || tag == b"Info" {
+      Some(&header[x..])
+    } else {
+      None
+    }
+  })?;
+  let flags = u32::from_be_bytes([*xing.get(4)?, xing[5], xing[6], xing[7]]);
+  let mut pos = 8;
+  let mut mpeg_frames = None;
+  if flags & 0x01 != 0 {
+    let x = xing.get(pos..pos + 4)?;
+    mpeg_frames = Some(u32::from_be_bytes([x[0], x[1], x[2], x[3]]));
+    pos += 4;
+  }
+  for (flag, len) in [(0x02, 4), (0x04, 100), (0x08, 4)] {
+    if flags & flag != 0 {
+      pos += len;
+    }
+  }
+  // The LAME tag: a 9 bytes encoder name, 12 bytes of other fields, then delay and padding.
+  let lame = xing.get(pos..pos + 24)?;
+  if !lame[..4].iter().all(|x| x.is_ascii_alphanumeric()) {
+    return None;
+  }
+  let x = &lame[21..24];
+  Some(GaplessInfo {
+    encoder_delay: (x[0] as u32) << 4 | (x[1] as u32) >> 4,
+    padding: ((x[1] & 0x0f) as u32) << 8 | x[2] as u32,
+    mpeg_frames,
+    samples_per_frame,
+  })
+}
+
+impl SndFile {
+  /// Get the encoder delay and padding of an MP3 file, or `None` if it has no LAME tag.
+  ///
+  /// Returns `SndFileError::InvalidParameter` if the file is not an MPEG file.
+  ///
+  /// This function does not affect the I/O cursor.
+  pub fn get_gapless_info(&mut self) -> Result<Option<GaplessInfo>, SndFileError> {
+    if self.get_major_format() != MajorFormat::Unknown(SF_FORMAT_MPEG) {
+      return Err(SndFileError::InvalidParameter(
+        "File is not an MPEG file.".to_string(),
+      ));
+    }
+    let f = unsafe { &mut *self.unsafe_fields.vio_user_ptr }.f.get_mut();
+    let pos = f.stream_position().map_err(SndFileError::IOError)?;
+    let read = |f: &mut dyn VirtualIo| -> std::io::Result<Vec<u8>> {
+      let mut id3 = [0u8; 10];
+      f.seek(SeekFrom::Start(0))?;
+      f.read_exact(&mut id3)?;
+      let offset = if id3.starts_with(b"ID3") {
+        let size = id3[6..10]
+          .iter()
+          .fold(0u64, |acc, &x| (acc << 7) | (x & 0x7f) as u64);
+        10 + size + if id3[5] & 0x10 != 0 { 10 } else { 0 }
+      } else {
+        0
+      };
+      let mut data = Vec::with_capacity(SEARCH_LEN);
+      f.seek(SeekFrom::Start(offset))?;
+      f.take(SEARCH_LEN as u64).read_to_end(&mut data)?;
+      Ok(data)
+    };
+    let data = read(f);
+    f.seek(SeekFrom::Start(pos))
+      .map_err(SndFileError::IOError)?;
+    Ok(parse_gapless(&data.map_err(SndFileError::IOError)?))
+  }
+
+  /// Read all frames of an MP3 file like `read_all_to_vec`, without the encoder delay and padding.
+  ///
+  /// Builds of libsndfile decoding gaplessly already drop them, then the frames are returned as
+  /// is. Otherwise the delay and padding of the LAME tag are trimmed, see `get_gapless_info`. Files
+  /// without a LAME tag are read like `read_all_to_vec`.
+  ///
+  /// The I/O cursor is left unchanged.
+  pub fn read_all_to_vec_gapless<T>(&mut self) -> Result<Vec<T>, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+  {
+    let info = self.get_gapless_info()?;
+    let mut buf: Vec<T> = self.read_all_to_vec()?;
+    let channels = self.get_channels();
+    let n_frames = (buf.len() / channels) as u64;
+    if let Some(info) = info {
+      match info.duration_frames() {
+        Some(duration) if n_frames > duration => {
+          let start = (info.encoder_delay as u64 + DECODER_DELAY).min(n_frames - duration);
+          buf.truncate((start + duration) as usize * channels);
+          buf.drain(..start as usize * channels);
+        }
+        _ => (),
+      }
+    }
+    Ok(buf)
+  }
+}
@@ -1,108 +1,202 @@
-use super::SndFileIO;
-use ndarray::{Array2, ArrayView2, ArrayViewMut2};
+use super::{default_read_limit, SndFile, SndFileError, SndFileIO, TrimOptions};
+use ndarray::{s, Array2, ArrayView2, ArrayViewMut2};
 use std::io::SeekFrom;
 
 /// Do I/O operation on 2D ndarray.
 ///
 /// The array shape must be (n_frames, n_channels).
 pub trait SndFileNDArrayIO<T> {
-  fn read_to_ndarray(&mut self, dst: ArrayViewMut2<T>) -> Result<usize, ()>;
-  fn write_from_ndarray(&mut self, src: ArrayView2<T>) -> Result<usize, ()>;
-  fn read_all_to_ndarray(&mut self) -> Result<Array2<T>, ()>;
+  fn read_to_ndarray(&mut self, dst: ArrayViewMut2<T>) -> Result<usize, SndFileError>;
+  fn write_from_ndarray(&mut self, src: ArrayView2<T>) -> Result<usize, SndFileError>;
+  /// Read all frames into a 2D ndarray if success, the I/O cursor is left unchanged.
+  ///
+  /// Short reads and files which are not seekable are handled like `SndFileIO::read_all_to_vec`.
+  fn read_all_to_ndarray(&mut self) -> Result<Array2<T>, SndFileError>;
+}
+
+fn check_ndarray_width(snd: &SndFile, width: usize) -> Result<(), SndFileError> {
+  if width == snd.get_channels() {
+    Ok(())
+  } else {
+    Err(SndFileError::InvalidParameter(format!(
+      "Got array width {}, expect channel count {}.",
+      width,
+      snd.get_channels()
+    )))
+  }
 }
 
 impl SndFileNDArrayIO<i16> for super::SndFile {
-  fn read_to_ndarray(&mut self, mut dst: ArrayViewMut2<i16>) -> Result<usize, ()> {
-    assert_eq!(dst.shape()[1], self.get_channels());
+  fn read_to_ndarray(&mut self, mut dst: ArrayViewMut2<i16>) -> Result<usize, SndFileError> {
+    check_ndarray_width(self, dst.shape()[1])?;
     match dst.as_slice_mut() {
       Some(s) => self.read_to_slice(s),
       None => self.read_to_iter(dst.iter_mut()),
     }
   }
 
-  fn write_from_ndarray(&mut self, src: ArrayView2<i16>) -> Result<usize, ()> {
-    assert_eq!(src.shape()[1], self.get_channels());
+  fn write_from_ndarray(&mut self, src: ArrayView2<i16>) -> Result<usize, SndFileError> {
+    check_ndarray_width(self, src.shape()[1])?;
     match src.as_slice() {
       Some(s) => self.write_from_slice(s),
       None => self.write_from_iter(src.iter().map(|x| *x)),
     }
   }
 
-  fn read_all_to_ndarray(&mut self) -> Result<Array2<i16>, ()> {
-    let mut arr = Array2::<_>::zeros((self.len()? as usize, self.get_channels()));
-    self.seek(SeekFrom::Start(0))?;
-    self.read_to_ndarray(arr.view_mut()).map(|_| arr)
+  fn read_all_to_ndarray(&mut self) -> Result<Array2<i16>, SndFileError> {
+    if !self.is_seekable() {
+      let buf = self.read_to_end::<i16>(default_read_limit())?;
+      let channels = self.get_channels();
+      return Ok(Array2::from_shape_vec((buf.len() / channels, channels), buf).unwrap());
+    }
+    self.preserving_cursor(|snd| {
+      let n = snd.check_read_all_len::<i16>(default_read_limit())?;
+      let mut arr = Array2::<_>::zeros((n / snd.get_channels(), snd.get_channels()));
+      snd.seek(SeekFrom::Start(0))?;
+      let n_frames = snd.read_to_ndarray(arr.view_mut())?;
+      Ok(arr.slice_move(s![..n_frames, ..]))
+    })
   }
 }
 
 impl SndFileNDArrayIO<i32> for super::SndFile {
-  fn read_to_ndarray(&mut self, mut dst: ArrayViewMut2<i32>) -> Result<usize, ()> {
-    assert_eq!(dst.shape()[1], self.get_channels());
+  fn read_to_ndarray(&mut self, mut dst: ArrayViewMut2<i32>) -> Result<usize, SndFileError> {
+    check_ndarray_width(self, dst.shape()[1])?;
     match dst.as_slice_mut() {
       Some(s) => self.read_to_slice(s),
       None => self.read_to_iter(dst.iter_mut()),
     }
   }
 
-  fn write_from_ndarray(&mut self, src: ArrayView2<i32>) -> Result<usize, ()> {
-    assert_eq!(src.shape()[1], self.get_channels());
+  fn write_from_ndarray(&mut self, src: ArrayView2<i32>) -> Result<usize, SndFileError> {
+    check_ndarray_width(self, src.shape()[1])?;
     match src.as_slice() {
       Some(s) => self.write_from_slice(s),
       None => self.write_from_iter(src.iter().map(|x| *x)),
     }
   }
 
-  fn read_all_to_ndarray(&mut self) -> Result<Array2<i32>, ()> {
-    let mut arr = Array2::<_>::zeros((self.len()? as usize, self.get_channels()));
-    self.seek(SeekFrom::Start(0))?;
-    self.read_to_ndarray(arr.view_mut()).map(|_| arr)
+  fn read_all_to_ndarray(&mut self) -> Result<Array2<i32>, SndFileError> {
+    if !self.is_seekable() {
+      let buf = self.read_to_end::<i32>(default_read_limit())?;
+      let channels = self.get_channels();
+      return Ok(Array2::from_shape_vec((buf.len() / channels, channels), buf).unwrap());
+    }
+    self.preserving_cursor(|snd| {
+      let n = snd.check_read_all_len::<i32>(default_read_limit())?;
+      let mut arr = Array2::<_>::zeros((n / snd.get_channels(), snd.get_channels()));
+      snd.seek(SeekFrom::Start(0))?;
+      let n_frames = snd.read_to_ndarray(arr.view_mut())?;
+      Ok(arr.slice_move(s![..n_frames, ..]))
+    })
   }
 }
 
 impl SndFileNDArrayIO<f32> for super::SndFile {
-  fn read_to_ndarray(&mut self, mut dst: ArrayViewMut2<f32>) -> Result<usize, ()> {
-    assert_eq!(dst.shape()[1], self.get_channels());
+  fn read_to_ndarray(&mut self, mut dst: ArrayViewMut2<f32>) -> Result<usize, SndFileError> {
+    check_ndarray_width(self, dst.shape()[1])?;
     match dst.as_slice_mut() {
       Some(s) => self.read_to_slice(s),
       None => self.read_to_iter(dst.iter_mut()),
     }
   }
 
-  fn write_from_ndarray(&mut self, src: ArrayView2<f32>) -> Result<usize, ()> {
-    assert_eq!(src.shape()[1], self.get_channels());
+  fn write_from_ndarray(&mut self, src: ArrayView2<f32>) -> Result<usize, SndFileError> {
+    check_ndarray_width(self, src.shape()[1])?;
     match src.as_slice() {
       Some(s) => self.write_from_slice(s),
       None => self.write_from_iter(src.iter().map(|x| *x)),
     }
   }
 
-  fn read_all_to_ndarray(&mut self) -> Result<Array2<f32>, ()> {
-    let mut arr = Array2::<_>::zeros((self.len()? as usize, self.get_channels()));
-    self.seek(SeekFrom::Start(0))?;
-    self.read_to_ndarray(arr.view_mut()).map(|_| arr)
+  fn read_all_to_ndarray(&mut self) -> Result<Array2<f32>, SndFileError> {
+    if !self.is_seekable() {
+      let buf = self.read_to_end::<f32>(default_read_limit())?;
+      let channels = self.get_channels();
+      return Ok(Array2::from_shape_vec((buf.len() / channels, channels), buf).unwrap());
+    }
+    self.preserving_cursor(|snd| {
+      let n = snd.check_read_all_len::<f32>(default_read_limit())?;
+      let mut arr = Array2::<_>::zeros((n / snd.get_channels(), snd.get_channels()));
+      snd.seek(SeekFrom::Start(0))?;
+      let n_frames = snd.read_to_ndarray(arr.view_mut())?;
+      Ok(arr.slice_move(s![..n_frames, ..]))
+    })
   }
 }
 
 impl SndFileNDArrayIO<f64> for super::SndFile {
-  fn read_to_ndarray(&mut self, mut dst: ArrayViewMut2<f64>) -> Result<usize, ()> {
-    assert_eq!(dst.shape()[1], self.get_channels());
+  fn read_to_ndarray(&mut self, mut dst: ArrayViewMut2<f64>) -> Result<usize, SndFileError> {
+    check_ndarray_width(self, dst.shape()[1])?;
     match dst.as_slice_mut() {
       Some(s) => self.read_to_slice(s),
       None => self.read_to_iter(dst.iter_mut()),
     }
   }
 
-  fn write_from_ndarray(&mut self, src: ArrayView2<f64>) -> Result<usize, ()> {
-    assert_eq!(src.shape()[1], self.get_channels());
+  fn write_from_ndarray(&mut self, src: ArrayView2<f64>) -> Result<usize, SndFileError> {
+    check_ndarray_width(self, src.shape()[1])?;
     match src.as_slice() {
       Some(s) => self.write_from_slice(s),
       None => self.write_from_iter(src.iter().map(|x| *x)),
     }
   }
 
-  fn read_all_to_ndarray(&mut self) -> Result<Array2<f64>, ()> {
-    let mut arr = Array2::<_>::zeros((self.len()? as usize, self.get_channels()));
-    self.seek(SeekFrom::Start(0))?;
-    self.read_to_ndarray(arr.view_mut()).map(|_| arr)
+  fn read_all_to_ndarray(&mut self) -> Result<Array2<f64>, SndFileError> {
+    if !self.is_seekable() {
+      let buf = self.read_to_end::<f64>(default_read_limit())?;
+      let channels = self.get_channels();
+      return Ok(Array2::from_shape_vec((buf.len() / channels, channels), buf).unwrap());
+    }
+    self.preserving_cursor(|snd| {
+      let n = snd.check_read_all_len::<f64>(default_read_limit())?;
+      let mut arr = Array2::<_>::zeros((n / snd.get_channels(), snd.get_channels()));
+      snd.seek(SeekFrom::Start(0))?;
+      let n_frames = snd.read_to_ndarray(arr.view_mut())?;
+      Ok(arr.slice_move(s![..n_frames, ..]))
+    })
+  }
+}
+
+impl SndFile {
+  /// Read frames from current I/O cursor into `dst` like `SndFileNDArrayIO::read_to_ndarray`,
+  /// returns the view of the filled rows if success.
+  ///
+  /// On short reads, e.g. at EOF, rows after the filled ones are set to `T::default()` instead of
+  /// being left untouched.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn read_to_ndarray_filled<'a, T>(
+    &mut self,
+    mut dst: ArrayViewMut2<'a, T>,
+  ) -> Result<ArrayViewMut2<'a, T>, SndFileError>
+  where
+    T: Default + Clone,
+    SndFile: SndFileNDArrayIO<T>,
+  {
+    let n_frames = self.read_to_ndarray(dst.view_mut())?;
+    dst.slice_mut(s![n_frames.., ..]).fill(T::default());
+    Ok(dst.slice_move(s![..n_frames, ..]))
+  }
+
+  /// Read all frames into a 2D ndarray with leading and trailing silence trimmed, returns the array
+  /// and the offset of its first frame in the file if success.
+  ///
+  /// See `SndFile::read_all_to_vec_trimmed`.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn read_all_to_ndarray_trimmed<T>(
+    &mut self,
+    options: &TrimOptions,
+  ) -> Result<(Array2<T>, u64), SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+  {
+    let channels = self.get_channels();
+    let (buf, offset) = self.read_all_to_vec_trimmed(options)?;
+    let arr = Array2::from_shape_vec((buf.len() / channels, channels), buf)
+      .map_err(|e| SndFileError::InternalError(e.to_string()))?;
+    Ok((arr, offset))
   }
 }
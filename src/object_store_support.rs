@@ -0,0 +1,131 @@
+use super::ranged::{FetchRange, RangedReader};
+use super::{OpenOptions, SndFile, SndFileError, SourceKind, VirtualIo};
+use object_store::path::Path;
+use object_store::ObjectStore;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// A read only `VirtualIo` reading an object of an `ObjectStore`, e.g. S3, GCS or Azure Blob
+/// Storage, with range requests.
+///
+/// Requests run on a private single threaded tokio runtime, so it must not be used from within an
+/// async context. Fetched blocks are kept in a least recently used cache, see
+/// `ObjectStoreSource::with_cache`.
+///
+/// ```ignore
+/// let store: Arc<dyn ObjectStore> = Arc::new(AmazonS3Builder::from_env().with_bucket_name("audio").build()?);
+/// let snd = OpenOptions::ReadOnly(ReadOptions::Auto).from_object_store(store, &"takes/a.flac".into())?;
+/// ```
+pub struct ObjectStoreSource(RangedReader<ObjectFetcher>);
+
+struct ObjectFetcher {
+  runtime: tokio::runtime::Runtime,
+  store: Arc<dyn ObjectStore>,
+  path: Path,
+}
+
+impl std::fmt::Debug for ObjectStoreSource {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ObjectStoreSource")
+      .field("store", &self.0.fetcher.store)
+      .field("path", &self.0.fetcher.path)
+      .field("len", &self.0.len())
+      .field("pos", &self.0.position())
+      .finish()
+  }
+}
+
+fn object_store_error(e: object_store::Error) -> io::Error {
+  match e {
+    object_store::Error::NotFound { .. } => io::Error::new(io::ErrorKind::NotFound, e),
+    e => io::Error::other(e),
+  }
+}
+
+impl FetchRange for ObjectFetcher {
+  fn fetch(&mut self, range: Range<u64>) -> io::Result<Vec<u8>> {
+    self
+      .runtime
+      .block_on(self.store.get_range(&self.path, range))
+      .map(|x| x.to_vec())
+      .map_err(object_store_error)
+  }
+}
+
+impl ObjectStoreSource {
+  /// Open the object at `path` of `store`, returns `SndFileError::IOError` if it can't be found.
+  ///
+  /// Uses blocks of 256 KiB and a cache of 16 blocks.
+  pub fn open(store: Arc<dyn ObjectStore>, path: &Path) -> Result<Self, SndFileError> {
+    ObjectStoreSource::with_cache(store, path, 256 * 1024, 16)
+  }
+
+  /// Open the object at `path` of `store`, fetching `block_size` bytes per request and caching up
+  /// to `n_blocks` blocks.
+  pub fn with_cache(
+    store: Arc<dyn ObjectStore>,
+    path: &Path,
+    block_size: usize,
+    n_blocks: usize,
+  ) -> Result<Self, SndFileError> {
+    if block_size == 0 || n_blocks == 0 {
+      return Err(SndFileError::InvalidParameter(format!(
+        "Got block size {} and {} blocks, expect positive numbers.",
+        block_size, n_blocks
+      )));
+    }
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .map_err(SndFileError::IOError)?;
+    let meta = runtime
+      .block_on(store.head(path))
+      .map_err(|e| SndFileError::IOError(object_store_error(e)))?;
+    let fetcher = ObjectFetcher {
+      runtime,
+      store,
+      path: path.clone(),
+    };
+    Ok(ObjectStoreSource(RangedReader::new(
+      fetcher, meta.size, block_size, n_blocks,
+    )))
+  }
+
+  /// Path of the object.
+  pub fn path(&self) -> &Path {
+    &self.0.fetcher.path
+  }
+}
+
+impl Read for ObjectStoreSource {
+  fn read(&mut self, dst: &mut [u8]) -> io::Result<usize> {
+    self.0.read(dst)
+  }
+}
+
+impl Seek for ObjectStoreSource {
+  fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+    self.0.seek(pos)
+  }
+}
+
+impl VirtualIo for ObjectStoreSource {
+  fn byte_len(&self) -> io::Result<u64> {
+    Ok(self.0.len())
+  }
+}
+
+impl OpenOptions {
+  /// Open the object at `path` of `store` with `ObjectStoreSource`, only `OpenOptions::ReadOnly` is
+  /// supported.
+  pub fn from_object_store(
+    &self,
+    store: Arc<dyn ObjectStore>,
+    path: &Path,
+  ) -> Result<SndFile, SndFileError> {
+    self
+      .from_virtual_io(ObjectStoreSource::open(store, path)?)
+      .map(|x| x.with_source(SourceKind::ObjectStore, None))
+  }
+}
@@ -0,0 +1,387 @@
+use super::{MajorFormat, SndFile, SndFileError};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Codec of the first logical stream of an Ogg file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Codec {
+  Vorbis,
+  Opus,
+}
+
+impl Codec {
+  fn from_ident_packet(packet: &[u8]) -> Option<Codec> {
+    if packet.starts_with(b"\x01vorbis") {
+      Some(Codec::Vorbis)
+    } else if packet.starts_with(b"OpusHead") {
+      Some(Codec::Opus)
+    } else {
+      None
+    }
+  }
+
+  fn n_header_packets(self) -> usize {
+    match self {
+      Codec::Vorbis => 3,
+      Codec::Opus => 2,
+    }
+  }
+
+  fn comment_magic(self) -> &'static [u8] {
+    match self {
+      Codec::Vorbis => b"\x03vorbis",
+      Codec::Opus => b"OpusTags",
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+struct Page {
+  header_type: u8,
+  granule_position: u64,
+  serial: u32,
+  sequence: u32,
+  segments: Vec<u8>,
+  data: Vec<u8>,
+}
+
+fn ogg_crc(data: &[u8]) -> u32 {
+  data.iter().fold(0u32, |crc, x| {
+    let mut crc = crc ^ (u32::from(*x) << 24);
+    for _ in 0..8 {
+      crc = if crc & 0x8000_0000 != 0 {
+        (crc << 1) ^ 0x04c1_1db7
+      } else {
+        crc << 1
+      };
+    }
+    crc
+  })
+}
+
+fn malformed(msg: &str) -> SndFileError {
+  SndFileError::MalformedFile(msg.to_string())
+}
+
+fn read_u32_le(src: &[u8]) -> Result<u32, SndFileError> {
+  if src.len() < 4 {
+    return Err(malformed("Truncated comment header."));
+  }
+  let mut buf = [0u8; 4];
+  buf.copy_from_slice(&src[..4]);
+  Ok(u32::from_le_bytes(buf))
+}
+
+impl Page {
+  /// Read the next page, returns `None` at the end of stream.
+  fn read<R: Read>(r: &mut R) -> Result<Option<Page>, SndFileError> {
+    let mut header = [0u8; 27];
+    match r.read_exact(&mut header) {
+      Ok(_) => (),
+      Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+      Err(e) => return Err(SndFileError::IOError(e)),
+    }
+    if &header[..4] != b"OggS" || header[4] != 0 {
+      return Err(malformed("Invalid Ogg page header."));
+    }
+    let mut granule = [0u8; 8];
+    granule.copy_from_slice(&header[6..14]);
+    let mut segments = vec![0u8; header[26] as usize];
+    r.read_exact(&mut segments).map_err(SndFileError::IOError)?;
+    let mut data = vec![0u8; segments.iter().map(|x| *x as usize).sum()];
+    r.read_exact(&mut data).map_err(SndFileError::IOError)?;
+    Ok(Some(Page {
+      header_type: header[5],
+      granule_position: u64::from_le_bytes(granule),
+      serial: read_u32_le(&header[14..18])?,
+      sequence: read_u32_le(&header[18..22])?,
+      segments,
+      data,
+    }))
+  }
+
+  fn encode(&self) -> Vec<u8> {
+    let mut out = Vec::with_capacity(27 + self.segments.len() + self.data.len());
+    out.extend_from_slice(b"OggS");
+    out.push(0);
+    out.push(self.header_type);
+    out.extend_from_slice(&self.granule_position.to_le_bytes());
+    out.extend_from_slice(&self.serial.to_le_bytes());
+    out.extend_from_slice(&self.sequence.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.push(self.segments.len() as u8);
+    out.extend_from_slice(&self.segments);
+    out.extend_from_slice(&self.data);
+    let crc = ogg_crc(&out);
+    out[22..26].copy_from_slice(&crc.to_le_bytes());
+    out
+  }
+}
+
+/// Header pages and packets of the first logical stream.
+struct Headers {
+  codec: Codec,
+  pages: Vec<Page>,
+  packets: Vec<Vec<u8>>,
+}
+
+fn read_headers<R: Read>(r: &mut R) -> Result<Headers, SndFileError> {
+  let mut pages: Vec<Page> = Vec::new();
+  let mut packets = Vec::new();
+  let mut partial = Vec::new();
+  let mut codec = None;
+  loop {
+    let page = Page::read(r)?.ok_or_else(|| malformed("Unexpected end of Ogg stream."))?;
+    if let Some(first) = pages.first() {
+      if first.serial != page.serial {
+        return Err(SndFileError::UnsupportedEncoding(
+          "Multiplexed Ogg streams are not supported.".to_string(),
+        ));
+      }
+    }
+    let mut pos = 0;
+    for seg in page.segments.iter() {
+      partial.extend_from_slice(&page.data[pos..pos + *seg as usize]);
+      pos += *seg as usize;
+      if *seg < 255 {
+        packets.push(std::mem::take(&mut partial));
+      }
+    }
+    pages.push(page);
+
+    if codec.is_none() {
+      if packets.len() != 1 || !partial.is_empty() {
+        return Err(malformed("First Ogg page must contain exactly one packet."));
+      }
+      codec = Codec::from_ident_packet(&packets[0]);
+      if codec.is_none() {
+        return Err(SndFileError::UnsupportedEncoding(
+          "Only Ogg Vorbis and Ogg Opus are supported.".to_string(),
+        ));
+      }
+    }
+    let codec = codec.unwrap();
+    if packets.len() >= codec.n_header_packets() {
+      if packets.len() > codec.n_header_packets() || !partial.is_empty() {
+        return Err(malformed("Audio data shares a page with header packets."));
+      }
+      return Ok(Headers {
+        codec,
+        pages,
+        packets,
+      });
+    }
+  }
+}
+
+/// Take a length prefixed field from `rest`.
+fn take_field(rest: &mut &[u8]) -> Result<Vec<u8>, SndFileError> {
+  let len = read_u32_le(rest)? as usize;
+  if rest.len() < 4 + len {
+    return Err(malformed("Truncated comment header."));
+  }
+  let out = rest[4..4 + len].to_vec();
+  *rest = &rest[4 + len..];
+  Ok(out)
+}
+
+/// Vendor string, comments and trailing bytes of a comment header packet.
+type CommentHeader = (Vec<u8>, Vec<(String, String)>, Vec<u8>);
+
+fn parse_comment_packet(codec: Codec, packet: &[u8]) -> Result<CommentHeader, SndFileError> {
+  let magic = codec.comment_magic();
+  if !packet.starts_with(magic) {
+    return Err(malformed("Invalid comment header."));
+  }
+  let mut rest = &packet[magic.len()..];
+  let vendor = take_field(&mut rest)?;
+  let n = read_u32_le(rest)?;
+  rest = &rest[4..];
+  let mut comments = Vec::new();
+  for _ in 0..n {
+    let comment = String::from_utf8_lossy(&take_field(&mut rest)?).into_owned();
+    let mut parts = comment.splitn(2, '=');
+    let key = parts.next().unwrap_or("").to_string();
+    let value = parts.next().unwrap_or("").to_string();
+    comments.push((key, value));
+  }
+  let trailing = match codec {
+    // The framing bit.
+    Codec::Vorbis => Vec::new(),
+    Codec::Opus => rest.to_vec(),
+  };
+  Ok((vendor, comments, trailing))
+}
+
+fn encode_comment_packet<K: AsRef<str>, V: AsRef<str>>(
+  codec: Codec,
+  vendor: &[u8],
+  comments: &[(K, V)],
+  trailing: &[u8],
+) -> Vec<u8> {
+  let mut out = codec.comment_magic().to_vec();
+  out.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+  out.extend_from_slice(vendor);
+  out.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+  for (k, v) in comments.iter() {
+    let comment = format!("{}={}", k.as_ref(), v.as_ref());
+    out.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+    out.extend_from_slice(comment.as_bytes());
+  }
+  match codec {
+    Codec::Vorbis => out.push(1),
+    Codec::Opus => out.extend_from_slice(trailing),
+  }
+  out
+}
+
+/// Split header packets into pages, the last page is flushed after the last packet.
+fn paginate(serial: u32, first_sequence: u32, packets: &[Vec<u8>]) -> Vec<Page> {
+  let mut lacing: Vec<(u8, bool)> = Vec::new();
+  for packet in packets.iter() {
+    for _ in 0..packet.len() / 255 {
+      lacing.push((255, false));
+    }
+    lacing.push(((packet.len() % 255) as u8, true));
+  }
+  let data: Vec<u8> = packets.concat();
+
+  let mut pages = Vec::new();
+  let mut pos = 0;
+  let mut continued = false;
+  for chunk in lacing.chunks(255) {
+    let segments: Vec<u8> = chunk.iter().map(|x| x.0).collect();
+    let len: usize = segments.iter().map(|x| *x as usize).sum();
+    let completes_packet = chunk.iter().any(|x| x.1);
+    pages.push(Page {
+      header_type: if continued { 1 } else { 0 },
+      granule_position: if completes_packet { 0 } else { u64::MAX },
+      serial,
+      sequence: first_sequence + pages.len() as u32,
+      segments,
+      data: data[pos..pos + len].to_vec(),
+    });
+    pos += len;
+    continued = !chunk.last().unwrap().1;
+  }
+  pages
+}
+
+fn check_comment_key(key: &str) -> Result<(), SndFileError> {
+  if key.is_empty() || !key.bytes().all(|x| (0x20..=0x7d).contains(&x) && x != b'=') {
+    return Err(SndFileError::InvalidParameter(format!(
+      "Got invalid Vorbis comment key `{}`, expect ASCII 0x20 through 0x7D excluding `=`.",
+      key
+    )));
+  }
+  Ok(())
+}
+
+/// Replace all Vorbis comments of an Ogg Vorbis or Ogg Opus file at `path`.
+///
+/// The file is rewritten through a temporary file in the same directory, so it must not be opened
+/// by any `SndFile` at the same time. Keys are case insensitive and stored as is, multiple values
+/// of the same key are allowed.
+pub fn write_vorbis_comments<P, K, V>(path: P, comments: &[(K, V)]) -> Result<(), SndFileError>
+where
+  P: AsRef<Path>,
+  K: AsRef<str>,
+  V: AsRef<str>,
+{
+  let comments: Vec<(String, String)> = comments
+    .iter()
+    .map(|(k, v)| (k.as_ref().to_string(), v.as_ref().to_string()))
+    .collect();
+  rewrite_vorbis_comments(path, |_, _| comments)
+}
+
+/// Rewrite the comment header of an Ogg Vorbis or Ogg Opus file at `path`, `f` maps the codec and
+/// existing comments to new comments.
+pub(crate) fn rewrite_vorbis_comments<P, F>(path: P, f: F) -> Result<(), SndFileError>
+where
+  P: AsRef<Path>,
+  F: FnOnce(Codec, Vec<(String, String)>) -> Vec<(String, String)>,
+{
+  let path = path.as_ref();
+  let mut src = BufReader::new(File::open(path).map_err(SndFileError::IOError)?);
+  let headers = read_headers(&mut src)?;
+  let (vendor, comments, trailing) = parse_comment_packet(headers.codec, &headers.packets[1])?;
+  let comments = f(headers.codec, comments);
+  for (k, _) in comments.iter() {
+    check_comment_key(k)?;
+  }
+
+  let mut packets = headers.packets[1..].to_vec();
+  packets[0] = encode_comment_packet(headers.codec, &vendor, &comments, &trailing);
+  let serial = headers.pages[0].serial;
+  let mut new_pages = vec![headers.pages[0].clone()];
+  new_pages.extend(paginate(serial, 1, &packets));
+  let delta = new_pages.len() as i64 - headers.pages.len() as i64;
+
+  let tmp_name = format!(
+    ".{}.tmp",
+    path.file_name().unwrap_or_default().to_string_lossy()
+  );
+  let tmp_path = path.with_file_name(tmp_name);
+  let result = (|| {
+    let mut dst = BufWriter::new(File::create(&tmp_path).map_err(SndFileError::IOError)?);
+    for page in new_pages.iter() {
+      dst
+        .write_all(&page.encode())
+        .map_err(SndFileError::IOError)?;
+    }
+    while let Some(mut page) = Page::read(&mut src)? {
+      if page.serial == serial {
+        page.sequence = (i64::from(page.sequence) + delta) as u32;
+      }
+      dst
+        .write_all(&page.encode())
+        .map_err(SndFileError::IOError)?;
+    }
+    dst.flush().map_err(SndFileError::IOError)
+  })();
+  match result {
+    Ok(()) => std::fs::rename(&tmp_path, path).map_err(SndFileError::IOError),
+    Err(e) => {
+      let _ = std::fs::remove_file(&tmp_path);
+      Err(e)
+    }
+  }
+}
+
+impl SndFile {
+  /// Get all Vorbis comments of an Ogg Vorbis or Ogg Opus file as `(key, value)` pairs in file
+  /// order, including the ones also reported by `get_tag`.
+  ///
+  /// This function does not affect the I/O cursor.
+  pub fn get_vorbis_comments(&self) -> Result<Vec<(String, String)>, SndFileError> {
+    if self.get_major_format() != MajorFormat::OGG {
+      return Err(SndFileError::InvalidParameter(
+        "File is not an Ogg file.".to_string(),
+      ));
+    }
+    let mut f: &File = unsafe { &*self.unsafe_fields.vio_user_ptr }.file()?;
+    let pos = f.stream_position().map_err(SndFileError::IOError)?;
+    f.seek(SeekFrom::Start(0)).map_err(SndFileError::IOError)?;
+    let headers = read_headers(&mut BufReader::new(f));
+    f.seek(SeekFrom::Start(pos))
+      .map_err(SndFileError::IOError)?;
+    let headers = headers?;
+    parse_comment_packet(headers.codec, &headers.packets[1]).map(|x| x.1)
+  }
+
+  /// Get the first value of Vorbis comment `key` of an Ogg Vorbis or Ogg Opus file, e.g.,
+  /// `REPLAYGAIN_TRACK_GAIN`. Keys are case insensitive.
+  ///
+  /// This function does not affect the I/O cursor.
+  pub fn get_vorbis_comment(&self, key: &str) -> Result<Option<String>, SndFileError> {
+    Ok(
+      self
+        .get_vorbis_comments()?
+        .into_iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v),
+    )
+  }
+}
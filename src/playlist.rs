@@ -0,0 +1,287 @@
+use super::edit::BLOCK_FRAMES;
+use super::{OpenOptions, ReadOptions, SndFile, SndFileError, SndFileIO};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+/// Track boundary reported by `Playlist::poll_event`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaylistEvent {
+  /// Track `index` starts at output frame `position`.
+  TrackStarted {
+    index: usize,
+    path: PathBuf,
+    position: u64,
+  },
+  /// Track `index` ends at output frame `position`, `skipped` is `true` if it ended by
+  /// `Playlist::next_track`.
+  TrackEnded {
+    index: usize,
+    position: u64,
+    skipped: bool,
+  },
+}
+
+/// A track being decoded, resampled by linear interpolation.
+struct Track {
+  snd: SndFile,
+  index: usize,
+  /// Input frames per output frame.
+  ratio: f64,
+  phase: f64,
+  a: Vec<f32>,
+  b: Vec<f32>,
+  has_a: bool,
+  has_b: bool,
+  buf: Vec<f32>,
+  buf_pos: usize,
+  buf_len: usize,
+}
+
+impl Track {
+  fn new(snd: SndFile, index: usize, samplerate: usize) -> Result<Track, SndFileError> {
+    let channels = snd.get_channels();
+    let mut track = Track {
+      ratio: snd.get_samplerate() as f64 / samplerate as f64,
+      snd,
+      index,
+      phase: 0.0,
+      a: vec![0.0; channels],
+      b: vec![0.0; channels],
+      has_a: false,
+      has_b: false,
+      buf: vec![0.0; BLOCK_FRAMES * channels],
+      buf_pos: 0,
+      buf_len: 0,
+    };
+    let mut a = std::mem::take(&mut track.a);
+    track.has_a = track.next_input(&mut a)?;
+    track.a = a;
+    let mut b = std::mem::take(&mut track.b);
+    track.has_b = track.next_input(&mut b)?;
+    track.b = b;
+    Ok(track)
+  }
+
+  /// Copy the next input frame to `dst`, returns `false` at the end of file.
+  fn next_input(&mut self, dst: &mut [f32]) -> Result<bool, SndFileError> {
+    if self.buf_pos == self.buf_len {
+      self.buf_len = self.snd.read_to_slice(&mut self.buf)?;
+      self.buf_pos = 0;
+      if self.buf_len == 0 {
+        return Ok(false);
+      }
+    }
+    let channels = dst.len();
+    dst.copy_from_slice(&self.buf[self.buf_pos * channels..(self.buf_pos + 1) * channels]);
+    self.buf_pos += 1;
+    Ok(true)
+  }
+
+  /// Fill `dst` with output frames, returns the number of frames written, less than requested only
+  /// at the end of the track.
+  fn read(&mut self, dst: &mut [f32]) -> Result<usize, SndFileError> {
+    let channels = self.a.len();
+    let mut done = 0;
+    for frame in dst.chunks_exact_mut(channels) {
+      if !self.has_a || (!self.has_b && self.phase > 0.0) {
+        break;
+      }
+      if self.has_b {
+        let t = self.phase as f32;
+        for ((x, a), b) in frame.iter_mut().zip(self.a.iter()).zip(self.b.iter()) {
+          *x = a + (b - a) * t;
+        }
+      } else {
+        frame.copy_from_slice(&self.a);
+      }
+      done += 1;
+      self.phase += self.ratio;
+      while self.phase >= 1.0 {
+        self.phase -= 1.0;
+        if !self.has_b {
+          self.has_a = false;
+          break;
+        }
+        std::mem::swap(&mut self.a, &mut self.b);
+        let mut b = std::mem::take(&mut self.b);
+        self.has_b = self.next_input(&mut b)?;
+        self.b = b;
+      }
+    }
+    Ok(done)
+  }
+}
+
+/// A queue of files decoded one after another into a continuous stream of `f32` frames, the
+/// backbone of a player.
+///
+/// Tracks with another samplerate are resampled to the samplerate of the playlist by linear
+/// interpolation. All tracks must have the channel count of the playlist.
+///
+/// ```ignore
+/// let mut playlist = Playlist::new(48000, 2)?;
+/// playlist.push("01.flac");
+/// playlist.push("02.ogg");
+/// while playlist.read(&mut buf)? > 0 {
+///   while let Some(event) = playlist.poll_event() {
+///     println!("{:?}", event);
+///   }
+///   output(&buf);
+/// }
+/// ```
+pub struct Playlist {
+  samplerate: usize,
+  channels: usize,
+  queue: VecDeque<PathBuf>,
+  current: Option<Track>,
+  next_index: usize,
+  position: u64,
+  events: VecDeque<PlaylistEvent>,
+}
+
+impl Playlist {
+  /// Create an empty playlist producing frames of `channels` channels at `samplerate`.
+  ///
+  /// Returns `SndFileError::InvalidParameter` if `samplerate` or `channels` is zero.
+  pub fn new(samplerate: usize, channels: usize) -> Result<Playlist, SndFileError> {
+    if samplerate == 0 {
+      return Err(SndFileError::InvalidParameter(
+        "Got invalid samplerate, expect a positive number.".to_string(),
+      ));
+    }
+    if channels == 0 {
+      return Err(SndFileError::InvalidParameter(
+        "Got invalid channels, expect a positive number.".to_string(),
+      ));
+    }
+    Ok(Playlist {
+      samplerate,
+      channels,
+      queue: VecDeque::new(),
+      current: None,
+      next_index: 0,
+      position: 0,
+      events: VecDeque::new(),
+    })
+  }
+
+  /// Get sample rate.
+  pub fn get_samplerate(&self) -> usize {
+    self.samplerate
+  }
+
+  /// Get channel count.
+  pub fn get_channels(&self) -> usize {
+    self.channels
+  }
+
+  /// Append the file at `path` to the queue. Tracks are indexed from 0 in the order they are pushed.
+  pub fn push<P: AsRef<Path>>(&mut self, path: P) {
+    self.queue.push_back(path.as_ref().to_path_buf());
+  }
+
+  /// Number of tracks waiting in the queue, not counting the current track.
+  pub fn queue_len(&self) -> usize {
+    self.queue.len()
+  }
+
+  /// Index of the track being decoded.
+  pub fn current_index(&self) -> Option<usize> {
+    self.current.as_ref().map(|x| x.index)
+  }
+
+  /// Number of frames produced so far.
+  pub fn position(&self) -> u64 {
+    self.position
+  }
+
+  /// Take the oldest pending track boundary event.
+  pub fn poll_event(&mut self) -> Option<PlaylistEvent> {
+    self.events.pop_front()
+  }
+
+  /// Skip the rest of the current track and start the next one, returns `false` if the queue is
+  /// empty.
+  ///
+  /// If the next track can't be opened, the error is returned and the track is dropped.
+  pub fn next_track(&mut self) -> Result<bool, SndFileError> {
+    self.end_track(true);
+    self.start_track()
+  }
+
+  /// Read interleaved frames, returns the number of frames read, less than requested only when the
+  /// queue is exhausted.
+  ///
+  /// `dst.len()` must be a multiple of the channel count, otherwise `SndFileError::InvalidParameter`
+  /// is returned. If a track can't be opened or decoded, the error is returned and the track is
+  /// dropped, so the next read continues with the next track.
+  pub fn read(&mut self, dst: &mut [f32]) -> Result<usize, SndFileError> {
+    if dst.len() % self.channels != 0 {
+      return Err(SndFileError::InvalidParameter(format!(
+        "Got buffer length {}, expect a multiple of channel count {}.",
+        dst.len(),
+        self.channels
+      )));
+    }
+    let n_frames = dst.len() / self.channels;
+    let mut done = 0;
+    while done < n_frames {
+      if self.current.is_none() && !self.start_track()? {
+        break;
+      }
+      let r = self
+        .current
+        .as_mut()
+        .unwrap()
+        .read(&mut dst[done * self.channels..]);
+      let n = match r {
+        Ok(n) => n,
+        Err(e) => {
+          self.end_track(false);
+          return Err(e);
+        }
+      };
+      done += n;
+      self.position += n as u64;
+      if done < n_frames {
+        self.end_track(false);
+      }
+    }
+    Ok(done)
+  }
+
+  fn start_track(&mut self) -> Result<bool, SndFileError> {
+    let path = match self.queue.pop_front() {
+      Some(x) => x,
+      None => return Ok(false),
+    };
+    let index = self.next_index;
+    self.next_index += 1;
+    let snd = OpenOptions::ReadOnly(ReadOptions::Auto).from_path(&path)?;
+    if snd.get_channels() != self.channels {
+      return Err(SndFileError::InvalidParameter(format!(
+        "`{}` has {} channels, expect {} channels.",
+        path.display(),
+        snd.get_channels(),
+        self.channels
+      )));
+    }
+    self.current = Some(Track::new(snd, index, self.samplerate)?);
+    self.events.push_back(PlaylistEvent::TrackStarted {
+      index,
+      path,
+      position: self.position,
+    });
+    Ok(true)
+  }
+
+  fn end_track(&mut self, skipped: bool) {
+    if let Some(track) = self.current.take() {
+      self.events.push_back(PlaylistEvent::TrackEnded {
+        index: track.index,
+        position: self.position,
+        skipped,
+      });
+    }
+  }
+}
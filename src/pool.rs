@@ -0,0 +1,127 @@
+use super::{OpenOptions, ReadOptions, SndFile, SndFileError};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex, PoisonError};
+
+/// Several independent read only handles to the same audio file, so different threads can read
+/// different regions concurrently.
+///
+/// ```ignore
+/// let pool = SndFilePool::open("long.flac", 4)?;
+/// std::thread::scope(|s| {
+///   for tile in 0..16 {
+///     let pool = &pool;
+///     s.spawn(move || {
+///       let mut snd = pool.checkout();
+///       let mut buf = vec![0.0f32; 4096 * snd.get_channels()];
+///       snd.read_at(tile * 4096, &mut buf)
+///     });
+///   }
+/// });
+/// ```
+#[derive(Debug)]
+pub struct SndFilePool {
+  path: PathBuf,
+  n_handles: usize,
+  idle: Mutex<Vec<SndFile>>,
+  returned: Condvar,
+}
+
+/// A handle checked out of a `SndFilePool`, returned to the pool on drop.
+///
+/// The I/O cursor is wherever the previous user left it, seek before reading or use
+/// `SndFile::read_at`.
+#[derive(Debug)]
+pub struct PooledSndFile<'a> {
+  pool: &'a SndFilePool,
+  snd: Option<SndFile>,
+}
+
+impl SndFilePool {
+  /// Open `n_handles` read only handles to the file at `path`.
+  pub fn open<P: AsRef<Path>>(path: P, n_handles: usize) -> Result<SndFilePool, SndFileError> {
+    if n_handles == 0 {
+      return Err(SndFileError::InvalidParameter(
+        "Got 0 handles, expect a positive number.".to_string(),
+      ));
+    }
+    let path = path.as_ref().to_path_buf();
+    let handles = (0..n_handles)
+      .map(|_| OpenOptions::ReadOnly(ReadOptions::Auto).from_path(&path))
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(SndFilePool {
+      path,
+      n_handles,
+      idle: Mutex::new(handles),
+      returned: Condvar::new(),
+    })
+  }
+
+  /// Path of the file.
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// Number of handles managed by this pool.
+  pub fn n_handles(&self) -> usize {
+    self.n_handles
+  }
+
+  /// Check out a handle, blocks until one is available.
+  pub fn checkout(&self) -> PooledSndFile<'_> {
+    let mut idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner);
+    loop {
+      if let Some(snd) = idle.pop() {
+        return PooledSndFile {
+          pool: self,
+          snd: Some(snd),
+        };
+      }
+      idle = self
+        .returned
+        .wait(idle)
+        .unwrap_or_else(PoisonError::into_inner);
+    }
+  }
+
+  /// Check out a handle, returns `None` if all handles are in use.
+  pub fn try_checkout(&self) -> Option<PooledSndFile<'_>> {
+    let snd = self
+      .idle
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .pop()?;
+    Some(PooledSndFile {
+      pool: self,
+      snd: Some(snd),
+    })
+  }
+}
+
+impl Deref for PooledSndFile<'_> {
+  type Target = SndFile;
+
+  fn deref(&self) -> &SndFile {
+    self.snd.as_ref().unwrap()
+  }
+}
+
+impl DerefMut for PooledSndFile<'_> {
+  fn deref_mut(&mut self) -> &mut SndFile {
+    self.snd.as_mut().unwrap()
+  }
+}
+
+impl Drop for PooledSndFile<'_> {
+  fn drop(&mut self) {
+    if let Some(snd) = self.snd.take() {
+      self
+        .pool
+        .idle
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(snd);
+      self.pool.returned.notify_one();
+    }
+  }
+}
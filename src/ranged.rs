@@ -0,0 +1,94 @@
+use super::vio::resolve_seek;
+use std::collections::VecDeque;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+
+/// A remote byte source which can read arbitrary byte ranges, e.g. with HTTP range requests.
+pub(crate) trait FetchRange {
+  /// Fetch bytes in `range`, which is never empty and within the source.
+  fn fetch(&mut self, range: Range<u64>) -> io::Result<Vec<u8>>;
+}
+
+/// Blocks of the remote source, the most recently used first.
+struct BlockCache {
+  blocks: VecDeque<(u64, Vec<u8>)>,
+  capacity: usize,
+}
+
+impl BlockCache {
+  fn get(&mut self, index: u64) -> Option<&[u8]> {
+    let i = self.blocks.iter().position(|(x, _)| *x == index)?;
+    let block = self.blocks.remove(i).unwrap();
+    self.blocks.push_front(block);
+    Some(&self.blocks[0].1)
+  }
+
+  fn insert(&mut self, index: u64, data: Vec<u8>) {
+    if self.blocks.len() >= self.capacity {
+      self.blocks.pop_back();
+    }
+    self.blocks.push_front((index, data));
+  }
+}
+
+/// `Read` and `Seek` over a `FetchRange` of `len` bytes, fetching fixed-size blocks kept in a least
+/// recently used cache.
+pub(crate) struct RangedReader<F> {
+  pub(crate) fetcher: F,
+  len: u64,
+  pos: u64,
+  block_size: u64,
+  cache: BlockCache,
+}
+
+impl<F: FetchRange> RangedReader<F> {
+  /// `block_size` and `n_blocks` must be positive.
+  pub(crate) fn new(fetcher: F, len: u64, block_size: usize, n_blocks: usize) -> Self {
+    RangedReader {
+      fetcher,
+      len,
+      pos: 0,
+      block_size: block_size as u64,
+      cache: BlockCache {
+        blocks: VecDeque::with_capacity(n_blocks),
+        capacity: n_blocks,
+      },
+    }
+  }
+
+  pub(crate) fn len(&self) -> u64 {
+    self.len
+  }
+
+  pub(crate) fn position(&self) -> u64 {
+    self.pos
+  }
+}
+
+impl<F: FetchRange> Read for RangedReader<F> {
+  fn read(&mut self, dst: &mut [u8]) -> io::Result<usize> {
+    if self.pos >= self.len || dst.is_empty() {
+      return Ok(0);
+    }
+    let index = self.pos / self.block_size;
+    if self.cache.get(index).is_none() {
+      let start = index * self.block_size;
+      let end = (start + self.block_size).min(self.len);
+      let data = self.fetcher.fetch(start..end)?;
+      self.cache.insert(index, data);
+    }
+    let offset = (self.pos % self.block_size) as usize;
+    let block = self.cache.get(index).unwrap();
+    let n = block.len().saturating_sub(offset).min(dst.len());
+    dst[..n].copy_from_slice(&block[offset..offset + n]);
+    self.pos += n as u64;
+    Ok(n)
+  }
+}
+
+impl<F: FetchRange> Seek for RangedReader<F> {
+  fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+    self.pos = resolve_seek(pos, self.pos, self.len)?;
+    Ok(self.pos)
+  }
+}
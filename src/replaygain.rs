@@ -0,0 +1,104 @@
+use super::ogg::{rewrite_vorbis_comments, Codec};
+use super::{MajorFormat, OpenOptions, ReadOptions, SndFile, SndFileError};
+use std::path::Path;
+
+/// Reference loudness of ReplayGain 2.0.
+const REFERENCE_LUFS: f64 = -18.0;
+/// Reference loudness of `R128_*` gains of Ogg Opus, see RFC 7845.
+const OPUS_REFERENCE_LUFS: f64 = -23.0;
+
+/// ReplayGain 2.0 values of a track.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayGain {
+  /// EBU R128 integrated loudness in LUFS.
+  pub loudness_lufs: f64,
+  /// Gain to reach the reference loudness of -18 LUFS, in dB.
+  pub gain_db: f64,
+  /// Peak absolute sample value over all channels, `1.0` is full scale.
+  pub peak: f64,
+}
+
+impl SndFile {
+  /// Measure ReplayGain 2.0 values by streaming through the whole file.
+  ///
+  /// Returns `SndFileError::InvalidParameter` if the file is too short or too quiet to be measured.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn compute_replaygain(&mut self) -> Result<ReplayGain, SndFileError> {
+    let levels = self.measure_levels()?;
+    let loudness_lufs = levels.integrated_lufs.ok_or_else(|| {
+      SndFileError::InvalidParameter("File is too short or too quiet to be measured.".to_string())
+    })?;
+    Ok(ReplayGain {
+      loudness_lufs,
+      gain_db: REFERENCE_LUFS - loudness_lufs,
+      peak: levels.peak.iter().cloned().fold(0.0, f64::max),
+    })
+  }
+}
+
+/// Write track gain tags to the file at `path`, replacing existing ones.
+///
+/// Ogg Vorbis files get `REPLAYGAIN_TRACK_GAIN` and `REPLAYGAIN_TRACK_PEAK` comments, Ogg Opus
+/// files get `R128_TRACK_GAIN` as required by RFC 7845.
+///
+/// Only Ogg files are supported: ID3 frames and RIFF INFO fields can't be written, as libsndfile
+/// has no way to write arbitrary ones. Other formats, e.g. WAV, FLAC or MP3, are rejected with
+/// `SndFileError::InvalidParameter` and left untouched.
+///
+/// The file must not be opened by any `SndFile` at the same time.
+pub fn write_replaygain_tags<P: AsRef<Path>>(
+  path: P,
+  gain: &ReplayGain,
+) -> Result<(), SndFileError> {
+  let major_format = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)?
+    .get_major_format();
+  if major_format != MajorFormat::OGG {
+    return Err(SndFileError::InvalidParameter(format!(
+      "Got a {:?} file, expect an Ogg Vorbis or Ogg Opus file, ReplayGain tags can't be written to ID3 or RIFF INFO.",
+      major_format
+    )));
+  }
+  rewrite_vorbis_comments(path, |codec, comments| {
+    let (prefix, new) = match codec {
+      Codec::Vorbis => (
+        "REPLAYGAIN_TRACK_",
+        vec![
+          (
+            "REPLAYGAIN_TRACK_GAIN".to_string(),
+            format!("{:.2} dB", gain.gain_db),
+          ),
+          (
+            "REPLAYGAIN_TRACK_PEAK".to_string(),
+            format!("{:.6}", gain.peak),
+          ),
+        ],
+      ),
+      Codec::Opus => {
+        // Q7.8 fixed point.
+        let q = ((OPUS_REFERENCE_LUFS - gain.loudness_lufs) * 256.0).round();
+        let q = q.max(i16::MIN as f64).min(i16::MAX as f64) as i16;
+        (
+          "R128_TRACK_",
+          vec![("R128_TRACK_GAIN".to_string(), q.to_string())],
+        )
+      }
+    };
+    comments
+      .into_iter()
+      .filter(|(k, _)| !k.to_ascii_uppercase().starts_with(prefix))
+      .chain(new)
+      .collect()
+  })
+}
+
+/// Measure ReplayGain 2.0 values of the file at `path` and write them as tags, see
+/// `write_replaygain_tags`.
+pub fn apply_replaygain<P: AsRef<Path>>(path: P) -> Result<ReplayGain, SndFileError> {
+  let gain = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)?
+    .compute_replaygain()?;
+  write_replaygain_tags(path, &gain)?;
+  Ok(gain)
+}
@@ -0,0 +1,103 @@
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Lock-free single producer single consumer ring buffer of samples.
+///
+/// Only one thread may call `push` and only one thread may call `pop` at a time, which the owners
+/// of the two halves guarantee.
+pub(crate) struct Ring {
+  buf: Box<[UnsafeCell<f32>]>,
+  /// Total number of samples pushed, modulo twice the capacity.
+  head: AtomicUsize,
+  /// Total number of samples popped, modulo twice the capacity.
+  tail: AtomicUsize,
+}
+
+unsafe impl Sync for Ring {}
+
+impl Ring {
+  pub(crate) fn new(capacity: usize) -> Ring {
+    Ring {
+      buf: (0..capacity).map(|_| UnsafeCell::new(0.0)).collect(),
+      head: AtomicUsize::new(0),
+      tail: AtomicUsize::new(0),
+    }
+  }
+
+  pub(crate) fn capacity(&self) -> usize {
+    self.buf.len()
+  }
+
+  /// Number of samples ready to pop.
+  pub(crate) fn len(&self) -> usize {
+    let head = self.head.load(Ordering::Acquire);
+    self.distance(self.tail.load(Ordering::Acquire), head)
+  }
+
+  /// Number of samples from counter `from` to counter `to`, counters being kept modulo twice the
+  /// capacity so that a full ring is told apart from an empty one, and the slot mapping does not
+  /// jump when they wrap, whatever the capacity.
+  fn distance(&self, from: usize, to: usize) -> usize {
+    if to >= from {
+      to - from
+    } else {
+      to + 2 * self.capacity() - from
+    }
+  }
+
+  /// Advance counter `x` by `n` samples, `n` being at most the capacity.
+  fn advance(&self, x: usize, n: usize) -> usize {
+    let x = x + n;
+    if x >= 2 * self.capacity() {
+      x - 2 * self.capacity()
+    } else {
+      x
+    }
+  }
+
+  /// Slot of counter `x`.
+  fn slot(&self, x: usize) -> usize {
+    if x >= self.capacity() {
+      x - self.capacity()
+    } else {
+      x
+    }
+  }
+
+  fn ptr(&self) -> *mut f32 {
+    // `UnsafeCell<f32>` has the same layout as `f32`.
+    self.buf.as_ptr() as *mut f32
+  }
+
+  /// Push at most `src.len()` samples, returns the number of samples pushed.
+  pub(crate) fn push(&self, src: &[f32]) -> usize {
+    let cap = self.capacity();
+    let head = self.head.load(Ordering::Relaxed);
+    let tail = self.tail.load(Ordering::Acquire);
+    let n = src.len().min(cap - self.distance(tail, head));
+    let start = self.slot(head);
+    let first = n.min(cap - start);
+    unsafe {
+      std::ptr::copy_nonoverlapping(src.as_ptr(), self.ptr().add(start), first);
+      std::ptr::copy_nonoverlapping(src.as_ptr().add(first), self.ptr(), n - first);
+    }
+    self.head.store(self.advance(head, n), Ordering::Release);
+    n
+  }
+
+  /// Pop at most `dst.len()` samples, returns the number of samples popped.
+  pub(crate) fn pop(&self, dst: &mut [f32]) -> usize {
+    let cap = self.capacity();
+    let tail = self.tail.load(Ordering::Relaxed);
+    let head = self.head.load(Ordering::Acquire);
+    let n = dst.len().min(self.distance(tail, head));
+    let start = self.slot(tail);
+    let first = n.min(cap - start);
+    unsafe {
+      std::ptr::copy_nonoverlapping(self.ptr().add(start), dst.as_mut_ptr(), first);
+      std::ptr::copy_nonoverlapping(self.ptr(), dst.as_mut_ptr().add(first), n - first);
+    }
+    self.tail.store(self.advance(tail, n), Ordering::Release);
+    n
+  }
+}
@@ -0,0 +1,139 @@
+use super::ffi;
+#[cfg(not(feature = "system_linking"))]
+use super::sys as sndfile_sys;
+use super::{sf_err_code_to_enum, SndFile, SndFileError};
+use sndfile_sys::sf_count_t;
+use std::io::SeekFrom;
+
+/// Error of `RtReader`, it is `Copy` so returning it never allocates.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RtError {
+  /// The buffer length is not a multiple of the channel count.
+  InvalidBufferLength,
+  /// The file is not seekable.
+  NotSeekable,
+  /// A libsndfile error code, convert it into `SndFileError` to get the message.
+  Sndfile(i32),
+}
+
+impl From<RtError> for SndFileError {
+  fn from(e: RtError) -> SndFileError {
+    match e {
+      RtError::InvalidBufferLength => SndFileError::InvalidParameter(
+        "Got buffer length not multiple of channel count, expect whole frames.".to_string(),
+      ),
+      RtError::NotSeekable => SndFileError::Unseekable { len_hint: None },
+      RtError::Sndfile(code) => sf_err_code_to_enum(code),
+    }
+  }
+}
+
+/// Sample types readable by `RtReader`.
+pub trait RtSample: private::Sealed + Copy {
+  #[doc(hidden)]
+  unsafe fn readf(
+    ptr: *mut sndfile_sys::SNDFILE,
+    dst: *mut Self,
+    n_frames: sf_count_t,
+  ) -> sf_count_t;
+}
+
+mod private {
+  pub trait Sealed {}
+  impl Sealed for i16 {}
+  impl Sealed for i32 {}
+  impl Sealed for f32 {}
+  impl Sealed for f64 {}
+}
+
+macro_rules! impl_rt_sample {
+  ($t:ty, $f:ident) => {
+    impl RtSample for $t {
+      unsafe fn readf(
+        ptr: *mut sndfile_sys::SNDFILE,
+        dst: *mut Self,
+        n_frames: sf_count_t,
+      ) -> sf_count_t {
+        ffi::$f(ptr, dst, n_frames)
+      }
+    }
+  };
+}
+
+impl_rt_sample!(i16, sf_readf_short);
+impl_rt_sample!(i32, sf_readf_int);
+impl_rt_sample!(f32, sf_readf_float);
+impl_rt_sample!(f64, sf_readf_double);
+
+/// A `SndFile` restricted to operations that can be called from a realtime audio callback.
+///
+/// `read_to_slice_rt` and `seek_rt` do not allocate, lock or panic in this crate, errors are
+/// returned as `RtError` codes. The I/O itself still goes through libsndfile and the file system,
+/// so it may block on a disk read unless the file is cached, and decoders of compressed formats
+/// like FLAC or Vorbis may allocate inside their libraries. PCM and float encodings do not.
+///
+/// ```ignore
+/// let mut rt = RtReader::new(OpenOptions::ReadOnly(ReadOptions::Auto).from_path("loop.wav")?);
+/// // In the audio callback:
+/// if rt.read_to_slice_rt(out).unwrap_or(0) == 0 {
+///   out.iter_mut().for_each(|x| *x = 0.0);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct RtReader(SndFile);
+
+impl RtReader {
+  /// Wrap `snd`, which should be opened for reading.
+  pub fn new(snd: SndFile) -> RtReader {
+    RtReader(snd)
+  }
+
+  /// Borrow the underlying `SndFile`, e.g. to get its format or tags.
+  pub fn get_ref(&self) -> &SndFile {
+    &self.0
+  }
+
+  /// Unwrap the underlying `SndFile`.
+  pub fn into_inner(self) -> SndFile {
+    self.0
+  }
+
+  /// Read frames from current I/O cursor, returns the number of frames read if success.
+  ///
+  /// `dst.len()` must be a multiple of the channel count, otherwise
+  /// `RtError::InvalidBufferLength` is returned.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn read_to_slice_rt<T: RtSample>(&mut self, dst: &mut [T]) -> Result<usize, RtError> {
+    let channels = self.0.channels;
+    if dst.len() % channels != 0 {
+      return Err(RtError::InvalidBufferLength);
+    }
+    let ptr = self.0.unsafe_fields.sndfile_ptr;
+    let n = unsafe { T::readf(ptr, dst.as_mut_ptr(), (dst.len() / channels) as sf_count_t) };
+    if n >= 0 {
+      Ok(n as usize)
+    } else {
+      Err(RtError::Sndfile(unsafe { ffi::sf_error(ptr) }))
+    }
+  }
+
+  /// Seek to a frame position, returns the new position, see `SndFile::seek`.
+  pub fn seek_rt(&mut self, pos: SeekFrom) -> Result<u64, RtError> {
+    if !self.0.is_seekable() {
+      return Err(RtError::NotSeekable);
+    }
+    let (offset, whence) = match pos {
+      SeekFrom::Start(x) => (x as sf_count_t, sndfile_sys::SF_SEEK_SET),
+      SeekFrom::Current(x) => (x as sf_count_t, sndfile_sys::SF_SEEK_CUR),
+      SeekFrom::End(x) => (x as sf_count_t, sndfile_sys::SF_SEEK_END),
+    };
+    let ptr = self.0.unsafe_fields.sndfile_ptr;
+    let r = unsafe { ffi::sf_seek(ptr, offset, whence) };
+    if r >= 0 {
+      Ok(r as u64)
+    } else {
+      Err(RtError::Sndfile(unsafe { ffi::sf_error(ptr) }))
+    }
+  }
+}
@@ -0,0 +1,173 @@
+use super::{detect_format, probe, MajorFormat, SndFileError, SndFileInfo};
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Bytes read to detect the format of files without a known extension, see `detect_format`.
+const MAGIC_LEN: u64 = 64;
+
+/// Options of `scan`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanOptions {
+  max_depth: Option<usize>,
+  follow_symlinks: bool,
+  magic_bytes: bool,
+}
+
+impl Default for ScanOptions {
+  fn default() -> Self {
+    ScanOptions::new()
+  }
+}
+
+impl ScanOptions {
+  /// Create new ScanOptions, scanning all subdirectories without following symbolic links, and
+  /// detecting the format of files without a known extension by their magic bytes.
+  pub fn new() -> Self {
+    ScanOptions {
+      max_depth: None,
+      follow_symlinks: false,
+      magic_bytes: true,
+    }
+  }
+
+  /// Only scan subdirectories up to `depth` levels below the scanned directory, `0` scans the
+  /// directory itself only.
+  pub fn with_max_depth(mut self, depth: usize) -> Self {
+    self.max_depth = Some(depth);
+    self
+  }
+
+  /// Follow symbolic links to files and directories, each directory is still scanned once.
+  pub fn with_follow_symlinks(mut self, follow: bool) -> Self {
+    self.follow_symlinks = follow;
+    self
+  }
+
+  /// Read the leading bytes of files without a known extension to detect audio files, otherwise
+  /// such files are skipped.
+  pub fn with_magic_bytes(mut self, magic_bytes: bool) -> Self {
+    self.magic_bytes = magic_bytes;
+    self
+  }
+}
+
+/// Error of `scan`, with the directory which failed to be read or the file which failed to be
+/// probed.
+#[derive(Debug)]
+pub struct ScanError {
+  pub path: PathBuf,
+  pub error: SndFileError,
+}
+
+/// Iterator of audio files found by `scan`.
+#[derive(Debug)]
+pub struct Scan {
+  options: ScanOptions,
+  /// Directories to scan, with their depth.
+  dirs: Vec<(PathBuf, usize)>,
+  /// Files of the last scanned directory.
+  files: VecDeque<PathBuf>,
+  /// Directories already scanned, when following symbolic links.
+  visited: HashSet<PathBuf>,
+}
+
+/// Walk `dir` recursively and probe the files which look like audio, by extension or by magic
+/// bytes, yielding their paths and information, see `probe`.
+///
+/// Entries are visited in the order of their names, files of a directory before its
+/// subdirectories. Directories which can't be read and audio files which fail to open are yielded
+/// as `ScanError` with their path, and the walk goes on.
+///
+/// ```ignore
+/// for entry in sndfile::scan("/music", ScanOptions::new()) {
+///   match entry {
+///     Ok((path, info)) => println!("{}: {:?}", path.display(), info.duration),
+///     Err(e) => eprintln!("{}: {:?}", e.path.display(), e.error),
+///   }
+/// }
+/// ```
+pub fn scan<P: AsRef<Path>>(dir: P, options: ScanOptions) -> Scan {
+  Scan {
+    options,
+    dirs: vec![(dir.as_ref().to_path_buf(), 0)],
+    files: VecDeque::new(),
+    visited: HashSet::new(),
+  }
+}
+
+impl Scan {
+  /// List `dir`, queue its files and push its subdirectories.
+  fn read_dir(&mut self, dir: &Path, depth: usize) -> std::io::Result<()> {
+    if self.options.follow_symlinks && !self.visited.insert(dir.canonicalize()?) {
+      return Ok(());
+    }
+    let mut entries = std::fs::read_dir(dir)?
+      .map(|x| x.map(|x| (x.path(), x.file_type())))
+      .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by(|x, y| x.0.cmp(&y.0));
+    let mut subdirs = Vec::new();
+    for (path, file_type) in entries {
+      let file_type = match file_type {
+        Ok(x) if x.is_symlink() && self.options.follow_symlinks => match path.metadata() {
+          Ok(x) => x.file_type(),
+          // Broken links are skipped.
+          Err(_) => continue,
+        },
+        Ok(x) => x,
+        Err(_) => continue,
+      };
+      if file_type.is_dir() {
+        if !matches!(self.options.max_depth, Some(x) if depth >= x) {
+          subdirs.push((path, depth + 1));
+        }
+      } else if file_type.is_file() {
+        self.files.push_back(path);
+      }
+    }
+    self.dirs.extend(subdirs.into_iter().rev());
+    Ok(())
+  }
+
+  /// Check if `path` looks like an audio file.
+  fn is_audio(&self, path: &Path) -> std::io::Result<bool> {
+    let ext = path.extension().and_then(|x| x.to_str()).unwrap_or("");
+    if MajorFormat::from_extension(ext).is_some() {
+      return Ok(true);
+    }
+    if !self.options.magic_bytes {
+      return Ok(false);
+    }
+    let mut header = Vec::with_capacity(MAGIC_LEN as usize);
+    File::open(path)?.take(MAGIC_LEN).read_to_end(&mut header)?;
+    Ok(detect_format(&header).is_some())
+  }
+}
+
+impl Iterator for Scan {
+  type Item = Result<(PathBuf, SndFileInfo), ScanError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(path) = self.files.pop_front() {
+        let error = match self.is_audio(&path) {
+          Ok(true) => match probe(&path) {
+            Ok(x) => return Some(Ok((path, x))),
+            Err(e) => e,
+          },
+          Ok(false) => continue,
+          Err(e) => SndFileError::IOError(e),
+        };
+        return Some(Err(ScanError { path, error }));
+      }
+      let (dir, depth) = self.dirs.pop()?;
+      if let Err(e) = self.read_dir(&dir, depth) {
+        return Some(Err(ScanError {
+          path: dir,
+          error: SndFileError::IOError(e),
+        }));
+      }
+    }
+  }
+}
@@ -0,0 +1,151 @@
+//! Writers of standard test signals, e.g. a 1 kHz tone for calibration or noise for tests.
+//!
+//! Every function appends `n_frames` frames at the I/O cursor of the writer, with the same signal in
+//! all channels. Levels are peak levels in dBFS, e.g. `-20.0`.
+
+use super::edit::BLOCK_FRAMES;
+use super::{SndFileError, SndWriter};
+use std::f64::consts::PI;
+
+/// Append frames of `next` to `w`, each call gives the sample of the next frame.
+fn write_signal<F: FnMut() -> f64>(
+  w: &mut SndWriter,
+  n_frames: u64,
+  mut next: F,
+) -> Result<(), SndFileError> {
+  let channels = w.get_ref().get_channels();
+  let mut buf = vec![0.0f64; BLOCK_FRAMES * channels];
+  let mut written = 0;
+  while written < n_frames {
+    let n = (n_frames - written).min(BLOCK_FRAMES as u64) as usize;
+    for frame in buf[..n * channels].chunks_exact_mut(channels) {
+      let x = next();
+      frame.iter_mut().for_each(|y| *y = x);
+    }
+    w.write_from_slice(&buf[..n * channels])?;
+    written += n as u64;
+  }
+  Ok(())
+}
+
+fn db_to_amplitude(level_db: f64) -> f64 {
+  10.0f64.powf(level_db / 20.0)
+}
+
+/// xorshift64*, good enough for test noise and reproducible across platforms.
+struct Rng(u64);
+
+impl Rng {
+  fn new(seed: u64) -> Self {
+    // The state must not be zero, which would give a constant stream.
+    match seed ^ 0x9e37_79b9_7f4a_7c15 {
+      0 => Rng(0x9e37_79b9_7f4a_7c15),
+      x => Rng(x),
+    }
+  }
+
+  /// A uniform sample in `[-1, 1)`.
+  fn next_f64(&mut self) -> f64 {
+    self.0 ^= self.0 >> 12;
+    self.0 ^= self.0 << 25;
+    self.0 ^= self.0 >> 27;
+    let x = self.0.wrapping_mul(0x2545_f491_4f6c_dd1d);
+    (x >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+  }
+}
+
+/// Write a sine wave of `freq` Hz.
+pub fn sine(
+  w: &mut SndWriter,
+  freq: f64,
+  level_db: f64,
+  n_frames: u64,
+) -> Result<(), SndFileError> {
+  let amp = db_to_amplitude(level_db);
+  let step = 2.0 * PI * freq / w.get_ref().get_samplerate() as f64;
+  let mut i = 0u64;
+  write_signal(w, n_frames, || {
+    let x = amp * (step * i as f64).sin();
+    i += 1;
+    x
+  })
+}
+
+/// Write uniform white noise, the same `seed` gives the same noise.
+pub fn white_noise(
+  w: &mut SndWriter,
+  level_db: f64,
+  n_frames: u64,
+  seed: u64,
+) -> Result<(), SndFileError> {
+  let amp = db_to_amplitude(level_db);
+  let mut rng = Rng::new(seed);
+  write_signal(w, n_frames, || amp * rng.next_f64())
+}
+
+/// Write pink noise (-3 dB per octave), the same `seed` gives the same noise.
+///
+/// White noise is filtered by the approximation of Paul Kellet, accurate to 0.05 dB above 9.2 Hz at
+/// 44.1 kHz. Rare peaks above the level are clipped.
+pub fn pink_noise(
+  w: &mut SndWriter,
+  level_db: f64,
+  n_frames: u64,
+  seed: u64,
+) -> Result<(), SndFileError> {
+  let amp = db_to_amplitude(level_db);
+  let mut rng = Rng::new(seed);
+  let mut b = [0.0f64; 7];
+  write_signal(w, n_frames, || {
+    let white = rng.next_f64();
+    b[0] = 0.99886 * b[0] + white * 0.0555179;
+    b[1] = 0.99332 * b[1] + white * 0.0750759;
+    b[2] = 0.96900 * b[2] + white * 0.1538520;
+    b[3] = 0.86650 * b[3] + white * 0.3104856;
+    b[4] = 0.55000 * b[4] + white * 0.5329522;
+    b[5] = -0.7616 * b[5] - white * 0.0168980;
+    let pink = b.iter().sum::<f64>() + white * 0.5362;
+    b[6] = white * 0.115926;
+    // Scale the filter gain back to about unity.
+    (amp * pink * 0.11).max(-amp).min(amp)
+  })
+}
+
+/// Write a logarithmic sine sweep from `start_freq` to `end_freq` Hz, both must be positive.
+///
+/// The frequency doubles in equal time steps, the usual sweep for measuring impulse responses.
+pub fn sweep(
+  w: &mut SndWriter,
+  start_freq: f64,
+  end_freq: f64,
+  level_db: f64,
+  n_frames: u64,
+) -> Result<(), SndFileError> {
+  if !(start_freq > 0.0 && end_freq > 0.0) {
+    return Err(SndFileError::InvalidParameter(format!(
+      "Got sweep from {} Hz to {} Hz, expect positive frequencies.",
+      start_freq, end_freq
+    )));
+  }
+  let amp = db_to_amplitude(level_db);
+  let samplerate = w.get_ref().get_samplerate() as f64;
+  let duration = n_frames as f64 / samplerate;
+  let ratio = (end_freq / start_freq).ln();
+  let mut i = 0u64;
+  write_signal(w, n_frames, || {
+    let t = i as f64 / samplerate;
+    // Phase of the exponential sweep of Farina, the integral of its instantaneous frequency.
+    let phase = if ratio == 0.0 {
+      2.0 * PI * start_freq * t
+    } else {
+      2.0 * PI * start_freq * duration / ratio * ((t / duration * ratio).exp() - 1.0)
+    };
+    i += 1;
+    amp * phase.sin()
+  })
+}
+
+/// Write silence.
+pub fn silence(w: &mut SndWriter, n_frames: u64) -> Result<(), SndFileError> {
+  w.write_silence(n_frames).map(|_| ())
+}
@@ -0,0 +1,239 @@
+use super::edit::BLOCK_FRAMES;
+use super::{SndFile, SndFileError, SndFileIO, SubtypeFormat};
+use std::io::SeekFrom;
+
+/// Sample types accepted by `Stats` and `best_subtype_for`, converted to `f64` normalized like
+/// libsndfile does, i.e. integers are divided by `2^(bits - 1)`.
+pub trait Sample: Copy {
+  /// Subtypes in order of preference, the ones preserving every value of the type first, then
+  /// the others from the highest precision to the lowest.
+  const PREFERRED_SUBTYPES: &'static [SubtypeFormat];
+
+  fn to_normalized(self) -> f64;
+}
+
+impl Sample for i16 {
+  const PREFERRED_SUBTYPES: &'static [SubtypeFormat] = &[
+    SubtypeFormat::PCM_16,
+    SubtypeFormat::ALAC_16,
+    SubtypeFormat::PCM_24,
+    SubtypeFormat::ALAC_24,
+    SubtypeFormat::PCM_32,
+    SubtypeFormat::ALAC_32,
+    SubtypeFormat::FLOAT,
+    SubtypeFormat::DOUBLE,
+  ];
+
+  fn to_normalized(self) -> f64 {
+    self as f64 / 32768.0
+  }
+}
+
+impl Sample for i32 {
+  const PREFERRED_SUBTYPES: &'static [SubtypeFormat] = &[
+    SubtypeFormat::PCM_32,
+    SubtypeFormat::ALAC_32,
+    SubtypeFormat::DOUBLE,
+    SubtypeFormat::PCM_24,
+    SubtypeFormat::ALAC_24,
+    SubtypeFormat::FLOAT,
+    SubtypeFormat::PCM_16,
+    SubtypeFormat::ALAC_16,
+  ];
+
+  fn to_normalized(self) -> f64 {
+    self as f64 / 2147483648.0
+  }
+}
+
+impl Sample for f32 {
+  const PREFERRED_SUBTYPES: &'static [SubtypeFormat] = &[
+    SubtypeFormat::FLOAT,
+    SubtypeFormat::DOUBLE,
+    SubtypeFormat::PCM_32,
+    SubtypeFormat::ALAC_32,
+    SubtypeFormat::PCM_24,
+    SubtypeFormat::ALAC_24,
+    SubtypeFormat::PCM_16,
+    SubtypeFormat::ALAC_16,
+  ];
+
+  fn to_normalized(self) -> f64 {
+    self as f64
+  }
+}
+
+impl Sample for f64 {
+  const PREFERRED_SUBTYPES: &'static [SubtypeFormat] = &[
+    SubtypeFormat::DOUBLE,
+    SubtypeFormat::FLOAT,
+    SubtypeFormat::PCM_32,
+    SubtypeFormat::ALAC_32,
+    SubtypeFormat::PCM_24,
+    SubtypeFormat::ALAC_24,
+    SubtypeFormat::PCM_16,
+    SubtypeFormat::ALAC_16,
+  ];
+
+  fn to_normalized(self) -> f64 {
+    self
+  }
+}
+
+/// Per-channel statistics accumulated over interleaved frames, for QC reports.
+///
+/// Feed it the frames passing through `read_to_slice` or `write_from_slice`, or use
+/// `SndFile::analyze` to scan a whole file.
+///
+/// ```ignore
+/// let mut stats = Stats::new(snd.get_channels())?;
+/// loop {
+///   let n = snd.read_to_slice(&mut buf)?;
+///   if n == 0 {
+///     break;
+///   }
+///   stats.process(&buf[..n * snd.get_channels()]);
+///   // ...
+/// }
+/// println!("{:?} {:?}", stats.rms(), stats.clipped());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+  clip_level: f64,
+  n_frames: u64,
+  min: Vec<f64>,
+  max: Vec<f64>,
+  sum: Vec<f64>,
+  sum_sq: Vec<f64>,
+  clipped: Vec<u64>,
+}
+
+impl Stats {
+  /// Create empty statistics of `channels` channels, counting samples with an absolute normalized
+  /// value of at least `1.0` as clipped.
+  ///
+  /// Returns `InvalidParameter` if `channels` is 0.
+  pub fn new(channels: usize) -> Result<Stats, SndFileError> {
+    Stats::with_clip_level(channels, 1.0)
+  }
+
+  /// Create empty statistics counting samples with an absolute normalized value of at least
+  /// `clip_level` as clipped.
+  ///
+  /// The positive full scale of integer samples is below `1.0`, e.g. `32767.0 / 32768.0` for
+  /// 16 bit, use it as `clip_level` to count them.
+  ///
+  /// Returns `InvalidParameter` if `channels` is 0.
+  pub fn with_clip_level(channels: usize, clip_level: f64) -> Result<Stats, SndFileError> {
+    if channels == 0 {
+      return Err(SndFileError::InvalidParameter(
+        "Got invalid channels, expect a positive number.".to_string(),
+      ));
+    }
+    Ok(Stats {
+      clip_level,
+      n_frames: 0,
+      min: vec![0.0; channels],
+      max: vec![0.0; channels],
+      sum: vec![0.0; channels],
+      sum_sq: vec![0.0; channels],
+      clipped: vec![0; channels],
+    })
+  }
+
+  /// Accumulate interleaved frames, trailing samples that do not form a whole frame are ignored.
+  pub fn process<T: Sample>(&mut self, frames: &[T]) {
+    let channels = self.min.len();
+    for frame in frames.chunks_exact(channels) {
+      for (i, x) in frame.iter().enumerate() {
+        let x = x.to_normalized();
+        if self.n_frames == 0 {
+          self.min[i] = x;
+          self.max[i] = x;
+        } else {
+          self.min[i] = self.min[i].min(x);
+          self.max[i] = self.max[i].max(x);
+        }
+        self.sum[i] += x;
+        self.sum_sq[i] += x * x;
+        if x.abs() >= self.clip_level {
+          self.clipped[i] += 1;
+        }
+      }
+      self.n_frames += 1;
+    }
+  }
+
+  /// Get channel count.
+  pub fn channels(&self) -> usize {
+    self.min.len()
+  }
+
+  /// Number of frames accumulated.
+  pub fn frames(&self) -> u64 {
+    self.n_frames
+  }
+
+  /// Minimum sample value of each channel, `0.0` if no frame is accumulated.
+  pub fn min(&self) -> &[f64] {
+    &self.min
+  }
+
+  /// Maximum sample value of each channel, `0.0` if no frame is accumulated.
+  pub fn max(&self) -> &[f64] {
+    &self.max
+  }
+
+  /// RMS of each channel.
+  pub fn rms(&self) -> Vec<f64> {
+    self.sum_sq.iter().map(|x| self.mean(*x).sqrt()).collect()
+  }
+
+  /// DC offset, i.e. mean sample value, of each channel.
+  pub fn dc_offset(&self) -> Vec<f64> {
+    self.sum.iter().map(|x| self.mean(*x)).collect()
+  }
+
+  /// Number of clipped samples of each channel.
+  pub fn clipped(&self) -> &[u64] {
+    &self.clipped
+  }
+
+  fn mean(&self, sum: f64) -> f64 {
+    if self.n_frames > 0 {
+      sum / self.n_frames as f64
+    } else {
+      0.0
+    }
+  }
+}
+
+impl SndFile {
+  /// Compute `Stats` by streaming through the whole file.
+  ///
+  /// For integer PCM encodings, samples at positive or negative full scale count as clipped. For
+  /// other encodings, samples with an absolute value of at least `1.0` do.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn analyze(&mut self) -> Result<Stats, SndFileError> {
+    let channels = self.get_channels();
+    let subtype = self.get_subtype_format();
+    let clip_level = match subtype.bit_depth() {
+      Some(bits) if !subtype.is_float() && !subtype.is_lossy() && bits <= 32 => {
+        1.0 - 0.5f64.powi(bits as i32 - 1)
+      }
+      _ => 1.0,
+    };
+    let mut stats = Stats::with_clip_level(channels, clip_level)?;
+    let mut buf = vec![0.0f64; BLOCK_FRAMES * channels];
+    self.seek(SeekFrom::Start(0))?;
+    loop {
+      let n = self.read_to_slice(&mut buf)?;
+      if n == 0 {
+        break;
+      }
+      stats.process(&buf[..n * channels]);
+    }
+    Ok(stats)
+  }
+}
@@ -0,0 +1,100 @@
+use super::edit::BLOCK_FRAMES;
+use super::{OpenOptions, ReadOptions, SndFile, SndFileError, SndFileIO};
+use std::io::SeekFrom;
+use std::path::Path;
+
+macro_rules! impl_common {
+  ($t:ident) => {
+    #[allow(clippy::len_without_is_empty)]
+    impl $t {
+      /// Open the file at `path` for reading, returns `SndFileError::InvalidParameter` if it has
+      /// another channel count.
+      pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, SndFileError> {
+        Self::new(OpenOptions::ReadOnly(ReadOptions::Auto).from_path(path)?)
+      }
+
+      /// Borrow the underlying `SndFile`, e.g. to get its samplerate or tags.
+      pub fn get_ref(&self) -> &SndFile {
+        &self.inner
+      }
+
+      /// Unwrap the underlying `SndFile`.
+      pub fn into_inner(self) -> SndFile {
+        self.inner
+      }
+
+      /// See `SndFile::seek`.
+      pub fn seek(&mut self, pos: SeekFrom) -> Result<u64, SndFileError> {
+        self.inner.seek(pos)
+      }
+
+      /// See `SndFile::len`.
+      pub fn len(&mut self) -> Result<u64, SndFileError> {
+        self.inner.len()
+      }
+    }
+  };
+}
+
+/// Reader of mono files as `f32` samples, without interleaving to care about.
+#[derive(Debug)]
+pub struct MonoReader {
+  inner: SndFile,
+}
+
+impl MonoReader {
+  /// Wrap `snd`, returns `SndFileError::InvalidParameter` if it is not a mono file.
+  pub fn new(snd: SndFile) -> Result<Self, SndFileError> {
+    snd.check_channels::<1>()?;
+    Ok(MonoReader { inner: snd })
+  }
+
+  /// Read samples from current I/O cursor, returns the number of samples read if success.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn read(&mut self, dst: &mut [f32]) -> Result<usize, SndFileError> {
+    self.inner.read_to_slice(dst)
+  }
+}
+
+/// Reader of stereo files as `(left, right)` pairs of `f32` samples.
+#[derive(Debug)]
+pub struct StereoReader {
+  inner: SndFile,
+  buf: Vec<[f32; 2]>,
+}
+
+impl StereoReader {
+  /// Wrap `snd`, returns `SndFileError::InvalidParameter` if it is not a stereo file.
+  pub fn new(snd: SndFile) -> Result<Self, SndFileError> {
+    snd.check_channels::<2>()?;
+    Ok(StereoReader {
+      inner: snd,
+      buf: Vec::new(),
+    })
+  }
+
+  /// Read frames from current I/O cursor, returns the number of frames read if success.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn read(&mut self, dst: &mut [(f32, f32)]) -> Result<usize, SndFileError> {
+    // The layout of tuples is unspecified, so frames are read to arrays first.
+    self.buf.resize(dst.len().min(BLOCK_FRAMES), [0.0; 2]);
+    let mut done = 0;
+    for block in dst.chunks_mut(BLOCK_FRAMES) {
+      let buf = &mut self.buf[..block.len()];
+      let n = self.inner.read_frames(buf)?;
+      for (x, y) in block.iter_mut().zip(&buf[..n]) {
+        *x = (y[0], y[1]);
+      }
+      done += n;
+      if n < block.len() {
+        break;
+      }
+    }
+    Ok(done)
+  }
+}
+
+impl_common!(MonoReader);
+impl_common!(StereoReader);
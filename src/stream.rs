@@ -0,0 +1,310 @@
+use super::ring::Ring;
+use super::{SndFile, SndFileError, SndFileIO};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+struct BridgeShared {
+  ring: Ring,
+  channels: usize,
+  finished: AtomicBool,
+  underruns: AtomicU64,
+  underrun_frames: AtomicU64,
+}
+
+/// A `SndFile` decoded on a disk thread into a lock-free ring buffer, played from a realtime audio
+/// callback.
+///
+/// ```ignore
+/// let snd = OpenOptions::ReadOnly(ReadOptions::Auto).from_path("song.flac")?;
+/// let (mut producer, mut consumer) = StreamBridge::new(snd, 48000)?.split();
+/// std::thread::spawn(move || {
+///   while !producer.is_finished() {
+///     producer.fill().unwrap();
+///     std::thread::sleep(Duration::from_millis(10));
+///   }
+/// });
+/// // In the audio callback:
+/// consumer.read(out);
+/// ```
+pub struct StreamBridge {
+  producer: StreamProducer,
+  consumer: StreamConsumer,
+}
+
+/// Disk thread half of a `StreamBridge`, decoding from the `SndFile`.
+pub struct StreamProducer {
+  snd: SndFile,
+  shared: Arc<BridgeShared>,
+  scratch: Vec<f32>,
+}
+
+/// Realtime half of a `StreamBridge`.
+///
+/// Its methods do not allocate, lock or block. Keep it alive in the audio thread as long as the
+/// producer, so the shared buffer is not freed there.
+pub struct StreamConsumer {
+  shared: Arc<BridgeShared>,
+}
+
+impl StreamBridge {
+  /// Create a bridge buffering at most `capacity_frames` frames decoded from the I/O cursor of
+  /// `snd`.
+  pub fn new(snd: SndFile, capacity_frames: usize) -> Result<StreamBridge, SndFileError> {
+    if capacity_frames == 0 {
+      return Err(SndFileError::InvalidParameter(
+        "Got 0 frames of capacity, expect a positive number.".to_string(),
+      ));
+    }
+    let channels = snd.get_channels();
+    let shared = Arc::new(BridgeShared {
+      ring: Ring::new(capacity_frames * channels),
+      channels,
+      finished: AtomicBool::new(false),
+      underruns: AtomicU64::new(0),
+      underrun_frames: AtomicU64::new(0),
+    });
+    Ok(StreamBridge {
+      producer: StreamProducer {
+        snd,
+        shared: shared.clone(),
+        scratch: vec![0.0; capacity_frames * channels],
+      },
+      consumer: StreamConsumer { shared },
+    })
+  }
+
+  /// Split into the producer for the disk thread and the consumer for the audio thread.
+  pub fn split(self) -> (StreamProducer, StreamConsumer) {
+    (self.producer, self.consumer)
+  }
+}
+
+impl StreamProducer {
+  /// Borrow the underlying `SndFile`.
+  pub fn get_ref(&self) -> &SndFile {
+    &self.snd
+  }
+
+  /// Decode frames until the buffer is full or the end of file is reached, returns the number of
+  /// frames decoded.
+  pub fn fill(&mut self) -> Result<usize, SndFileError> {
+    let shared = &self.shared;
+    if shared.finished.load(Ordering::Acquire) {
+      return Ok(0);
+    }
+    let free = shared.ring.capacity() - shared.ring.len();
+    let n_samples = free / shared.channels * shared.channels;
+    if n_samples == 0 {
+      return Ok(0);
+    }
+    let n = self.snd.read_to_slice(&mut self.scratch[..n_samples])?;
+    shared.ring.push(&self.scratch[..n * shared.channels]);
+    if n * shared.channels < n_samples {
+      shared.finished.store(true, Ordering::Release);
+    }
+    Ok(n)
+  }
+
+  /// Returns `true` once the end of file is reached.
+  pub fn is_finished(&self) -> bool {
+    self.shared.finished.load(Ordering::Acquire)
+  }
+
+  /// Unwrap the underlying `SndFile`.
+  pub fn into_inner(self) -> SndFile {
+    self.snd
+  }
+}
+
+impl StreamConsumer {
+  /// Get channel count.
+  pub fn channels(&self) -> usize {
+    self.shared.channels
+  }
+
+  /// Number of frames buffered.
+  pub fn available(&self) -> usize {
+    self.shared.ring.len() / self.shared.channels
+  }
+
+  /// Read interleaved frames, returns the number of frames read.
+  ///
+  /// Missing frames are filled with silence. They count as an underrun unless the end of file was
+  /// reached. Trailing samples of `dst` that do not form a whole frame are left untouched.
+  pub fn read(&mut self, dst: &mut [f32]) -> usize {
+    let shared = &*self.shared;
+    let len = dst.len() / shared.channels * shared.channels;
+    let dst = &mut dst[..len];
+    // Load `finished` before popping, so frames pushed right before the end are not lost.
+    let finished = shared.finished.load(Ordering::Acquire);
+    let n = shared.ring.pop(dst);
+    dst[n..].iter_mut().for_each(|x| *x = 0.0);
+    if n < len && !finished {
+      shared.underruns.fetch_add(1, Ordering::Relaxed);
+      shared
+        .underrun_frames
+        .fetch_add(((len - n) / shared.channels) as u64, Ordering::Relaxed);
+    }
+    n / shared.channels
+  }
+
+  /// Returns `true` once the end of file is reached and all frames are read.
+  pub fn is_finished(&self) -> bool {
+    self.shared.finished.load(Ordering::Acquire) && self.shared.ring.len() == 0
+  }
+
+  /// Number of reads that could not be filled because the producer was too slow.
+  pub fn underruns(&self) -> u64 {
+    self.shared.underruns.load(Ordering::Relaxed)
+  }
+
+  /// Total number of frames replaced by silence because the producer was too slow.
+  pub fn underrun_frames(&self) -> u64 {
+    self.shared.underrun_frames.load(Ordering::Relaxed)
+  }
+}
+
+struct CaptureShared {
+  ring: Ring,
+  channels: usize,
+  stop: AtomicBool,
+  overflows: AtomicU64,
+  overflow_frames: AtomicU64,
+}
+
+/// A `SndFile` written on a worker thread from frames captured in a realtime audio callback.
+///
+/// Frames are queued in a lock-free ring buffer by `CaptureInput` and flushed to the file by the
+/// worker, so the audio callback never waits for disk I/O.
+///
+/// ```ignore
+/// let snd = OpenOptions::WriteOnly(options).from_path("take.wav")?;
+/// let (mut input, writer) = CaptureWriter::spawn(snd, 48000, Duration::from_millis(10))?;
+/// // In the audio callback:
+/// input.write(captured);
+/// // When the recording stops:
+/// let snd = writer.finish()?;
+/// ```
+pub struct CaptureWriter {
+  shared: Arc<CaptureShared>,
+  worker: Option<JoinHandle<Result<SndFile, SndFileError>>>,
+}
+
+/// Realtime half of a `CaptureWriter`.
+///
+/// Its methods do not allocate, lock or block.
+pub struct CaptureInput {
+  shared: Arc<CaptureShared>,
+}
+
+impl CaptureWriter {
+  /// Start a worker thread writing to `snd`, buffering at most `capacity_frames` frames. The worker
+  /// sleeps `flush_interval` whenever the buffer is empty.
+  pub fn spawn(
+    mut snd: SndFile,
+    capacity_frames: usize,
+    flush_interval: Duration,
+  ) -> Result<(CaptureInput, CaptureWriter), SndFileError> {
+    if capacity_frames == 0 {
+      return Err(SndFileError::InvalidParameter(
+        "Got 0 frames of capacity, expect a positive number.".to_string(),
+      ));
+    }
+    let channels = snd.get_channels();
+    let shared = Arc::new(CaptureShared {
+      ring: Ring::new(capacity_frames * channels),
+      channels,
+      stop: AtomicBool::new(false),
+      overflows: AtomicU64::new(0),
+      overflow_frames: AtomicU64::new(0),
+    });
+    let worker_shared = shared.clone();
+    let worker = std::thread::spawn(move || {
+      let shared = worker_shared;
+      let mut scratch = vec![0.0f32; shared.ring.capacity()];
+      loop {
+        // Load `stop` before popping, so frames pushed right before stopping are not lost.
+        let stop = shared.stop.load(Ordering::Acquire);
+        let n = shared.ring.pop(&mut scratch);
+        if n > 0 {
+          snd.write_from_slice(&scratch[..n])?;
+        } else if stop {
+          return Ok(snd);
+        } else {
+          std::thread::sleep(flush_interval);
+        }
+      }
+    });
+    Ok((
+      CaptureInput {
+        shared: shared.clone(),
+      },
+      CaptureWriter {
+        shared,
+        worker: Some(worker),
+      },
+    ))
+  }
+
+  /// Number of writes that could not be queued entirely because the buffer was full.
+  pub fn overflows(&self) -> u64 {
+    self.shared.overflows.load(Ordering::Relaxed)
+  }
+
+  /// Total number of frames dropped because the buffer was full.
+  pub fn overflow_frames(&self) -> u64 {
+    self.shared.overflow_frames.load(Ordering::Relaxed)
+  }
+
+  /// Flush all queued frames and stop the worker, returns the `SndFile` or the first write error.
+  ///
+  /// Frames written by `CaptureInput` afterwards are dropped.
+  pub fn finish(mut self) -> Result<SndFile, SndFileError> {
+    self.shared.stop.store(true, Ordering::Release);
+    match self.worker.take().unwrap().join() {
+      Ok(r) => r,
+      Err(_) => Err(SndFileError::InternalError(
+        "Capture worker panicked.".to_string(),
+      )),
+    }
+  }
+}
+
+impl Drop for CaptureWriter {
+  fn drop(&mut self) {
+    if let Some(worker) = self.worker.take() {
+      self.shared.stop.store(true, Ordering::Release);
+      let _ = worker.join();
+    }
+  }
+}
+
+impl CaptureInput {
+  /// Get channel count.
+  pub fn channels(&self) -> usize {
+    self.shared.channels
+  }
+
+  /// Queue interleaved frames, returns the number of frames queued.
+  ///
+  /// Frames that do not fit in the buffer are dropped and counted as an overflow. Trailing samples
+  /// of `src` that do not form a whole frame are ignored.
+  pub fn write(&mut self, src: &[f32]) -> usize {
+    let shared = &*self.shared;
+    let len = src.len() / shared.channels * shared.channels;
+    let n = if shared.stop.load(Ordering::Acquire) {
+      0
+    } else {
+      shared.ring.push(&src[..len])
+    };
+    if n < len {
+      shared.overflows.fetch_add(1, Ordering::Relaxed);
+      shared
+        .overflow_frames
+        .fetch_add(((len - n) / shared.channels) as u64, Ordering::Relaxed);
+    }
+    n / shared.channels
+  }
+}
@@ -0,0 +1,183 @@
+//! libsndfile types and constants used by this crate.
+//!
+//! They come from `sndfile-sys` with the default `system_linking` feature. Its build script links
+//! the system libsndfile, so without that feature, e.g. with `runtime_linking`, the subset used by
+//! this crate is declared here after `sndfile.h`.
+
+#![allow(non_camel_case_types, clippy::upper_case_acronyms)]
+
+use std::os::raw::{c_char, c_int, c_void};
+
+pub type sf_count_t = i64;
+
+/// Opaque handle of an open file.
+pub enum SNDFILE {}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SF_INFO {
+  pub frames: sf_count_t,
+  pub samplerate: c_int,
+  pub channels: c_int,
+  pub format: c_int,
+  pub sections: c_int,
+  pub seekable: c_int,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SF_FORMAT_INFO {
+  pub format: c_int,
+  pub name: *const c_char,
+  pub extension: *const c_char,
+}
+
+pub type sf_vio_get_filelen = extern "C" fn(user_data: *mut c_void) -> sf_count_t;
+pub type sf_vio_seek =
+  extern "C" fn(offset: sf_count_t, whence: c_int, user_data: *mut c_void) -> sf_count_t;
+pub type sf_vio_read =
+  extern "C" fn(ptr: *mut c_void, count: sf_count_t, user_data: *mut c_void) -> sf_count_t;
+pub type sf_vio_write =
+  extern "C" fn(ptr: *const c_void, count: sf_count_t, user_data: *mut c_void) -> sf_count_t;
+pub type sf_vio_tell = extern "C" fn(user_data: *mut c_void) -> sf_count_t;
+
+#[repr(C)]
+pub struct SF_VIRTUAL_IO {
+  pub get_filelen: sf_vio_get_filelen,
+  pub seek: sf_vio_seek,
+  pub read: sf_vio_read,
+  pub write: sf_vio_write,
+  pub tell: sf_vio_tell,
+}
+
+// Major formats.
+pub const SF_FORMAT_WAV: c_int = 0x010000;
+pub const SF_FORMAT_AIFF: c_int = 0x020000;
+pub const SF_FORMAT_AU: c_int = 0x030000;
+pub const SF_FORMAT_RAW: c_int = 0x040000;
+pub const SF_FORMAT_PAF: c_int = 0x050000;
+pub const SF_FORMAT_SVX: c_int = 0x060000;
+pub const SF_FORMAT_NIST: c_int = 0x070000;
+pub const SF_FORMAT_VOC: c_int = 0x080000;
+pub const SF_FORMAT_IRCAM: c_int = 0x0A0000;
+pub const SF_FORMAT_W64: c_int = 0x0B0000;
+pub const SF_FORMAT_MAT4: c_int = 0x0C0000;
+pub const SF_FORMAT_MAT5: c_int = 0x0D0000;
+pub const SF_FORMAT_PVF: c_int = 0x0E0000;
+pub const SF_FORMAT_XI: c_int = 0x0F0000;
+pub const SF_FORMAT_HTK: c_int = 0x100000;
+pub const SF_FORMAT_SDS: c_int = 0x110000;
+pub const SF_FORMAT_AVR: c_int = 0x120000;
+pub const SF_FORMAT_WAVEX: c_int = 0x130000;
+pub const SF_FORMAT_SD2: c_int = 0x160000;
+pub const SF_FORMAT_FLAC: c_int = 0x170000;
+pub const SF_FORMAT_CAF: c_int = 0x180000;
+pub const SF_FORMAT_WVE: c_int = 0x190000;
+pub const SF_FORMAT_OGG: c_int = 0x200000;
+pub const SF_FORMAT_MPC2K: c_int = 0x210000;
+pub const SF_FORMAT_RF64: c_int = 0x220000;
+
+// Subtypes.
+pub const SF_FORMAT_PCM_S8: c_int = 1;
+pub const SF_FORMAT_PCM_16: c_int = 2;
+pub const SF_FORMAT_PCM_24: c_int = 3;
+pub const SF_FORMAT_PCM_32: c_int = 4;
+pub const SF_FORMAT_PCM_U8: c_int = 5;
+pub const SF_FORMAT_FLOAT: c_int = 6;
+pub const SF_FORMAT_DOUBLE: c_int = 7;
+pub const SF_FORMAT_ULAW: c_int = 0x10;
+pub const SF_FORMAT_ALAW: c_int = 0x11;
+pub const SF_FORMAT_IMA_ADPCM: c_int = 0x12;
+pub const SF_FORMAT_MS_ADPCM: c_int = 0x13;
+pub const SF_FORMAT_GSM610: c_int = 0x20;
+pub const SF_FORMAT_VOX_ADPCM: c_int = 0x21;
+pub const SF_FORMAT_G721_32: c_int = 0x30;
+pub const SF_FORMAT_G723_24: c_int = 0x31;
+pub const SF_FORMAT_G723_40: c_int = 0x32;
+pub const SF_FORMAT_DWVW_12: c_int = 0x40;
+pub const SF_FORMAT_DWVW_16: c_int = 0x41;
+pub const SF_FORMAT_DWVW_24: c_int = 0x42;
+pub const SF_FORMAT_DWVW_N: c_int = 0x43;
+pub const SF_FORMAT_DPCM_8: c_int = 0x50;
+pub const SF_FORMAT_DPCM_16: c_int = 0x51;
+pub const SF_FORMAT_VORBIS: c_int = 0x60;
+pub const SF_FORMAT_ALAC_16: c_int = 0x70;
+pub const SF_FORMAT_ALAC_20: c_int = 0x71;
+pub const SF_FORMAT_ALAC_24: c_int = 0x72;
+pub const SF_FORMAT_ALAC_32: c_int = 0x73;
+
+// Endian.
+pub const SF_ENDIAN_FILE: c_int = 0;
+pub const SF_ENDIAN_LITTLE: c_int = 0x10000000;
+pub const SF_ENDIAN_BIG: c_int = 0x20000000;
+pub const SF_ENDIAN_CPU: c_int = 0x30000000;
+
+// Masks.
+pub const SF_FORMAT_SUBMASK: c_int = 0xFFFF;
+pub const SF_FORMAT_TYPEMASK: c_int = 0x0FFF0000;
+pub const SF_FORMAT_ENDMASK: c_int = 0x30000000;
+
+// Commands of `sf_command`.
+pub const SFC_GET_LOG_INFO: c_int = 0x1001;
+pub const SFC_GET_CURRENT_SF_INFO: c_int = 0x1002;
+pub const SFC_GET_NORM_DOUBLE: c_int = 0x1010;
+pub const SFC_GET_NORM_FLOAT: c_int = 0x1011;
+pub const SFC_SET_NORM_DOUBLE: c_int = 0x1012;
+pub const SFC_SET_NORM_FLOAT: c_int = 0x1013;
+pub const SFC_SET_SCALE_FLOAT_INT_READ: c_int = 0x1014;
+pub const SFC_SET_SCALE_INT_FLOAT_WRITE: c_int = 0x1015;
+pub const SFC_GET_FORMAT_MAJOR_COUNT: c_int = 0x1030;
+pub const SFC_GET_FORMAT_MAJOR: c_int = 0x1031;
+pub const SFC_GET_FORMAT_SUBTYPE_COUNT: c_int = 0x1032;
+pub const SFC_GET_FORMAT_SUBTYPE: c_int = 0x1033;
+pub const SFC_CALC_SIGNAL_MAX: c_int = 0x1040;
+pub const SFC_CALC_NORM_SIGNAL_MAX: c_int = 0x1041;
+pub const SFC_SET_ADD_PEAK_CHUNK: c_int = 0x1050;
+pub const SFC_UPDATE_HEADER_NOW: c_int = 0x1060;
+pub const SFC_SET_UPDATE_HEADER_AUTO: c_int = 0x1061;
+pub const SFC_FILE_TRUNCATE: c_int = 0x1080;
+pub const SFC_SET_RAW_START_OFFSET: c_int = 0x1090;
+pub const SFC_SET_CLIPPING: c_int = 0x10C0;
+pub const SFC_GET_CLIPPING: c_int = 0x10C1;
+pub const SFC_GET_CUE: c_int = 0x10CE;
+pub const SFC_SET_CUE: c_int = 0x10CF;
+pub const SFC_GET_INSTRUMENT: c_int = 0x10D0;
+pub const SFC_GET_BROADCAST_INFO: c_int = 0x10F0;
+pub const SFC_SET_BROADCAST_INFO: c_int = 0x10F1;
+pub const SFC_GET_CHANNEL_MAP_INFO: c_int = 0x1100;
+pub const SFC_SET_CHANNEL_MAP_INFO: c_int = 0x1101;
+pub const SFC_RAW_DATA_NEEDS_ENDSWAP: c_int = 0x1110;
+pub const SFC_RF64_AUTO_DOWNGRADE: c_int = 0x1210;
+pub const SFC_SET_VBR_ENCODING_QUALITY: c_int = 0x1300;
+pub const SFC_SET_COMPRESSION_LEVEL: c_int = 0x1301;
+
+// String types.
+pub const SF_STR_TITLE: c_int = 1;
+pub const SF_STR_COPYRIGHT: c_int = 2;
+pub const SF_STR_SOFTWARE: c_int = 3;
+pub const SF_STR_ARTIST: c_int = 4;
+pub const SF_STR_COMMENT: c_int = 5;
+pub const SF_STR_DATE: c_int = 6;
+pub const SF_STR_ALBUM: c_int = 7;
+pub const SF_STR_LICENSE: c_int = 8;
+pub const SF_STR_TRACKNUMBER: c_int = 9;
+pub const SF_STR_GENRE: c_int = 0x10;
+
+// Open modes and booleans.
+pub const SF_FALSE: c_int = 0;
+pub const SF_TRUE: c_int = 1;
+pub const SFM_READ: c_int = 0x10;
+pub const SFM_WRITE: c_int = 0x20;
+pub const SFM_RDWR: c_int = 0x30;
+
+// Error codes.
+pub const SF_ERR_NO_ERROR: c_int = 0;
+pub const SF_ERR_UNRECOGNISED_FORMAT: c_int = 1;
+pub const SF_ERR_SYSTEM: c_int = 2;
+pub const SF_ERR_MALFORMED_FILE: c_int = 3;
+pub const SF_ERR_UNSUPPORTED_ENCODING: c_int = 4;
+
+// Seek origins.
+pub const SF_SEEK_SET: c_int = 0;
+pub const SF_SEEK_CUR: c_int = 1;
+pub const SF_SEEK_END: c_int = 2;
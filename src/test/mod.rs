@@ -32,6 +32,41 @@ fn supported_format() {
     SubtypeFormat::PCM_24,
     Endian::File
   ));
+
+  assert!(check_format_verbose(
+    2,
+    44100,
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File
+  )
+  .is_ok());
+  let suggestions = check_format_verbose(
+    2,
+    44100,
+    MajorFormat::FLAC,
+    SubtypeFormat::FLOAT,
+    Endian::File,
+  )
+  .unwrap_err();
+  assert_eq!(suggestions[0].subtype_format, SubtypeFormat::PCM_16);
+  assert!(suggestions
+    .iter()
+    .all(|x| x.major_format == MajorFormat::FLAC && x.subtype_format != SubtypeFormat::FLOAT));
+}
+
+#[test]
+fn unknown_format_flags() {
+  let flags = 0x7f0000 | 0x7f | sndfile_sys::SF_ENDIAN_FILE;
+  let major_format = format::flags_to_major_format(flags);
+  let subtype_format = format::flags_to_subtype_format(flags);
+  assert_eq!(major_format, MajorFormat::Unknown(0x7f0000));
+  assert_eq!(subtype_format, SubtypeFormat::Unknown(0x7f));
+  assert_eq!(default_subtype(major_format), None);
+  assert_eq!(
+    format::assembly_format_flags(major_format, subtype_format, Endian::File),
+    flags
+  );
 }
 
 #[test]
@@ -140,3 +175,3589 @@ fn file_io_ok_1() {
   }
   std::fs::remove_file(&tmp_path).unwrap();
 }
+
+#[test]
+fn write_options_try_new() {
+  assert!(WriteOptions::try_new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    44100,
+    2
+  )
+  .is_ok());
+  assert!(
+    WriteOptions::try_new(MajorFormat::WAV, SubtypeFormat::PCM_16, Endian::File, 0, 2).is_err()
+  );
+  assert!(WriteOptions::try_new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    44100,
+    0
+  )
+  .is_err());
+}
+
+#[test]
+fn write_options_validate() {
+  assert!(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    44100,
+    2
+  )
+  .validate()
+  .is_ok());
+  match WriteOptions::new(
+    MajorFormat::FLAC,
+    SubtypeFormat::VORBIS,
+    Endian::File,
+    44100,
+    2,
+  )
+  .validate()
+  {
+    Err(SndFileError::InvalidParameter(msg)) => {
+      assert!(msg.contains("FLAC"));
+      assert!(msg.contains("VORBIS"));
+      assert!(msg.contains("Got subtype_format VORBIS"));
+      assert!(msg.contains("PCM_16"));
+    }
+    _ => panic!(),
+  }
+}
+
+#[test]
+fn buffer_len_mismatch() {
+  let tmp_dir = TempDir::new().unwrap();
+  let tmp_path = tmp_dir.as_ref().join("buffer_len_mismatch.wav");
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    8000,
+    2,
+  ))
+  .from_path(&tmp_path)
+  .unwrap();
+  match snd.write_from_slice(&[0.0f32; 3]) {
+    Err(SndFileError::InvalidParameter(_)) => {}
+    _ => panic!(),
+  }
+  match snd.read_to_slice(&mut [0i32; 5]) {
+    Err(SndFileError::InvalidParameter(_)) => {}
+    _ => panic!(),
+  }
+  assert_eq!(snd.write_from_slice(&[0i16; 4]).unwrap(), 2);
+}
+
+#[cfg(feature = "ndarray_features")]
+#[test]
+fn ndarray_width_mismatch() {
+  use ndarray::Array2;
+  let tmp_dir = TempDir::new().unwrap();
+  let tmp_path = tmp_dir.as_ref().join("ndarray_width_mismatch.wav");
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    8000,
+    2,
+  ))
+  .from_path(&tmp_path)
+  .unwrap();
+  match snd.write_from_ndarray(Array2::<f32>::zeros((4, 3)).view()) {
+    Err(SndFileError::InvalidParameter(_)) => {}
+    _ => panic!(),
+  }
+  match snd.read_to_ndarray(Array2::<i16>::zeros((4, 1)).view_mut()) {
+    Err(SndFileError::InvalidParameter(_)) => {}
+    _ => panic!(),
+  }
+  assert_eq!(
+    snd
+      .write_from_ndarray(Array2::<f64>::zeros((4, 2)).view())
+      .unwrap(),
+    4
+  );
+}
+
+#[test]
+fn raw_round_trip() {
+  let tmp_dir = TempDir::new().unwrap();
+  let tmp_path = tmp_dir.as_ref().join("raw_round_trip.wav");
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    8000,
+    2,
+  ))
+  .from_path(&tmp_path)
+  .unwrap();
+  let is_null = snd.with_raw(|ptr| ptr.is_null()).unwrap();
+  assert!(!is_null);
+  let snd = unsafe { SndFile::from_raw(snd.into_raw()) }.unwrap();
+  assert_eq!(snd.get_samplerate(), 8000);
+  assert_eq!(snd.get_channels(), 2);
+  assert_eq!(snd.get_major_format(), MajorFormat::WAV);
+}
+
+#[test]
+fn bulk_tags() {
+  let tmp_dir = TempDir::new().unwrap();
+  let tmp_path = tmp_dir.as_ref().join("bulk_tags.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      1,
+    ))
+    .from_path(&tmp_path)
+    .unwrap();
+    snd
+      .set_tags(vec![(TagType::Title, "title"), (TagType::Artist, "artist")])
+      .unwrap();
+    snd.write_from_slice(&[0i16; 16]).unwrap();
+  }
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&tmp_path)
+    .unwrap();
+  let tags = snd.get_tags();
+  assert_eq!(tags.len(), 2);
+  assert_eq!(tags[&TagType::Title], "title");
+  assert_eq!(tags[&TagType::Artist], "artist");
+}
+
+#[test]
+fn concat_files() {
+  let tmp_dir = TempDir::new().unwrap();
+  let write = |name: &str, channels: usize, data: &[i16]| {
+    let path = tmp_dir.as_ref().join(name);
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      channels,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(data).unwrap();
+    path
+  };
+  let a = write("a.wav", 2, &[1, 2, 3, 4]);
+  let b = write("b.wav", 2, &[5, 6]);
+  let c = write("c.wav", 1, &[7, 8]);
+  let out = tmp_dir.as_ref().join("out.wav");
+  let options = || {
+    WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    )
+  };
+
+  assert_eq!(concat(&[&a, &b], &out, options(), true).unwrap(), 3);
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&out)
+    .unwrap();
+  let buf: Vec<i16> = snd.read_all_to_vec().unwrap();
+  assert_eq!(buf, vec![1, 2, 3, 4, 5, 6]);
+
+  match concat(&[&a, &c], &out, options(), true) {
+    Err(SndFileError::InvalidParameter(_)) => {}
+    _ => panic!(),
+  }
+}
+
+#[test]
+fn split_file() {
+  use std::time::Duration;
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("in.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      10,
+      1,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.set_tag(TagType::Title, "split").unwrap();
+    snd
+      .write_from_slice(&(0..30).collect::<Vec<i16>>())
+      .unwrap();
+  }
+  let template = tmp_dir.as_ref().join("out_{}.wav");
+  let out = split(
+    &path,
+    &[
+      Duration::from_secs(0)..Duration::from_secs(1),
+      Duration::from_millis(2500)..Duration::from_secs(10),
+    ],
+    template.to_str().unwrap(),
+  )
+  .unwrap();
+  assert_eq!(out.len(), 2);
+  let expected: [Vec<i16>; 2] = [(0..10).collect(), (25..30).collect()];
+  for (p, e) in out.iter().zip(expected.iter()) {
+    let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+      .from_path(p)
+      .unwrap();
+    let buf: Vec<i16> = snd.read_all_to_vec().unwrap();
+    assert_eq!(&buf, e);
+    assert_eq!(snd.get_tag(TagType::Title).unwrap(), "split");
+  }
+}
+
+#[test]
+fn trim_silence() {
+  use std::time::Duration;
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("trim.wav");
+  let mut data = vec![0.0f32; 2 * 100];
+  for x in data[2 * 30..2 * 60].iter_mut() {
+    *x = 0.5;
+  }
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::FLOAT,
+    Endian::File,
+    1000,
+    2,
+  ))
+  .from_path(&path)
+  .unwrap();
+  snd.write_from_slice(&data).unwrap();
+
+  let options = TrimOptions::new(-40.0, Duration::from_millis(20));
+  let (buf, offset): (Vec<f32>, u64) = snd.read_all_to_vec_trimmed(&options).unwrap();
+  assert_eq!(offset, 30);
+  assert_eq!(buf, vec![0.5; 2 * 30]);
+
+  // Leading silence is shorter than `min_duration` and kept.
+  let options = TrimOptions::new(-40.0, Duration::from_millis(35));
+  let (buf, offset): (Vec<f32>, u64) = snd.read_all_to_vec_trimmed(&options).unwrap();
+  assert_eq!(offset, 0);
+  assert_eq!(buf.len(), 2 * 60);
+}
+
+#[test]
+fn measure_levels() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("levels.wav");
+  let samplerate = 48000;
+  let amp = 10.0f64.powf(-23.0 / 20.0);
+  let data: Vec<f64> = (0..samplerate * 10)
+    .flat_map(|i| {
+      let x = amp * (2.0 * std::f64::consts::PI * 1000.0 * i as f64 / samplerate as f64).sin();
+      vec![x, 0.5 * x]
+    })
+    .collect();
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::DOUBLE,
+    Endian::File,
+    samplerate,
+    2,
+  ))
+  .from_path(&path)
+  .unwrap();
+  snd.write_from_slice(&data).unwrap();
+
+  let levels = snd.measure_levels().unwrap();
+  assert!((levels.peak[0] - amp).abs() < 1e-6);
+  assert!((levels.peak[1] - 0.5 * amp).abs() < 1e-6);
+  assert!((levels.rms[0] - amp / 2.0f64.sqrt()).abs() < 1e-6);
+  assert!((levels.rms[1] - 0.5 * amp / 2.0f64.sqrt()).abs() < 1e-6);
+  #[cfg(feature = "loudness")]
+  {
+    let expected = -23.0 + 10.0 * 1.25f64.log10() - 3.01;
+    assert!((levels.integrated_lufs.unwrap() - expected).abs() < 0.1);
+  }
+}
+
+#[cfg(feature = "loudness")]
+#[test]
+fn measure_loudness_7_1() {
+  let tmp_dir = TempDir::new().unwrap();
+  let samplerate = 48000;
+  let amp = 10.0f64.powf(-23.0 / 20.0);
+  // A sine in channel `ch` of 7.1, i.e. L R C LFE BL BR SL SR.
+  let loudness = |ch: usize| {
+    let path = tmp_dir.as_ref().join(format!("levels_7_1_{}.wav", ch));
+    let data: Vec<f32> = (0..samplerate * 5)
+      .flat_map(|i| {
+        let x = amp * (2.0 * std::f64::consts::PI * 1000.0 * i as f64 / samplerate as f64).sin();
+        (0..8).map(move |c| if c == ch { x as f32 } else { 0.0 })
+      })
+      .collect();
+    let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::FLOAT,
+      Endian::File,
+      samplerate,
+      8,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+    snd.measure_levels().unwrap().integrated_lufs
+  };
+  let front = -23.0 - 3.01;
+  let surround = front + 10.0 * 1.41f64.log10();
+  assert!((loudness(0).unwrap() - front).abs() < 0.1);
+  assert_eq!(loudness(3), None);
+  for ch in 4..8 {
+    assert!((loudness(ch).unwrap() - surround).abs() < 0.1);
+  }
+}
+
+#[test]
+fn compute_peaks() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("peaks.wav");
+  let data: Vec<f32> = (0..8)
+    .flat_map(|i| vec![i as f32 / 8.0, -(i as f32) / 8.0])
+    .collect();
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::FLOAT,
+    Endian::File,
+    8000,
+    2,
+  ))
+  .from_path(&path)
+  .unwrap();
+  snd.write_from_slice(&data).unwrap();
+
+  let peaks = snd.compute_peaks(4).unwrap();
+  assert_eq!(
+    peaks[0],
+    vec![(0.0, 0.125), (0.25, 0.375), (0.5, 0.625), (0.75, 0.875)]
+  );
+  assert_eq!(
+    peaks[1],
+    vec![
+      (-0.125, 0.0),
+      (-0.375, -0.25),
+      (-0.625, -0.5),
+      (-0.875, -0.75)
+    ]
+  );
+
+  let peaks = snd.compute_peaks(16).unwrap();
+  assert_eq!(peaks[0].len(), 16);
+  assert_eq!(peaks[0][1], (0.0, 0.0));
+}
+
+#[test]
+fn hash_pcm() {
+  use std::collections::hash_map::DefaultHasher;
+  let tmp_dir = TempDir::new().unwrap();
+  let data: Vec<i16> = (0..1000).map(|x| (x * 31) as i16).collect();
+  let mut hashes = Vec::new();
+  for (name, major_format, data) in [
+    ("a.wav", MajorFormat::WAV, &data[..]),
+    ("b.flac", MajorFormat::FLAC, &data[..]),
+    ("c.wav", MajorFormat::WAV, &data[..998]),
+  ]
+  .iter()
+  {
+    let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+      *major_format,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(tmp_dir.as_ref().join(name))
+    .unwrap();
+    snd.write_from_slice(data).unwrap();
+    hashes.push(snd.hash_pcm::<DefaultHasher>().unwrap());
+  }
+  assert_eq!(hashes[0], hashes[1]);
+  assert_ne!(hashes[0], hashes[2]);
+}
+
+#[test]
+fn compare_files() {
+  let tmp_dir = TempDir::new().unwrap();
+  let data: Vec<i16> = (0..1000).map(|x| (x * 31) as i16).collect();
+  let mut changed = data.clone();
+  changed[501] += 2;
+  for (name, title, data) in [
+    ("a.wav", "a", &data[..]),
+    ("b.wav", "a", &data[..]),
+    ("c.wav", "c", &changed[..]),
+    ("d.wav", "a", &data[..998]),
+  ]
+  .iter()
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(tmp_dir.as_ref().join(name))
+    .unwrap();
+    snd.set_tag(TagType::Title, title).unwrap();
+    snd.write_from_slice(data).unwrap();
+  }
+  let path = |name: &str| tmp_dir.as_ref().join(name);
+
+  let r = compare(path("a.wav"), path("b.wav")).unwrap();
+  assert!(r.is_identical());
+  assert_eq!(r.max_sample_delta, 0.0);
+
+  let r = compare(path("a.wav"), path("c.wav")).unwrap();
+  assert!(!r.is_identical());
+  assert_eq!(r.first_differing_frame, Some(250));
+  assert_eq!(r.max_sample_delta, 2.0 / 32768.0);
+  assert_eq!(
+    r.metadata_differences,
+    vec![MetadataDifference::Tag(
+      TagType::Title,
+      Some("a".to_string()),
+      Some("c".to_string())
+    )]
+  );
+
+  let r = compare(path("a.wav"), path("d.wav")).unwrap();
+  assert_eq!(r.first_differing_frame, Some(499));
+  assert_eq!(
+    r.metadata_differences,
+    vec![MetadataDifference::Length(500, 499)]
+  );
+}
+
+#[test]
+fn copy_with_metadata_wav() {
+  let tmp_dir = TempDir::new().unwrap();
+  let src = tmp_dir.as_ref().join("src.wav");
+  let dst = tmp_dir.as_ref().join("dst.wav");
+  let info = BroadcastInfo {
+    description: "take 1".to_string(),
+    originator: "sndfile.rs".to_string(),
+    origination_date: "2020-01-02".to_string(),
+    origination_time: "03:04:05".to_string(),
+    time_reference: 48000 * 3600,
+    version: 1,
+    ..Default::default()
+  };
+  let cues = vec![CuePoint {
+    id: 1,
+    position: 10,
+    fcc_chunk: 0x6174_6164,
+    sample_offset: 10,
+    name: "marker".to_string(),
+    ..Default::default()
+  }];
+  let chunk = Chunk {
+    id: "iXML".to_string(),
+    data: b"<BWFXML></BWFXML>\0\0\0".to_vec(),
+  };
+  let data: Vec<i16> = (0..100).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      48000,
+      1,
+    ))
+    .from_path(&src)
+    .unwrap();
+    snd.set_tag(TagType::Title, "copy").unwrap();
+    snd.set_broadcast_info(&info).unwrap();
+    snd.set_cues(&cues).unwrap();
+    snd.add_chunk(&chunk).unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+
+  assert_eq!(copy_with_metadata(&src, &dst).unwrap(), 100);
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&dst)
+    .unwrap();
+  assert_eq!(snd.get_tag(TagType::Title).unwrap(), "copy");
+  let copied_info = snd.get_broadcast_info().unwrap();
+  assert_eq!(copied_info.description, info.description);
+  assert_eq!(copied_info.time_reference, info.time_reference);
+  assert_eq!(snd.get_cues()[0].position, 10);
+  assert_eq!(snd.get_cues()[0].name, "marker");
+  assert_eq!(snd.get_chunks(), vec![chunk]);
+  let buf: Vec<i16> = snd.read_all_to_vec().unwrap();
+  assert_eq!(buf, data);
+
+  // Float samples out of range are copied as is.
+  let src = tmp_dir.as_ref().join("src_float.wav");
+  let dst = tmp_dir.as_ref().join("dst_float.wav");
+  let data: Vec<f32> = vec![1.5, -3.0, f32::MIN_POSITIVE, 0.25];
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::FLOAT,
+      Endian::File,
+      48000,
+      1,
+    ))
+    .from_path(&src)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  assert_eq!(copy_with_metadata(&src, &dst).unwrap(), 4);
+  let raw = |path: &std::path::Path| {
+    let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+      .from_path(path)
+      .unwrap();
+    let mut buf = vec![0u8; 32];
+    let n = snd.read_raw(&mut buf).unwrap();
+    buf.truncate(n);
+    buf
+  };
+  assert_eq!(raw(&dst), raw(&src));
+  assert_eq!(raw(&dst).len(), 16);
+}
+
+#[test]
+fn transcode_preserve_chunks() {
+  let tmp_dir = TempDir::new().unwrap();
+  let src = tmp_dir.as_ref().join("src.wav");
+  let chunk = Chunk {
+    id: "iXML".to_string(),
+    data: b"<BWFXML></BWFXML>\0\0\0".to_vec(),
+  };
+  let data: Vec<i16> = (0..100).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      48000,
+      1,
+    ))
+    .from_path(&src)
+    .unwrap();
+    snd.add_chunk(&chunk).unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+
+  for (name, major_format, preserve_chunks, expected) in [
+    ("a.aiff", MajorFormat::AIFF, true, vec![chunk.clone()]),
+    ("b.aiff", MajorFormat::AIFF, false, vec![]),
+    ("c.flac", MajorFormat::FLAC, true, vec![]),
+  ]
+  .iter()
+  {
+    let dst = tmp_dir.as_ref().join(name);
+    let options = WriteOptions::new(*major_format, SubtypeFormat::PCM_16, Endian::File, 48000, 1);
+    assert_eq!(
+      transcode(&src, &dst, options, *preserve_chunks).unwrap(),
+      100
+    );
+    let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+      .from_path(&dst)
+      .unwrap();
+    assert_eq!(&snd.get_chunks(), expected);
+    let buf: Vec<i16> = snd.read_all_to_vec().unwrap();
+    assert_eq!(buf, data);
+  }
+}
+
+#[test]
+fn vorbis_comments() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("comments.ogg");
+  let data: Vec<f32> = (0..48000).map(|i| (i as f32 * 0.01).sin() * 0.5).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::OGG,
+      SubtypeFormat::VORBIS,
+      Endian::File,
+      48000,
+      1,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.set_tag(TagType::Title, "old").unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  {
+    let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+      .from_path(&path)
+      .unwrap();
+    assert_eq!(snd.get_vorbis_comment("title").unwrap().unwrap(), "old");
+  }
+
+  let long_value = "x".repeat(70000);
+  write_vorbis_comments(
+    &path,
+    &[
+      ("TITLE", "new"),
+      ("REPLAYGAIN_TRACK_GAIN", "-3.00 dB"),
+      ("LYRICS", &long_value),
+    ],
+  )
+  .unwrap();
+  assert!(write_vorbis_comments(&path, &[("BAD=KEY", "")]).is_err());
+
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  assert_eq!(snd.get_tag(TagType::Title).unwrap(), "new");
+  assert_eq!(
+    snd
+      .get_vorbis_comment("replaygain_track_gain")
+      .unwrap()
+      .unwrap(),
+    "-3.00 dB"
+  );
+  assert_eq!(
+    snd.get_vorbis_comment("LYRICS").unwrap().unwrap(),
+    long_value
+  );
+  assert_eq!(snd.get_vorbis_comments().unwrap().len(), 3);
+  assert_eq!(snd.len().unwrap(), 48000);
+}
+
+#[cfg(feature = "replaygain")]
+#[test]
+fn replaygain() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("rg.ogg");
+  let amp = 10.0f64.powf(-23.0 / 20.0);
+  let data: Vec<f32> = (0..48000 * 5)
+    .flat_map(|i| {
+      let x = (amp * (2.0 * std::f64::consts::PI * 1000.0 * i as f64 / 48000.0).sin()) as f32;
+      vec![x, x]
+    })
+    .collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::OGG,
+      SubtypeFormat::VORBIS,
+      Endian::File,
+      48000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.set_tag(TagType::Title, "rg").unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  write_vorbis_comments(
+    &path,
+    &[("TITLE", "rg"), ("replaygain_track_gain", "+9.99 dB")],
+  )
+  .unwrap();
+
+  let gain = apply_replaygain(&path).unwrap();
+  assert!((gain.loudness_lufs + 23.0).abs() < 0.5);
+  assert!((gain.gain_db - 5.0).abs() < 0.5);
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let comments = snd.get_vorbis_comments().unwrap();
+  assert_eq!(comments.len(), 3);
+  assert_eq!(snd.get_tag(TagType::Title).unwrap(), "rg");
+  assert_eq!(
+    snd
+      .get_vorbis_comment("REPLAYGAIN_TRACK_GAIN")
+      .unwrap()
+      .unwrap(),
+    format!("{:.2} dB", gain.gain_db)
+  );
+
+  let wav_path = tmp_dir.as_ref().join("rg.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::FLOAT,
+      Endian::File,
+      48000,
+      2,
+    ))
+    .from_path(&wav_path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let before = std::fs::read(&wav_path).unwrap();
+  assert!(matches!(
+    apply_replaygain(&wav_path),
+    Err(SndFileError::InvalidParameter(_))
+  ));
+  assert_eq!(std::fs::read(&wav_path).unwrap(), before);
+}
+
+#[test]
+fn smpte_timecode() {
+  let mut info = BroadcastInfo {
+    time_reference: 48000 * 3600,
+    ..Default::default()
+  };
+  assert_eq!(
+    info.timecode(48000, FrameRate::FPS_25).unwrap(),
+    "01:00:00:00"
+  );
+  assert_eq!(
+    info.time_reference_as_duration(48000).unwrap(),
+    std::time::Duration::from_secs(3600)
+  );
+  assert!(info.time_reference_as_duration(0).is_err());
+  assert!(info.timecode(0, FrameRate::FPS_25).is_err());
+
+  // 1800 frames at 29.97 fps, labels 00:01:00;00 and 00:01:00;01 are dropped.
+  let rate = FrameRate::FPS_29_97_DF;
+  assert_eq!(
+    rate.samples_to_timecode(2882880, 48000).unwrap(),
+    "00:01:00;02"
+  );
+  assert_eq!(
+    rate.samples_to_timecode(2882879, 48000).unwrap(),
+    "00:00:59;29"
+  );
+  assert_eq!(
+    rate.timecode_to_samples("00:01:00;02", 48000).unwrap(),
+    2882880
+  );
+  assert!(rate.timecode_to_samples("00:01:00;00", 48000).is_err());
+  // No labels are dropped every tenth minute.
+  let samples = rate.timecode_to_samples("00:10:00;00", 48000).unwrap();
+  assert_eq!(
+    samples,
+    (17982.0f64 * 48000.0 * 1001.0 / 30000.0).ceil() as u64
+  );
+  assert_eq!(
+    rate.samples_to_timecode(samples, 48000).unwrap(),
+    "00:10:00;00"
+  );
+
+  for tc in ["00:00:00:00", "09:59:59:29", "23:12:34:05"].iter() {
+    info
+      .set_timecode(tc, 44100, FrameRate::FPS_29_97_NDF)
+      .unwrap();
+    assert_eq!(&info.timecode(44100, FrameRate::FPS_29_97_NDF).unwrap(), tc);
+  }
+  assert!(FrameRate::new(25, false, true).is_err());
+}
+
+#[test]
+fn wav_markers() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("markers.wav");
+  let copied = tmp_dir.as_ref().join("copied.wav");
+  let markers = vec![
+    Marker {
+      position: 10,
+      label: "intro".to_string(),
+      comment: "count in".to_string(),
+    },
+    Marker {
+      position: 50,
+      label: "verse".to_string(),
+      comment: String::new(),
+    },
+    Marker {
+      position: 90,
+      ..Default::default()
+    },
+  ];
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      1,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.set_tag(TagType::Title, "markers").unwrap();
+    snd.set_markers(&markers).unwrap();
+    snd.write_from_slice(&[0i16; 100]).unwrap();
+  }
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  assert_eq!(snd.markers(), markers);
+  assert_eq!(snd.get_tag(TagType::Title).unwrap(), "markers");
+
+  copy_with_metadata(&path, &copied).unwrap();
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&copied)
+    .unwrap();
+  assert_eq!(snd.markers(), markers);
+}
+
+#[test]
+fn raw_bytes_io() {
+  let tmp_dir = TempDir::new().unwrap();
+  let src_path = tmp_dir.as_ref().join("src.wav");
+  let dst_path = tmp_dir.as_ref().join("dst.wav");
+  let options = || {
+    WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::Little,
+      8000,
+      2,
+    )
+  };
+  let data: Vec<i16> = (0..200).map(|x| x * 100 - 10000).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(options())
+      .from_path(&src_path)
+      .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+
+  let mut src = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&src_path)
+    .unwrap();
+  let mut dst = OpenOptions::WriteRead(options())
+    .from_path(&dst_path)
+    .unwrap();
+  let mut buf = vec![0u8; 400];
+  assert_eq!(src.read_raw(&mut buf).unwrap(), 400);
+  assert_eq!(&buf[..4], &data[0].to_le_bytes()[..]);
+  assert!(src.read_raw(&mut buf[..3]).is_err());
+  assert_eq!(dst.write_raw(&buf).unwrap(), 400);
+  let copied: Vec<i16> = dst.read_all_to_vec().unwrap();
+  assert_eq!(copied, data);
+}
+
+#[test]
+fn item_io() {
+  let tmp_dir = TempDir::new().unwrap();
+  let tmp_path = tmp_dir.as_ref().join("items.wav");
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    8000,
+    2,
+  ))
+  .from_path(&tmp_path)
+  .unwrap();
+  let data: Vec<i16> = (0..10).collect();
+  assert_eq!(snd.write_items_from_slice(&data[..3]).unwrap(), 3);
+  assert_eq!(snd.write_items_from_slice(&data[3..]).unwrap(), 7);
+  snd.seek(SeekFrom::Start(0)).unwrap();
+  let mut buf = vec![0i16; 7];
+  assert_eq!(snd.read_items_to_slice(&mut buf).unwrap(), 7);
+  assert_eq!(buf, &data[..7]);
+  let mut buf = vec![0i16; 5];
+  assert_eq!(snd.read_items_to_slice(&mut buf).unwrap(), 3);
+  assert_eq!(&buf[..3], &data[7..]);
+}
+
+#[test]
+fn progress_callbacks() {
+  let tmp_dir = TempDir::new().unwrap();
+  let src = tmp_dir.as_ref().join("src.wav");
+  let dst = tmp_dir.as_ref().join("dst.flac");
+  let data: Vec<i16> = (0..20000).map(|x| x as i16).collect();
+  let mut calls = Vec::new();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&src)
+    .unwrap();
+    let n = snd
+      .write_all_with_progress(&data, |done, total| calls.push((done, total)))
+      .unwrap();
+    assert_eq!(n, 10000);
+  }
+  assert_eq!(calls.first(), Some(&(0, 10000)));
+  assert_eq!(calls.last(), Some(&(10000, 10000)));
+
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&src)
+    .unwrap();
+  calls.clear();
+  let buf: Vec<i16> = snd
+    .read_all_to_vec_with_progress(|done, total| calls.push((done, total)))
+    .unwrap();
+  assert_eq!(buf, data);
+  assert!(calls.windows(2).all(|w| w[0].0 <= w[1].0));
+  assert_eq!(calls.last(), Some(&(10000, 10000)));
+
+  calls.clear();
+  let options = WriteOptions::new(
+    MajorFormat::FLAC,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    8000,
+    2,
+  );
+  transcode_with_progress(&src, &dst, options, false, |done, total| {
+    calls.push((done, total))
+  })
+  .unwrap();
+  assert_eq!(calls.last(), Some(&(10000, 10000)));
+}
+
+#[test]
+fn cancel_operation() {
+  use std::ops::ControlFlow;
+  use std::sync::atomic::{AtomicBool, Ordering};
+  let tmp_dir = TempDir::new().unwrap();
+  let src = tmp_dir.as_ref().join("src.wav");
+  let dst = tmp_dir.as_ref().join("dst.flac");
+  let data: Vec<i16> = (0..20000).map(|x| x as i16).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&src)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&src)
+    .unwrap();
+  let r = snd.read_all_to_vec_with_progress::<i16, _, _>(|done, _| {
+    if done > 0 {
+      ControlFlow::Break(())
+    } else {
+      ControlFlow::Continue(())
+    }
+  });
+  assert!(matches!(r, Err(SndFileError::Cancelled)));
+
+  let cancelled = AtomicBool::new(true);
+  let options = WriteOptions::new(
+    MajorFormat::FLAC,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    8000,
+    2,
+  );
+  let r = transcode_with_progress(&src, &dst, options, false, |_, _| {
+    if cancelled.load(Ordering::Relaxed) {
+      ControlFlow::Break(())
+    } else {
+      ControlFlow::Continue(())
+    }
+  });
+  assert!(matches!(r, Err(SndFileError::Cancelled)));
+  assert!(!dst.exists());
+}
+
+#[test]
+fn read_all_limited() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("limited.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&[0i16; 2000]).unwrap();
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  assert_eq!(
+    snd.read_all_to_vec_limited::<i16>(4000).unwrap().len(),
+    2000
+  );
+  match snd.read_all_to_vec_limited::<f32>(4000) {
+    Err(SndFileError::MemoryLimitExceeded { required, limit }) => {
+      assert_eq!(required, 8000);
+      assert_eq!(limit, 4000);
+    }
+    _ => panic!(),
+  }
+
+  // Not changing the crate-wide limit here, it would affect tests running in parallel.
+  assert_eq!(default_read_limit(), None);
+}
+
+#[test]
+fn probe_file() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("probe.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_24,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.set_tag(TagType::Title, "probe").unwrap();
+    snd.write_from_slice(&[0i32; 12000]).unwrap();
+  }
+  let info = probe(&path).unwrap();
+  assert_eq!(info.samplerate, 8000);
+  assert_eq!(info.channels, 2);
+  assert_eq!(info.frames, Some(6000));
+  assert_eq!(info.duration, Some(std::time::Duration::from_millis(750)));
+  assert_eq!(info.major_format, MajorFormat::WAV);
+  assert_eq!(info.subtype_format, SubtypeFormat::PCM_24);
+  assert_eq!(
+    info.tags.get(&TagType::Title).map(|x| x.as_str()),
+    Some("probe")
+  );
+  assert!(info.seekable);
+  assert_eq!(info.sections, 1);
+  assert!(probe(tmp_dir.as_ref().join("missing.wav")).is_err());
+}
+
+#[test]
+fn detect_container_format() {
+  let tmp_dir = TempDir::new().unwrap();
+  for &(major, subtype) in [
+    (MajorFormat::WAV, SubtypeFormat::PCM_16),
+    (MajorFormat::AIFF, SubtypeFormat::PCM_16),
+    (MajorFormat::FLAC, SubtypeFormat::PCM_16),
+    (MajorFormat::OGG, SubtypeFormat::VORBIS),
+    (MajorFormat::CAF, SubtypeFormat::PCM_16),
+    (MajorFormat::AU, SubtypeFormat::PCM_16),
+  ]
+  .iter()
+  {
+    let path = tmp_dir.as_ref().join("detect");
+    {
+      let mut snd =
+        OpenOptions::WriteOnly(WriteOptions::new(major, subtype, Endian::File, 8000, 1))
+          .from_path(&path)
+          .unwrap();
+      snd.write_from_slice(&[0i16; 1000]).unwrap();
+    }
+    let header = std::fs::read(&path).unwrap();
+    assert_eq!(detect_format(&header[..64]), Some(major));
+  }
+  assert_eq!(detect_format(b"<html></html>"), None);
+  assert_eq!(detect_format(b""), None);
+
+  let mut id3 = b"ID3\x04\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00".to_vec();
+  id3.extend_from_slice(b"fLaC");
+  assert_eq!(detect_format(&id3), Some(MajorFormat::FLAC));
+
+  assert_eq!(MajorFormat::FLAC.mime_type(), Some("audio/flac"));
+  assert_eq!(
+    MajorFormat::from_mime_type("Audio/X-WAV"),
+    Some(MajorFormat::WAV)
+  );
+  assert_eq!(
+    MajorFormat::from_mime_type("audio/ogg; codecs=opus"),
+    Some(MajorFormat::OGG)
+  );
+  assert_eq!(MajorFormat::from_mime_type("text/html"), None);
+}
+
+#[test]
+fn major_format_extensions() {
+  assert_eq!(MajorFormat::FLAC.canonical_extension(), Some("flac"));
+  assert_eq!(MajorFormat::WAV.extensions()[0], "wav");
+  assert!(MajorFormat::AIFF.extensions().contains(&"aif"));
+  assert_eq!(MajorFormat::from_extension("flac"), Some(MajorFormat::FLAC));
+  assert_eq!(MajorFormat::from_extension(".WAV"), Some(MajorFormat::WAV));
+  assert_eq!(MajorFormat::from_extension("aif"), Some(MajorFormat::AIFF));
+  assert_eq!(MajorFormat::from_extension("txt"), None);
+  for (major, info) in get_supported_major_format_dict().iter() {
+    assert!(major.extensions().contains(&info.extension.as_str()));
+    assert!(MajorFormat::from_extension(&info.extension).is_some());
+  }
+}
+
+#[test]
+fn format_display() {
+  assert_eq!(MajorFormat::WAV.to_string(), "WAV (Microsoft)");
+  assert_eq!(SubtypeFormat::PCM_24.to_string(), "Signed 24 bit PCM");
+  assert_eq!(Endian::Little.to_string(), "Little endian");
+  assert_eq!(
+    MajorFormat::Unknown(0x7f0000).to_string(),
+    "Unknown container (0x007F0000)"
+  );
+}
+
+#[test]
+fn parse_format() {
+  assert_eq!("wav".parse::<MajorFormat>().unwrap(), MajorFormat::WAV);
+  assert_eq!("WAVEX".parse::<MajorFormat>().unwrap(), MajorFormat::WAVEX);
+  assert_eq!("aif".parse::<MajorFormat>().unwrap(), MajorFormat::AIFF);
+  assert_eq!(
+    "Ogg/Vorbis".parse::<MajorFormat>().unwrap(),
+    MajorFormat::OGG
+  );
+  assert!("mkv".parse::<MajorFormat>().is_err());
+
+  assert_eq!(
+    "PCM_16".parse::<SubtypeFormat>().unwrap(),
+    SubtypeFormat::PCM_16
+  );
+  assert_eq!(
+    "pcm24".parse::<SubtypeFormat>().unwrap(),
+    SubtypeFormat::PCM_24
+  );
+  assert_eq!(
+    "f32".parse::<SubtypeFormat>().unwrap(),
+    SubtypeFormat::FLOAT
+  );
+  assert_eq!(
+    "ogg/vorbis".parse::<SubtypeFormat>().unwrap(),
+    SubtypeFormat::VORBIS
+  );
+  assert!("pcm12".parse::<SubtypeFormat>().is_err());
+}
+
+#[test]
+fn subtype_classification() {
+  assert_eq!(SubtypeFormat::PCM_24.bit_depth(), Some(24));
+  assert_eq!(SubtypeFormat::ALAC_20.bit_depth(), Some(20));
+  assert_eq!(SubtypeFormat::VORBIS.bit_depth(), None);
+  assert!(SubtypeFormat::DOUBLE.is_float());
+  assert!(!SubtypeFormat::PCM_32.is_float());
+  assert!(SubtypeFormat::MS_ADPCM.is_adpcm());
+  assert!(SubtypeFormat::IMA_ADPCM.is_lossy());
+  assert!(SubtypeFormat::VORBIS.is_lossy());
+  assert!(!SubtypeFormat::ALAC_16.is_lossy());
+  assert!(!SubtypeFormat::FLOAT.is_lossy());
+}
+
+#[test]
+fn write_options_for_path() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("out.flac");
+  {
+    let options = WriteOptions::for_path(&path, 8000, 2).unwrap();
+    let mut snd = OpenOptions::WriteOnly(options).from_path(&path).unwrap();
+    snd.write_from_slice(&[0i16; 100]).unwrap();
+  }
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  assert_eq!(snd.get_major_format(), MajorFormat::FLAC);
+  assert_eq!(snd.get_subtype_format(), SubtypeFormat::PCM_16);
+
+  assert!(WriteOptions::for_path("out.txt", 8000, 2).is_err());
+  assert!(WriteOptions::for_path("out", 8000, 2).is_err());
+  assert!(WriteOptions::for_path("out.raw", 8000, 2).is_err());
+  assert!(WriteOptions::for_path("out.wav", 0, 2).is_err());
+}
+
+#[test]
+fn file_info() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("info.wav");
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::FLOAT,
+    Endian::Little,
+    16000,
+    1,
+  ))
+  .from_path(&path)
+  .unwrap();
+  snd.write_from_slice(&[0.0f32; 8000]).unwrap();
+  let info = snd.info();
+  assert_eq!(info.frames, Some(8000));
+  assert_eq!(info.duration, Some(std::time::Duration::from_millis(500)));
+  assert_eq!(info.subtype_format, SubtypeFormat::FLOAT);
+  assert_eq!(info.endian, Endian::Little);
+  assert_eq!(snd.seek(SeekFrom::Current(0)).unwrap(), 8000);
+}
+
+#[cfg(feature = "serde_features")]
+#[test]
+fn info_to_json() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("json.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      1,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.set_tag(TagType::Artist, "someone \"quoted\"").unwrap();
+    snd.write_from_slice(&[0i16; 4000]).unwrap();
+  }
+  let info = probe(&path).unwrap();
+  assert!(info.chunks.iter().any(|x| x.id == "fmt "));
+  let v: serde_json::Value = serde_json::from_str(&info.to_json()).unwrap();
+  assert_eq!(v["major_format"], "WAV");
+  assert_eq!(v["subtype_format"], "PCM_16");
+  assert_eq!(v["samplerate"], 8000);
+  assert_eq!(v["frames"], 4000);
+  assert_eq!(v["duration"], 0.5);
+  assert_eq!(v["tags"]["artist"], "someone \"quoted\"");
+  assert!(v["chunks"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .any(|x| x["id"] == "data"));
+}
+
+#[test]
+fn unicode_path() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("音声ファイル.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      1,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&[1i16, 2, 3]).unwrap();
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let buf: Vec<i16> = snd.read_all_to_vec().unwrap();
+  assert_eq!(buf, vec![1, 2, 3]);
+}
+
+#[test]
+fn typed_open_modes() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("modes.wav");
+  let options = || {
+    WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    )
+  };
+  {
+    let mut w = SndWriter::create(&path, options()).unwrap();
+    w.set_tag(TagType::Title, "modes").unwrap();
+    assert_eq!(w.write_from_slice(&[1i16, 2, 3, 4]).unwrap(), 2);
+  }
+  {
+    let mut rw = SndReadWriter::open(&path, ReadOptions::Auto).unwrap();
+    rw.seek(SeekFrom::End(0)).unwrap();
+    rw.write_from_slice(&[5i16, 6]).unwrap();
+    assert_eq!(rw.len().unwrap(), 3);
+    assert_eq!(rw.stats().frames, 1);
+    let summary = rw.finalize().unwrap();
+    assert_eq!(summary.frames, 1);
+    assert_eq!(summary.bytes, std::fs::metadata(&path).unwrap().len());
+  }
+  let mut r = SndReader::open(&path, ReadOptions::Auto).unwrap();
+  assert_eq!(r.get_ref().get_channels(), 2);
+  assert_eq!(
+    r.get_ref().get_tag(TagType::Title).as_deref(),
+    Some("modes")
+  );
+  let buf: Vec<i16> = r.read_all_to_vec().unwrap();
+  assert_eq!(buf, vec![1, 2, 3, 4, 5, 6]);
+  assert!(SndReader::open(tmp_dir.as_ref().join("missing.wav"), ReadOptions::Auto).is_err());
+}
+
+#[test]
+fn typed_channels() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("typed.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::FLOAT,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap()
+    .with_channels::<f32, 2>()
+    .unwrap();
+    assert_eq!(snd.write_frames(&[[0.5, -0.5], [0.25, -0.25]]).unwrap(), 2);
+  }
+  let open = || {
+    OpenOptions::ReadOnly(ReadOptions::Auto)
+      .from_path(&path)
+      .unwrap()
+  };
+  assert!(matches!(
+    open().with_channels::<f32, 1>(),
+    Err(SndFileError::InvalidParameter(_))
+  ));
+  let mut snd = open().with_channels::<_, 2>().unwrap();
+  let frames: Vec<[f32; 2]> = snd.read_all_frames().unwrap();
+  assert_eq!(frames, vec![[0.5, -0.5], [0.25, -0.25]]);
+  snd.seek(SeekFrom::Start(1)).unwrap();
+  let mut buf = [[0.0f32; 2]; 4];
+  assert_eq!(snd.read_frames(&mut buf).unwrap(), 1);
+  assert_eq!(buf[0], [0.25, -0.25]);
+}
+
+#[test]
+fn dyn_audio_io() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("dyn.wav");
+  {
+    let mut sink: Box<dyn AudioWrite> = Box::new(
+      SndWriter::create(
+        &path,
+        WriteOptions::new(
+          MajorFormat::WAV,
+          SubtypeFormat::FLOAT,
+          Endian::File,
+          8000,
+          2,
+        ),
+      )
+      .unwrap(),
+    );
+    assert_eq!(sink.channels(), 2);
+    assert_eq!(sink.write_f32(&[0.5, -0.5, 0.25, -0.25]).unwrap(), 2);
+  }
+  let sources: Vec<Box<dyn AudioRead>> = vec![
+    Box::new(SndReader::open(&path, ReadOptions::Auto).unwrap()),
+    Box::new(
+      OpenOptions::ReadOnly(ReadOptions::Auto)
+        .from_path(&path)
+        .unwrap(),
+    ),
+  ];
+  for mut src in sources {
+    assert_eq!(src.samplerate(), 8000);
+    src.seek(SeekFrom::Start(1)).unwrap();
+    let mut buf = [0.0f32; 4];
+    assert_eq!(src.read_f32(&mut buf).unwrap(), 1);
+    assert_eq!(&buf[..2], &[0.25, -0.25]);
+  }
+}
+
+#[test]
+fn positional_io() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("positional.wav");
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    8000,
+    1,
+  ))
+  .from_path(&path)
+  .unwrap();
+  snd.write_from_slice(&[0i16; 10]).unwrap();
+  assert_eq!(snd.write_at(4, &[7i16, 8]).unwrap(), 2);
+  let mut buf = [0i16; 4];
+  assert_eq!(snd.read_at(3, &mut buf).unwrap(), 4);
+  assert_eq!(buf, [0, 7, 8, 0]);
+  assert_eq!(snd.read_at(8, &mut buf).unwrap(), 2);
+}
+
+#[test]
+fn reader_pool() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("pool.wav");
+  let data: Vec<i16> = (0..8000).map(|x| x as i16).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      1,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  assert!(SndFilePool::open(&path, 0).is_err());
+  let pool = SndFilePool::open(&path, 2).unwrap();
+  {
+    let _a = pool.checkout();
+    let _b = pool.checkout();
+    assert!(pool.try_checkout().is_none());
+  }
+  let results: Vec<Vec<i16>> = std::thread::scope(|s| {
+    let handles: Vec<_> = (0..8u64)
+      .map(|tile| {
+        let pool = &pool;
+        s.spawn(move || {
+          let mut buf = vec![0i16; 1000];
+          pool.checkout().read_at(tile * 1000, &mut buf).unwrap();
+          buf
+        })
+      })
+      .collect();
+    handles.into_iter().map(|h| h.join().unwrap()).collect()
+  });
+  assert_eq!(results.concat(), data);
+}
+
+#[test]
+fn tell_position() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("tell.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    assert_eq!(snd.tell().unwrap(), 0);
+    snd.write_from_slice(&[0i16; 200]).unwrap();
+    assert_eq!(snd.tell().unwrap(), 100);
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let mut buf = [0i16; 20];
+  snd.read_to_slice(&mut buf).unwrap();
+  assert_eq!(snd.tell().unwrap(), 10);
+  snd.seek(SeekFrom::Start(42)).unwrap();
+  assert_eq!(snd.tell().unwrap(), 42);
+  assert_eq!(snd.tell().unwrap(), 42);
+}
+
+#[test]
+fn read_uninit() {
+  use std::mem::MaybeUninit;
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("uninit.wav");
+  let data: Vec<f32> = (0..200).map(|x| x as f32 / 256.0).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::FLOAT,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let mut buf = vec![MaybeUninit::<f32>::uninit(); 300];
+  let out = snd.read_to_uninit_slice(&mut buf).unwrap();
+  assert_eq!(out, &data[..]);
+  let mut odd = vec![MaybeUninit::<f32>::uninit(); 3];
+  assert!(snd.read_to_uninit_slice(&mut odd).is_err());
+  let all: Vec<f32> = snd.read_all_to_vec().unwrap();
+  assert_eq!(all, data);
+}
+
+#[cfg(feature = "bytemuck_features")]
+#[test]
+fn bytes_io() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("bytes.wav");
+  let data: Vec<f32> = (0..64).map(|x| x as f32 / 64.0).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::FLOAT,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    let src: &[u8] = bytemuck::cast_slice(&data);
+    assert!(snd.write_from_bytes::<f32>(src, Endian::File).is_err());
+    assert!(snd.write_from_bytes::<f32>(&src[..5], Endian::CPU).is_err());
+    assert_eq!(snd.write_from_bytes::<f32>(src, Endian::CPU).unwrap(), 32);
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let mut out = vec![0.0f32; 64];
+  assert_eq!(
+    snd
+      .read_to_bytes::<f32>(bytemuck::cast_slice_mut(&mut out), Endian::CPU)
+      .unwrap(),
+    32
+  );
+  assert_eq!(out, data);
+}
+
+#[test]
+fn rt_reader() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("rt.wav");
+  let data: Vec<i16> = (0..100).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let mut rt = RtReader::new(
+    OpenOptions::ReadOnly(ReadOptions::Auto)
+      .from_path(&path)
+      .unwrap(),
+  );
+  let mut buf = [0i16; 64];
+  assert_eq!(
+    rt.read_to_slice_rt(&mut buf[..3]),
+    Err(RtError::InvalidBufferLength)
+  );
+  assert_eq!(rt.read_to_slice_rt(&mut buf).unwrap(), 32);
+  assert_eq!(&buf[..], &data[..64]);
+  assert_eq!(rt.read_to_slice_rt(&mut buf).unwrap(), 18);
+  assert_eq!(rt.seek_rt(SeekFrom::Start(10)).unwrap(), 10);
+  assert_eq!(rt.read_to_slice_rt(&mut buf[..2]).unwrap(), 1);
+  assert_eq!(&buf[..2], &data[20..22]);
+}
+
+#[test]
+fn stream_bridge() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("bridge.wav");
+  let data: Vec<f32> = (0..200).map(|x| x as f32 / 256.0).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::FLOAT,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  assert!(StreamBridge::new(snd, 0).is_err());
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let (mut producer, mut consumer) = StreamBridge::new(snd, 30).unwrap().split();
+
+  let mut buf = [1.0f32; 8];
+  assert_eq!(consumer.read(&mut buf), 0);
+  assert_eq!(buf, [0.0; 8]);
+  assert_eq!(consumer.underruns(), 1);
+  assert_eq!(consumer.underrun_frames(), 4);
+
+  let mut out = Vec::new();
+  let consumer = std::thread::spawn(move || {
+    while !consumer.is_finished() {
+      let mut buf = [0.0f32; 14];
+      let n = consumer.read(&mut buf);
+      out.extend_from_slice(&buf[..n * 2]);
+      std::thread::yield_now();
+    }
+    out
+  });
+  while !producer.is_finished() {
+    producer.fill().unwrap();
+    std::thread::yield_now();
+  }
+  assert_eq!(consumer.join().unwrap(), data);
+}
+
+#[test]
+fn capture_writer() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("capture.wav");
+  let data: Vec<f32> = (0..200).map(|x| x as f32 / 256.0).collect();
+  let snd = OpenOptions::WriteOnly(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::FLOAT,
+    Endian::File,
+    8000,
+    2,
+  ))
+  .from_path(&path)
+  .unwrap();
+  let (mut input, writer) =
+    CaptureWriter::spawn(snd, 16, std::time::Duration::from_millis(1)).unwrap();
+  let mut written = 0;
+  while written < 100 {
+    written += input.write(&data[written * 2..(written + 8).min(100) * 2]);
+    std::thread::yield_now();
+  }
+  drop(writer.finish().unwrap());
+  assert_eq!(input.write(&data[..4]), 0);
+
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let out: Vec<f32> = snd.read_all_to_vec().unwrap();
+  assert_eq!(out, data);
+}
+
+#[test]
+fn ring_wraps_with_any_capacity() {
+  let ring = crate::ring::Ring::new(6);
+  let mut next = 0.0f32;
+  let mut expected = 0.0f32;
+  let mut buf = [0.0f32; 6];
+  for i in 0..50 {
+    let src: Vec<f32> = (0..4).map(|x| next + x as f32).collect();
+    let n = ring.push(&src);
+    next += n as f32;
+    assert!(ring.len() <= ring.capacity());
+    let n = ring.pop(&mut buf[..i % 5 + 1]);
+    for x in buf[..n].iter() {
+      assert_eq!(*x, expected);
+      expected += 1.0;
+    }
+  }
+  let free = ring.capacity() - ring.len();
+  assert_eq!(ring.push(&[0.0; 8]), free);
+  assert_eq!(ring.len(), 6);
+}
+
+#[test]
+fn looping_reader() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("loop.wav");
+  let data: Vec<i16> = (0..10).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      1,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let open = || {
+    OpenOptions::ReadOnly(ReadOptions::Auto)
+      .from_path(&path)
+      .unwrap()
+  };
+  assert!(LoopingReader::new(open(), 4..11).is_err());
+  assert!(LoopingReader::new(open(), 4..4).is_err());
+  assert!(LoopingReader::from_instrument_loop(open()).is_err());
+  let mut rd = LoopingReader::new(open(), 6..9).unwrap();
+  let mut buf = [0i16; 12];
+  assert_eq!(rd.read_to_slice(&mut buf).unwrap(), 12);
+  assert_eq!(buf, [0, 1, 2, 3, 4, 5, 6, 7, 8, 6, 7, 8]);
+  assert_eq!(rd.position(), 6);
+}
+
+#[test]
+fn concat_reader() {
+  let tmp_dir = TempDir::new().unwrap();
+  let paths: Vec<_> = (0..3)
+    .map(|i| tmp_dir.as_ref().join(format!("part{}.wav", i)))
+    .collect();
+  for (i, path) in paths.iter().enumerate() {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(path)
+    .unwrap();
+    let data: Vec<i16> = (i as i16 * 10..i as i16 * 10 + 10).collect();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let mut rd = ConcatReader::open(&paths).unwrap();
+  assert_eq!(rd.len(), 15);
+  let mut buf = [0i16; 40];
+  assert_eq!(rd.read_to_slice(&mut buf).unwrap(), 15);
+  assert_eq!(&buf[..30], &(0..30).collect::<Vec<i16>>()[..]);
+  assert_eq!(rd.seek(SeekFrom::Start(4)).unwrap(), 4);
+  assert_eq!(rd.read_to_slice(&mut buf[..4]).unwrap(), 2);
+  assert_eq!(&buf[..4], &[8, 9, 10, 11]);
+  assert_eq!(rd.seek(SeekFrom::End(-1)).unwrap(), 14);
+  assert!(rd.seek(SeekFrom::Current(2)).is_err());
+}
+
+#[test]
+fn playlist() {
+  let tmp_dir = TempDir::new().unwrap();
+  let write = |name: &str, samplerate: usize, data: &[f32]| {
+    let path = tmp_dir.as_ref().join(name);
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::FLOAT,
+      Endian::File,
+      samplerate,
+      1,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(data).unwrap();
+    path
+  };
+  let a = write("a.wav", 8000, &[0.0, 0.1, 0.2]);
+  let b = write("b.wav", 16000, &[0.0, 0.1, 0.2, 0.3, 0.4, 0.5]);
+  let c = write("c.wav", 4000, &[0.0, 0.4, 0.8]);
+
+  assert!(Playlist::new(0, 1).is_err());
+  assert!(Playlist::new(8000, 0).is_err());
+  let mut playlist = Playlist::new(8000, 1).unwrap();
+  playlist.push(&a);
+  playlist.push(&b);
+  playlist.push(&c);
+  let mut buf = [0.0f32; 4];
+  assert_eq!(playlist.read(&mut buf).unwrap(), 4);
+  assert_eq!(buf, [0.0, 0.1, 0.2, 0.0]);
+  assert_eq!(
+    playlist.poll_event(),
+    Some(PlaylistEvent::TrackStarted {
+      index: 0,
+      path: a,
+      position: 0
+    })
+  );
+  assert_eq!(
+    playlist.poll_event(),
+    Some(PlaylistEvent::TrackEnded {
+      index: 0,
+      position: 3,
+      skipped: false
+    })
+  );
+  assert!(matches!(
+    playlist.poll_event(),
+    Some(PlaylistEvent::TrackStarted { index: 1, .. })
+  ));
+  assert_eq!(playlist.poll_event(), None);
+
+  assert!(playlist.next_track().unwrap());
+  let mut buf = [0.0f32; 8];
+  assert_eq!(playlist.read(&mut buf).unwrap(), 5);
+  let expected = [0.0, 0.2, 0.4, 0.6, 0.8];
+  for (x, y) in buf.iter().zip(expected.iter()) {
+    assert!((x - y).abs() < 1e-6);
+  }
+  assert_eq!(
+    playlist.poll_event(),
+    Some(PlaylistEvent::TrackEnded {
+      index: 1,
+      position: 4,
+      skipped: true
+    })
+  );
+  assert_eq!(playlist.position(), 9);
+  assert_eq!(playlist.current_index(), None);
+}
+
+#[test]
+fn analyze_stats() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("stats.wav");
+  let data: Vec<i16> = vec![i16::MAX, -100, 100, -100, i16::MIN, 300];
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let stats = snd.analyze().unwrap();
+  assert_eq!(stats.frames(), 3);
+  assert_eq!(stats.clipped(), &[2, 0]);
+  assert_eq!(stats.min(), &[-1.0, -100.0 / 32768.0]);
+  assert_eq!(stats.max(), &[32767.0 / 32768.0, 300.0 / 32768.0]);
+  assert!((stats.dc_offset()[1] - 100.0 / 3.0 / 32768.0).abs() < 1e-12);
+
+  let mut stats = Stats::new(2).unwrap();
+  stats.process(&data);
+  assert_eq!(stats.clipped(), &[1, 0]);
+  assert!((stats.rms()[1] - (110000.0f64 / 3.0).sqrt() / 32768.0).abs() < 1e-12);
+
+  assert!(Stats::new(0).is_err());
+  assert!(Stats::with_clip_level(0, 1.0).is_err());
+}
+
+#[test]
+fn metered_writer() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("meter.wav");
+  let snd = OpenOptions::WriteOnly(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::FLOAT,
+    Endian::File,
+    8000,
+    2,
+  ))
+  .from_path(&path)
+  .unwrap();
+  let meter = PeakMeter::new(2);
+  let mut writer = MeteredWriter::new(snd, meter.clone());
+  writer.write_from_slice(&[0.5f32, 0.0, -0.5, 0.25]).unwrap();
+  writer.write_from_slice(&[0.0f32, -1.0]).unwrap();
+  let levels = meter.take();
+  assert_eq!(levels.peak, vec![0.5, 1.0]);
+  assert!((levels.rms[0] - (0.5f64 / 3.0).sqrt()).abs() < 1e-12);
+  assert_eq!(meter.take().peak, vec![0.0, 0.0]);
+
+  let mut blocks = Vec::new();
+  let snd = writer.into_inner().0;
+  let mut writer = MeteredWriter::new(snd, |n, peak: &[f64], _: &[f64]| {
+    blocks.push((n, peak.to_vec()))
+  });
+  writer.write_from_slice(&[0.25f32, -0.125]).unwrap();
+  drop(writer);
+  assert_eq!(blocks, vec![(1, vec![0.25, 0.125])]);
+}
+
+#[test]
+fn verify_file() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("verify.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&[0i16; 10000]).unwrap();
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let v = snd.verify().unwrap();
+  assert!(v.is_ok());
+  assert_eq!(v.decoded_frames, 5000);
+  assert_eq!(v.declared_frames, Some(5000));
+  assert_eq!(v.first_failed_frame(), None);
+  drop(snd);
+
+  // Truncate the data chunk after 1000 frames.
+  let f = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+  f.set_len(44 + 4000).unwrap();
+  drop(f);
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let v = snd.verify().unwrap();
+  assert_eq!(v.decoded_frames, 1000);
+}
+
+#[test]
+fn close_into_inner() {
+  use std::io::{Read, Seek};
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("inner.wav");
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    8000,
+    1,
+  ))
+  .from_path(&path)
+  .unwrap();
+  snd.write_from_slice(&[0i16; 8]).unwrap();
+  let mut f = snd.close_into_inner().unwrap();
+  let mut bytes = Vec::new();
+  f.seek(SeekFrom::Start(0)).unwrap();
+  f.read_to_end(&mut bytes).unwrap();
+  assert_eq!(bytes, std::fs::read(&path).unwrap());
+  assert_eq!(&bytes[..4], b"RIFF");
+}
+
+#[test]
+fn byte_len() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("byte_len.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&[0i16; 200]).unwrap();
+  }
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  assert_eq!(
+    snd.byte_len().unwrap(),
+    std::fs::metadata(&path).unwrap().len()
+  );
+  assert!(snd.byte_len().unwrap() >= 400);
+}
+
+#[test]
+fn read_all_to_arc() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("arc.wav");
+  let data: Vec<f32> = (0..100).map(|x| x as f32 / 128.0).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::FLOAT,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let shared: std::sync::Arc<[f32]> = snd.read_all_to_arc().unwrap();
+  let other = shared.clone();
+  let sum = std::thread::spawn(move || other.iter().sum::<f32>())
+    .join()
+    .unwrap();
+  assert_eq!(&shared[..], &data[..]);
+  assert_eq!(sum, data.iter().sum::<f32>());
+}
+
+#[test]
+fn read_all_to_collection() {
+  use std::collections::VecDeque;
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("collect.wav");
+  let data: Vec<i16> = (0..9000).map(|x| (x % 1000) as i16).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let deque: VecDeque<i16> = snd.read_all_to().unwrap();
+  assert!(deque.iter().eq(data.iter()));
+
+  let mut out = vec![1i16, 2];
+  assert_eq!(snd.read_append_to::<i16, _>(&mut out).unwrap(), 4500);
+  assert_eq!(&out[..2], &[1, 2]);
+  assert_eq!(&out[2..], &data[..]);
+}
+
+#[test]
+fn error_kind_and_code() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("garbage.wav");
+  std::fs::write(&path, vec![0x55u8; 4096]).unwrap();
+  let e = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap_err();
+  assert_eq!(e.kind(), ErrorKind::Unsupported);
+  assert_eq!(e.code(), Some(sndfile_sys::SF_ERR_UNRECOGNISED_FORMAT));
+
+  let e = SndFileError::InvalidParameter("x".to_string());
+  assert_eq!(e.kind(), ErrorKind::InvalidInput);
+  assert_eq!(e.code(), None);
+  let e = SndFileError::LibraryError {
+    code: 42,
+    message: "x".to_string(),
+  };
+  assert_eq!((e.kind(), e.code()), (ErrorKind::Other, Some(42)));
+}
+
+#[test]
+fn open_after_global_lock_poisoned() {
+  let _ = std::thread::spawn(|| {
+    let _guard = get_sf_global_lock().lock();
+    panic!("poison the global lock");
+  })
+  .join();
+  assert!(get_sf_global_lock().is_poisoned());
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("poisoned.wav");
+  let snd = OpenOptions::WriteOnly(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    8000,
+    1,
+  ))
+  .from_path(&path);
+  assert!(snd.is_ok());
+}
+
+#[test]
+fn len_and_read_all_preserve_cursor() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("cursor.wav");
+  let data: Vec<i16> = (0..200).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  snd.seek(SeekFrom::Start(30)).unwrap();
+  assert_eq!(snd.len().unwrap(), 100);
+  assert_eq!(snd.tell().unwrap(), 30);
+  let all: Vec<i16> = snd.read_all_to_vec().unwrap();
+  assert_eq!(all, data);
+  assert_eq!(snd.tell().unwrap(), 30);
+  let mut buf = [0i16; 2];
+  snd.read_to_slice(&mut buf).unwrap();
+  assert_eq!(buf, [60, 61]);
+}
+
+#[test]
+fn read_write_cursors_are_independent() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("rdwr_cursor.wav");
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    8000,
+    1,
+  ))
+  .from_path(&path)
+  .unwrap();
+  let data: Vec<i16> = (0..150).collect();
+  snd.write_from_slice(&data[..100]).unwrap();
+  assert_eq!(snd.tell().unwrap(), 100);
+  assert_eq!(snd.len().unwrap(), 100);
+  let mut buf = [0i16; 10];
+  snd.read_to_slice(&mut buf).unwrap();
+  assert_eq!(buf[..], data[..10]);
+  assert_eq!(snd.tell().unwrap(), 100);
+
+  snd.write_from_slice(&data[100..]).unwrap();
+  assert_eq!(snd.tell().unwrap(), 150);
+  assert_eq!(snd.len().unwrap(), 150);
+  let all: Vec<i16> = snd.read_all_to_vec().unwrap();
+  assert_eq!(all, data);
+  assert_eq!(snd.tell().unwrap(), 150);
+  snd.read_to_slice(&mut buf).unwrap();
+  assert_eq!(buf[..], data[10..20]);
+  assert_eq!(snd.tell().unwrap(), 150);
+}
+
+#[test]
+fn read_all_truncated_file() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("short.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&[1i16; 10000]).unwrap();
+  }
+  // Truncate the data chunk after 1000 frames, the header still declares 5000 frames.
+  let f = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+  f.set_len(44 + 4000).unwrap();
+  drop(f);
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let buf: Vec<i16> = snd.read_all_to_vec().unwrap();
+  assert_eq!(buf, vec![1i16; 2000]);
+  let shared: std::sync::Arc<[i16]> = snd.read_all_to_arc().unwrap();
+  assert_eq!(&shared[..], &buf[..]);
+}
+
+#[test]
+fn tag_with_nul_and_raw_bytes() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("tags.wav");
+  let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    8000,
+    1,
+  ))
+  .from_path(&path)
+  .unwrap();
+  match snd.set_tag(TagType::Title, "a\0b") {
+    Err(SndFileError::InvalidParameter(_)) => (),
+    r => panic!("unexpected result {:?}", r),
+  }
+  snd.set_tag(TagType::Artist, "Dvořák").unwrap();
+  assert_eq!(
+    snd.get_tag_raw(TagType::Artist).unwrap(),
+    "Dvořák".as_bytes()
+  );
+  assert_eq!(snd.get_tag(TagType::Artist).unwrap(), "Dvořák");
+  assert_eq!(snd.get_tag_raw(TagType::Album), None);
+}
+
+#[test]
+fn write_options_with_tags() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("header_tags.wav");
+  let options = || {
+    WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      1,
+    )
+  };
+  {
+    let mut snd =
+      OpenOptions::WriteOnly(options().with_tag(TagType::Title, "Header").with_tags(vec![
+        (TagType::Artist, "Someone"),
+        (TagType::Album, "Album"),
+      ]))
+      .from_path(&path)
+      .unwrap();
+    snd.write_from_slice(&[0i16; 100]).unwrap();
+  }
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  assert_eq!(snd.get_tag(TagType::Title).unwrap(), "Header");
+  assert_eq!(snd.get_tag(TagType::Artist).unwrap(), "Someone");
+  assert_eq!(snd.get_tag(TagType::Album).unwrap(), "Album");
+  drop(snd);
+  assert!(
+    OpenOptions::WriteOnly(options().with_tag(TagType::Title, "a\0b"))
+      .from_path(&path)
+      .is_err()
+  );
+}
+
+#[test]
+fn open_from_virtual_io() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("vio.wav");
+  let data: Vec<i16> = (0..1000).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let bytes = std::fs::read(&path).unwrap();
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_virtual_io(std::io::Cursor::new(bytes.clone()))
+    .unwrap();
+  assert_eq!(snd.get_channels(), 2);
+  assert_eq!(snd.byte_len().unwrap(), bytes.len() as u64);
+  let buf: Vec<i16> = snd.read_all_to_vec().unwrap();
+  assert_eq!(buf, data);
+  match snd.close_into_inner() {
+    Err(SndFileError::InvalidParameter(_)) => (),
+    r => panic!("unexpected result {:?}", r),
+  }
+}
+
+#[cfg(feature = "http_features")]
+#[test]
+fn open_from_url() {
+  use std::io::{BufRead, BufReader, Write};
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("remote.wav");
+  let data: Vec<f32> = (0..20000).map(|x| (x % 100) as f32 / 128.0).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::FLOAT,
+      Endian::File,
+      8000,
+      1,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let bytes = std::fs::read(&path).unwrap();
+
+  // A minimal server answering range requests, one request per connection.
+  let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+  let url = format!("http://{}/remote.wav", listener.local_addr().unwrap());
+  std::thread::spawn(move || {
+    for stream in listener.incoming() {
+      let mut stream = stream.unwrap();
+      let mut range = None;
+      for line in BufReader::new(&stream).lines() {
+        let line = line.unwrap();
+        if line.is_empty() {
+          break;
+        }
+        if let Some(x) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+          let (a, b) = x.split_once('-').unwrap();
+          range = Some((a.parse::<usize>().unwrap(), b.parse::<usize>().unwrap()));
+        }
+      }
+      let (a, b) = range.unwrap();
+      let b = b.min(bytes.len() - 1);
+      write!(
+        stream,
+        "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        a,
+        b,
+        bytes.len(),
+        b - a + 1
+      )
+      .unwrap();
+      stream.write_all(&bytes[a..=b]).unwrap();
+    }
+  });
+
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_virtual_io(HttpSource::with_cache(&url, 4096, 4).unwrap())
+    .unwrap();
+  assert_eq!(snd.len().unwrap(), 20000);
+  let mut buf = vec![0.0f32; 100];
+  snd.seek(SeekFrom::Start(15000)).unwrap();
+  assert_eq!(snd.read_to_slice(&mut buf).unwrap(), 100);
+  assert_eq!(buf[..], data[15000..15100]);
+  let all: Vec<f32> = snd.read_all_to_vec().unwrap();
+  assert_eq!(all, data);
+}
+
+#[cfg(feature = "object_store_features")]
+#[test]
+fn open_from_object_store() {
+  use object_store::{memory::InMemory, path::Path, ObjectStore};
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("object.flac");
+  let data: Vec<i16> = (0..30000).map(|x| (x % 500) as i16).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::FLAC,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      44100,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let store: std::sync::Arc<dyn ObjectStore> = std::sync::Arc::new(InMemory::new());
+  let key = Path::from("takes/object.flac");
+  tokio::runtime::Builder::new_current_thread()
+    .build()
+    .unwrap()
+    .block_on(store.put(&key, std::fs::read(&path).unwrap().into()))
+    .unwrap();
+
+  let source = ObjectStoreSource::with_cache(store.clone(), &key, 1024, 2).unwrap();
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_virtual_io(source)
+    .unwrap();
+  assert_eq!(snd.get_major_format(), MajorFormat::FLAC);
+  let buf: Vec<i16> = snd.read_all_to_vec().unwrap();
+  assert_eq!(buf, data);
+
+  // A single read spanning several cached blocks.
+  let wav_path = tmp_dir.as_ref().join("object.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      44100,
+      2,
+    ))
+    .from_path(&wav_path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let wav_key = Path::from("takes/object.wav");
+  tokio::runtime::Builder::new_current_thread()
+    .build()
+    .unwrap()
+    .block_on(store.put(&wav_key, std::fs::read(&wav_path).unwrap().into()))
+    .unwrap();
+  let source = ObjectStoreSource::with_cache(store.clone(), &wav_key, 1024, 2).unwrap();
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_virtual_io(source)
+    .unwrap();
+  let mut buf = vec![0i16; 8000];
+  assert_eq!(snd.read_to_slice(&mut buf).unwrap(), 4000);
+  assert_eq!(buf[..], data[..8000]);
+
+  assert!(OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_object_store(store, &Path::from("missing.flac"))
+    .is_err());
+}
+
+#[cfg(feature = "zip_features")]
+#[test]
+fn open_from_zip_entry() {
+  use std::io::Write;
+  use zip::write::SimpleFileOptions;
+  let tmp_dir = TempDir::new().unwrap();
+  let wav_path = tmp_dir.as_ref().join("kick.wav");
+  let data: Vec<i16> = (0..4000).map(|x| (x % 300) as i16).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      44100,
+      1,
+    ))
+    .from_path(&wav_path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let wav = std::fs::read(&wav_path).unwrap();
+  let zip_path = tmp_dir.as_ref().join("library.zip");
+  {
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+    zip
+      .start_file("readme.txt", SimpleFileOptions::default())
+      .unwrap();
+    zip.write_all(b"drums").unwrap();
+    for (name, method) in [
+      ("stored/kick.wav", zip::CompressionMethod::Stored),
+      ("deflated/kick.wav", zip::CompressionMethod::Deflated),
+    ] {
+      zip
+        .start_file(
+          name,
+          SimpleFileOptions::default().compression_method(method),
+        )
+        .unwrap();
+      zip.write_all(&wav).unwrap();
+    }
+    zip.finish().unwrap();
+  }
+  for name in ["stored/kick.wav", "deflated/kick.wav"] {
+    let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+      .from_zip_entry(&zip_path, name)
+      .unwrap();
+    assert_eq!(snd.byte_len().unwrap(), wav.len() as u64);
+    let buf: Vec<i16> = snd.read_all_to_vec().unwrap();
+    assert_eq!(buf, data);
+  }
+  match OpenOptions::ReadOnly(ReadOptions::Auto).from_zip_entry(&zip_path, "missing.wav") {
+    Err(SndFileError::InvalidParameter(_)) => (),
+    r => panic!("unexpected result {:?}", r),
+  }
+}
+
+#[test]
+fn open_uri_schemes() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("uri test.wav");
+  let data: Vec<i16> = (0..600).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let bytes = std::fs::read(&path).unwrap();
+  const B64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+  let base64: String = bytes
+    .chunks(3)
+    .flat_map(|c| {
+      let x = c.iter().fold(0u32, |acc, b| (acc << 8) | *b as u32) << (8 * (3 - c.len()));
+      (0..4).map(move |i| {
+        if i <= c.len() {
+          B64[(x >> (18 - 6 * i) & 63) as usize] as char
+        } else {
+          '='
+        }
+      })
+    })
+    .collect();
+  let percent: String = bytes.iter().map(|b| format!("%{:02X}", b)).collect();
+  let file_uri = format!("file://{}", path.to_str().unwrap().replace(' ', "%20"));
+  for uri in [
+    path.to_str().unwrap().to_string(),
+    file_uri,
+    format!("data:audio/wav;base64,{}", base64),
+    format!("data:audio/wav,{}", percent),
+  ] {
+    let mut snd = open_uri(&uri, ReadOptions::Auto).unwrap();
+    let buf: Vec<i16> = snd.read_all_to_vec().unwrap();
+    assert_eq!(buf, data);
+  }
+  match open_uri("data:audio/wav;base64,!!", ReadOptions::Auto) {
+    Err(SndFileError::InvalidParameter(_)) => (),
+    r => panic!("unexpected result {:?}", r),
+  }
+}
+
+#[test]
+fn load_library_succeeds() {
+  assert!(load_library().is_ok());
+}
+
+#[test]
+fn supports_formats() {
+  assert!(library_version().unwrap().starts_with("libsndfile"));
+  assert!(supports(MajorFormat::WAV, SubtypeFormat::PCM_16));
+  assert!(supports(MajorFormat::WVE, SubtypeFormat::ALAW));
+  assert!(!supports(MajorFormat::WAV, SubtypeFormat::VORBIS));
+  assert!(!supports(
+    MajorFormat::Unknown(0x7f_0000),
+    SubtypeFormat::PCM_16
+  ));
+}
+
+#[test]
+fn path_and_source() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("source.wav");
+  let snd = OpenOptions::WriteOnly(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    8000,
+    1,
+  ))
+  .from_path(&path)
+  .unwrap();
+  assert_eq!(snd.path(), Some(path.as_path()));
+  assert_eq!(snd.source(), SourceKind::Path);
+  drop(snd);
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_file(std::fs::File::open(&path).unwrap())
+    .unwrap();
+  assert_eq!(snd.path(), None);
+  assert_eq!(snd.source(), SourceKind::File);
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_virtual_io(std::io::Cursor::new(std::fs::read(&path).unwrap()))
+    .unwrap();
+  assert_eq!(snd.path(), None);
+  assert_eq!(snd.source(), SourceKind::VirtualIo);
+}
+
+#[test]
+fn write_test_signals() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("signal.wav");
+  let mut w = SndWriter::create(
+    &path,
+    WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::FLOAT,
+      Endian::File,
+      48000,
+      2,
+    ),
+  )
+  .unwrap();
+  signal::sine(&mut w, 1000.0, -6.0, 48000).unwrap();
+  signal::silence(&mut w, 100).unwrap();
+  signal::white_noise(&mut w, -20.0, 4800, 1).unwrap();
+  signal::pink_noise(&mut w, -20.0, 4800, 1).unwrap();
+  signal::sweep(&mut w, 20.0, 20000.0, -3.0, 48000).unwrap();
+  assert!(signal::sweep(&mut w, 0.0, 20000.0, -3.0, 10).is_err());
+  drop(w);
+
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let buf: Vec<f32> = snd.read_all_to_vec().unwrap();
+  assert_eq!(buf.len(), (48000 + 100 + 4800 * 2 + 48000) * 2);
+  let peak = |x: &[f32]| x.iter().fold(0.0f32, |acc, y| acc.max(y.abs()));
+  let (sine, rest) = buf.split_at(48000 * 2);
+  let (silence, rest) = rest.split_at(100 * 2);
+  let (white, rest) = rest.split_at(4800 * 2);
+  let (pink, sweep) = rest.split_at(4800 * 2);
+  assert!((peak(sine) - 0.5012).abs() < 1e-3);
+  assert_eq!(sine[0], sine[1]);
+  assert_eq!(peak(silence), 0.0);
+  assert!(peak(white) <= 0.1 && peak(white) > 0.09);
+  assert!(peak(pink) <= 0.1 && peak(pink) > 0.01);
+  assert!((peak(sweep) - 0.708).abs() < 1e-2);
+}
+
+#[test]
+fn write_silence_in_blocks() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("silence.wav");
+  let mut w = SndWriter::create(
+    &path,
+    WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ),
+  )
+  .unwrap();
+  w.write_from_slice(&[1i16, 2]).unwrap();
+  assert_eq!(w.write_silence(10000).unwrap(), 10000);
+  assert_eq!(w.write_silence(0).unwrap(), 0);
+  w.write_from_slice(&[3i16, 4]).unwrap();
+  drop(w);
+
+  let mut snd = SndReader::open(&path, ReadOptions::Auto).unwrap();
+  let buf: Vec<i16> = snd.read_all_to_vec().unwrap();
+  assert_eq!(buf.len(), 10002 * 2);
+  assert_eq!(&buf[..2], &[1, 2]);
+  assert!(buf[2..20002].iter().all(|x| *x == 0));
+  assert_eq!(&buf[20002..], &[3, 4]);
+}
+
+#[test]
+fn gapless_info() {
+  let info = GaplessInfo {
+    encoder_delay: 576,
+    padding: 1000,
+    mpeg_frames: Some(100),
+    samples_per_frame: 1152,
+  };
+  assert_eq!(info.duration_frames(), Some(100 * 1152 - 1576));
+  assert_eq!(
+    GaplessInfo {
+      mpeg_frames: None,
+      ..info
+    }
+    .duration_frames(),
+    None
+  );
+
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("not_mpeg.wav");
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    44100,
+    1,
+  ))
+  .from_path(&path)
+  .unwrap();
+  match snd.get_gapless_info() {
+    Err(SndFileError::InvalidParameter(_)) => (),
+    r => panic!("unexpected result {:?}", r),
+  }
+}
+
+#[test]
+fn wavex_channel_mask() {
+  assert_eq!(ChannelMask::SURROUND_7_1_4.channels(), 12);
+  assert!(ChannelMask::SURROUND_5_1.contains(ChannelMask::STEREO | ChannelMask::LOW_FREQUENCY));
+  let positions = ChannelMask::SURROUND_7_1.positions().unwrap();
+  assert_eq!(positions[3], ChannelPosition::Lfe);
+  assert_eq!(
+    ChannelMask::from_positions(&positions),
+    Some(ChannelMask::SURROUND_7_1)
+  );
+  assert_eq!(
+    ChannelMask::from_positions(&[ChannelPosition::FrontRight, ChannelPosition::FrontLeft]),
+    None
+  );
+
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("surround.wav");
+  let options = || {
+    WriteOptions::new(
+      MajorFormat::WAVEX,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      48000,
+      6,
+    )
+  };
+  {
+    let mut snd = OpenOptions::WriteOnly(options().with_channel_mask(ChannelMask::SURROUND_5_1))
+      .from_path(&path)
+      .unwrap();
+    snd.write_from_slice(&[0i16; 60]).unwrap();
+  }
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  assert_eq!(
+    snd.get_channel_mask().unwrap(),
+    Some(ChannelMask::SURROUND_5_1)
+  );
+  match OpenOptions::WriteOnly(options().with_channel_mask(ChannelMask::STEREO)).from_path(&path) {
+    Err(SndFileError::InvalidParameter(_)) => (),
+    r => panic!("unexpected result {:?}", r),
+  }
+}
+
+#[test]
+fn read_write_frame_arrays() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("frames.wav");
+  let frames: Vec<[f32; 2]> = (0..100)
+    .map(|i| [i as f32 / 128.0, -i as f32 / 128.0])
+    .collect();
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::FLOAT,
+    Endian::File,
+    8000,
+    2,
+  ))
+  .from_path(&path)
+  .unwrap();
+  assert_eq!(snd.write_frames(&frames).unwrap(), 100);
+  match snd.write_frames(&[[0.0f32; 3]]) {
+    Err(SndFileError::InvalidParameter(_)) => (),
+    r => panic!("unexpected result {:?}", r),
+  }
+  snd.seek(SeekFrom::Start(0)).unwrap();
+  let mut buf = vec![[0.0f32; 2]; 150];
+  assert_eq!(snd.read_frames(&mut buf).unwrap(), 100);
+  assert_eq!(&buf[..100], &frames[..]);
+  assert!(snd.read_frames(&mut [[0.0f32; 1]; 4]).is_err());
+}
+
+#[test]
+fn mono_and_stereo_readers() {
+  let tmp_dir = TempDir::new().unwrap();
+  let mono_path = tmp_dir.as_ref().join("mono.wav");
+  let stereo_path = tmp_dir.as_ref().join("stereo.wav");
+  for (path, channels) in [(&mono_path, 1), (&stereo_path, 2)] {
+    let mut snd = SndWriter::create(
+      path,
+      WriteOptions::new(
+        MajorFormat::WAV,
+        SubtypeFormat::FLOAT,
+        Endian::File,
+        8000,
+        channels,
+      ),
+    )
+    .unwrap();
+    let data: Vec<f32> = (0..10000 * channels).map(|i| i as f32 / 65536.0).collect();
+    snd.write_from_slice(&data).unwrap();
+  }
+
+  let mut mono = MonoReader::open(&mono_path).unwrap();
+  let mut buf = vec![0.0f32; 12000];
+  assert_eq!(mono.read(&mut buf).unwrap(), 10000);
+  assert_eq!(buf[9999], 9999.0 / 65536.0);
+  assert!(MonoReader::open(&stereo_path).is_err());
+
+  let mut stereo = StereoReader::open(&stereo_path).unwrap();
+  let mut buf = vec![(0.0f32, 0.0f32); 12000];
+  assert_eq!(stereo.read(&mut buf).unwrap(), 10000);
+  assert_eq!(buf[9999], (19998.0 / 65536.0, 19999.0 / 65536.0));
+  match StereoReader::open(&mono_path) {
+    Err(SndFileError::InvalidParameter(_)) => (),
+    r => panic!("unexpected result {:?}", r),
+  }
+}
+
+#[test]
+fn frames_view() {
+  let buf: Vec<i16> = (0..12).collect();
+  let frames = Frames::new(&buf, 3).unwrap();
+  assert_eq!(frames.len(), 4);
+  assert_eq!(&frames[1], &[3, 4, 5]);
+  assert_eq!(frames.get(4), None);
+  assert_eq!(frames.sample(2, 1), Some(&7));
+  assert_eq!(frames.sample(2, 3), None);
+  assert_eq!(
+    frames.channel(2).copied().collect::<Vec<_>>(),
+    vec![2, 5, 8, 11]
+  );
+  assert_eq!(frames.iter().map(|x| x[0]).sum::<i16>(), 18);
+  assert_eq!(frames.into_iter().count(), 4);
+  assert!(Frames::new(&buf, 5).is_err());
+  assert!(Frames::new(&buf, 0).is_err());
+  assert_eq!(Frames::<f32>::new(&[], 2).unwrap().channel(1).count(), 0);
+}
+
+#[cfg(feature = "ndarray_features")]
+#[test]
+fn read_to_ndarray_filled_zeroes_tail() {
+  use ndarray::Array2;
+  let tmp_dir = TempDir::new().unwrap();
+  let tmp_path = tmp_dir.as_ref().join("read_to_ndarray_filled.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&tmp_path)
+    .unwrap();
+    snd.write_from_slice(&[1i16; 20]).unwrap();
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&tmp_path)
+    .unwrap();
+  let mut buf = Array2::<i16>::from_elem((16, 2), 7);
+  let filled = snd.read_to_ndarray_filled(buf.view_mut()).unwrap();
+  assert_eq!(filled.shape(), &[10, 2]);
+  assert!(filled.iter().all(|x| *x == 1));
+  assert!(buf.slice(ndarray::s![10.., ..]).iter().all(|x| *x == 0));
+}
+
+#[test]
+fn finalize_writer_summary() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("finalize.wav");
+  let mut w = SndWriter::create(
+    &path,
+    WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ),
+  )
+  .unwrap();
+  w.write_from_slice(&[0.25f32, -0.5, 0.0, 0.125]).unwrap();
+  w.write_silence(7998).unwrap();
+  let summary = w.finalize().unwrap();
+  assert_eq!(summary.frames, 8000);
+  assert_eq!(summary.duration, std::time::Duration::from_secs(1));
+  assert_eq!(summary.peak, 0.5);
+  assert_eq!(summary.bytes, std::fs::metadata(&path).unwrap().len());
+  assert!(summary.bytes >= 8000 * 4);
+}
+
+#[test]
+fn writer_stats() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("writer_stats.wav");
+  let options = || {
+    WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    )
+  };
+  let mut w = SndWriter::create(&path, options()).unwrap();
+  w.write_from_slice(&[1.5f32, 0.0]).unwrap();
+  assert_eq!(w.stats().frames, 1);
+  assert_eq!(w.stats().clipped, None);
+  drop(w);
+
+  let mut w = SndWriter::create(&path, options())
+    .unwrap()
+    .with_clip_counting();
+  w.write_from_slice(&[1.5f32, 0.0, -1.0, -2.0]).unwrap();
+  w.write_silence(100).unwrap();
+  let stats = w.stats();
+  assert_eq!(stats.frames, 102);
+  assert_eq!(stats.clipped, Some(2));
+  assert!(stats.frames_per_second() > 0.0);
+}
+
+#[test]
+fn writer_clipping_per_channel() {
+  let tmp_dir = TempDir::new().unwrap();
+  let options = |subtype| WriteOptions::new(MajorFormat::WAV, subtype, Endian::File, 8000, 2);
+  let mut w = SndWriter::create(
+    tmp_dir.as_ref().join("clip_pcm.wav"),
+    options(SubtypeFormat::PCM_24),
+  )
+  .unwrap()
+  .with_clip_counting();
+  w.write_from_slice(&[0.0f64, 0.0, 0.5, 1.25]).unwrap();
+  w.write_from_slice(&[-1.5f64, 1.5, 0.0, 0.0]).unwrap();
+  let stats = w.stats();
+  assert_eq!(stats.clipped, Some(3));
+  drop(w);
+  // Clipped to full scale, not wrapped around.
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(tmp_dir.as_ref().join("clip_pcm.wav"))
+    .unwrap();
+  let mut buf = [0i32; 8];
+  assert_eq!(snd.read_to_slice(&mut buf).unwrap(), 4);
+  assert_eq!(buf[3], i32::MAX & !0xff);
+  assert_eq!(buf[4], i32::MIN);
+  assert_eq!(buf[5], i32::MAX & !0xff);
+  assert_eq!(
+    stats.clipped_channels.unwrap(),
+    vec![
+      ClippedChannel {
+        count: 1,
+        first_frame: Some(2)
+      },
+      ClippedChannel {
+        count: 2,
+        first_frame: Some(1)
+      },
+    ]
+  );
+
+  let mut w = SndWriter::create(
+    tmp_dir.as_ref().join("clip_float.wav"),
+    options(SubtypeFormat::FLOAT),
+  )
+  .unwrap()
+  .with_clip_counting();
+  w.write_from_slice(&[2.0f32, -2.0]).unwrap();
+  assert_eq!(w.stats().clipped, Some(0));
+}
+
+#[test]
+fn writer_strict_range() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("strict.wav");
+  let mut w = SndWriter::create(
+    &path,
+    WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ),
+  )
+  .unwrap()
+  .with_strict_range();
+  w.write_from_slice(&[0.5f32, -1.0, 0.0, 0.0]).unwrap();
+  match w.write_from_slice(&[0.0f32, 0.0, 0.0, 1.01]) {
+    Err(SndFileError::InvalidParameter(msg)) => assert!(msg.contains("frame 3")),
+    r => panic!("unexpected result {:?}", r),
+  }
+  w.write_from_slice(&[32767i16, -32768]).unwrap();
+  assert_eq!(w.finalize().unwrap().frames, 3);
+}
+
+#[test]
+fn best_subtype_for_sample_types() {
+  assert_eq!(
+    best_subtype_for::<f32>(MajorFormat::WAV),
+    Some(SubtypeFormat::FLOAT)
+  );
+  assert_eq!(
+    best_subtype_for::<f64>(MajorFormat::AIFF),
+    Some(SubtypeFormat::DOUBLE)
+  );
+  assert_eq!(
+    best_subtype_for::<i16>(MajorFormat::WAV),
+    Some(SubtypeFormat::PCM_16)
+  );
+  assert_eq!(
+    best_subtype_for::<i32>(MajorFormat::WAV),
+    Some(SubtypeFormat::PCM_32)
+  );
+  assert_eq!(best_subtype_for::<f32>(MajorFormat::OGG), None);
+  if supports(MajorFormat::FLAC, SubtypeFormat::PCM_24) {
+    assert!(matches!(
+      best_subtype_for::<f32>(MajorFormat::FLAC),
+      Some(SubtypeFormat::PCM_24) | Some(SubtypeFormat::PCM_32)
+    ));
+  }
+}
+
+#[test]
+fn write_options_bit_depth() {
+  let options = |major| WriteOptions::new(major, SubtypeFormat::PCM_16, Endian::File, 44100, 2);
+  let w = options(MajorFormat::WAV).with_bit_depth(24).unwrap();
+  assert_eq!(w.subtype_format, SubtypeFormat::PCM_24);
+  let w = options(MajorFormat::WAV).with_bit_depth(8).unwrap();
+  assert_eq!(w.subtype_format, SubtypeFormat::PCM_U8);
+  let w = options(MajorFormat::AIFF).with_bit_depth(8).unwrap();
+  assert_eq!(w.subtype_format, SubtypeFormat::PCM_S8);
+  assert!(options(MajorFormat::WAV).with_bit_depth(20).is_err());
+  assert!(options(MajorFormat::WAV).with_bit_depth(12).is_err());
+}
+
+#[test]
+fn format_classes() {
+  assert!(MajorFormat::FLAC.is_compressed());
+  assert!(!MajorFormat::WAV.is_compressed());
+  assert!(SubtypeFormat::PCM_24.is_lossless());
+  assert!(SubtypeFormat::ALAC_16.is_lossless());
+  assert!(!SubtypeFormat::VORBIS.is_lossless());
+  assert!(!SubtypeFormat::Unknown(0x7f).is_lossless());
+  assert_eq!(
+    FormatClass::of(MajorFormat::WAV, SubtypeFormat::PCM_16),
+    FormatClass::Uncompressed
+  );
+  assert_eq!(
+    FormatClass::of(MajorFormat::FLAC, SubtypeFormat::PCM_16),
+    FormatClass::Lossless
+  );
+  assert_eq!(
+    FormatClass::of(MajorFormat::CAF, SubtypeFormat::ALAC_24),
+    FormatClass::Lossless
+  );
+  assert_eq!(
+    FormatClass::of(MajorFormat::WAV, SubtypeFormat::IMA_ADPCM),
+    FormatClass::Lossy
+  );
+  assert!(FormatClass::of(MajorFormat::OGG, SubtypeFormat::Unknown(0x64)).is_lossy());
+  assert_eq!(
+    FormatClass::of(MajorFormat::WAV, SubtypeFormat::Unknown(0x7f)),
+    FormatClass::Unknown
+  );
+}
+
+#[test]
+fn resolved_endian_of_containers() {
+  let tmp_dir = TempDir::new().unwrap();
+  for &(major, endian, expected) in [
+    (MajorFormat::WAV, Endian::File, Endian::Little),
+    (MajorFormat::AIFF, Endian::File, Endian::Big),
+    (MajorFormat::AU, Endian::Little, Endian::Little),
+  ]
+  .iter()
+  {
+    let path = tmp_dir.as_ref().join(format!("endian_{:?}.snd", major));
+    {
+      let snd = OpenOptions::WriteOnly(WriteOptions::new(
+        major,
+        SubtypeFormat::PCM_16,
+        endian,
+        8000,
+        1,
+      ))
+      .from_path(&path)
+      .unwrap();
+      assert_eq!(snd.resolved_endian(), expected);
+    }
+    let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+      .from_path(&path)
+      .unwrap();
+    assert_eq!(snd.resolved_endian(), expected);
+  }
+}
+
+#[test]
+fn refresh_info_after_writes() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("refresh_info.wav");
+  let mut snd = OpenOptions::WriteRead(WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    8000,
+    2,
+  ))
+  .from_path(&path)
+  .unwrap();
+  assert_eq!(snd.refresh_info().unwrap().frames, Some(0));
+  snd.write_from_slice(&[0i16; 200]).unwrap();
+  let info = snd.refresh_info().unwrap();
+  assert_eq!(info.frames, Some(100));
+  assert_eq!(info.channels, 2);
+  assert_eq!(info.major_format, MajorFormat::WAV);
+}
+
+#[test]
+fn streaming_read_and_len_hint() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("streaming.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    let data: Vec<i16> = (0..20000).map(|x| x as i16).collect();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  assert_eq!(snd.len_hint(), Some(10000));
+  snd.seek(SeekFrom::Start(1000)).unwrap();
+  let buf: Vec<i16> = snd.read_to_end(None).unwrap();
+  assert_eq!(buf.len(), 18000);
+  assert_eq!(buf[0], 2000);
+  snd.seek(SeekFrom::Start(0)).unwrap();
+  match snd.read_to_end::<i16>(Some(30000)) {
+    Err(SndFileError::MemoryLimitExceeded { limit: 30000, .. }) => (),
+    r => panic!("unexpected result {:?}", r),
+  }
+
+  // An AU stream read through a pipe, which can only be read forward.
+  struct Pipe(std::io::Cursor<Vec<u8>>);
+  impl std::io::Read for Pipe {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+      self.0.read(buf)
+    }
+  }
+  impl std::io::Seek for Pipe {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+      let here = self.0.position();
+      match pos {
+        SeekFrom::Current(0) => Ok(here),
+        SeekFrom::Start(x) if x == here => Ok(here),
+        _ => Err(std::io::Error::other("Stream is not seekable.")),
+      }
+    }
+  }
+  impl VirtualIo for Pipe {
+    fn byte_len(&self) -> std::io::Result<u64> {
+      Ok(self.0.get_ref().len() as u64)
+    }
+    fn is_seekable(&self) -> bool {
+      false
+    }
+  }
+  let au_path = tmp_dir.as_ref().join("streaming.au");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::AU,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&au_path)
+    .unwrap();
+    let data: Vec<i16> = (0..20000).map(|x| x as i16).collect();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let au = std::fs::read(&au_path).unwrap();
+  let open = || {
+    OpenOptions::ReadOnly(ReadOptions::Auto)
+      .from_virtual_io(Pipe(std::io::Cursor::new(au.clone())))
+      .unwrap()
+  };
+  let mut snd = open();
+  assert!(!snd.is_seekable());
+  match snd.len() {
+    Err(SndFileError::Unseekable {
+      len_hint: Some(10000),
+    }) => (),
+    r => panic!("unexpected result {:?}", r),
+  }
+  assert!(snd.tell().is_err());
+  let buf: Vec<i16> = snd.read_all_to_vec().unwrap();
+  assert_eq!(buf.len(), 20000);
+  assert_eq!(buf[19999], 19999);
+  let buf: Vec<i16> = open().read_all_to_vec_limited(40000).unwrap();
+  assert_eq!(buf.len(), 20000);
+  match open().read_all_to_vec_limited::<i16>(30000) {
+    Err(SndFileError::MemoryLimitExceeded { limit: 30000, .. }) => (),
+    r => panic!("unexpected result {:?}", r),
+  }
+  let buf: std::sync::Arc<[f32]> = open().read_all_to_arc().unwrap();
+  assert_eq!(buf.len(), 20000);
+  let buf: std::collections::VecDeque<i32> = open().read_all_to().unwrap();
+  assert_eq!(buf.len(), 20000);
+  let mut buf: Vec<i16> = vec![7];
+  assert_eq!(open().read_append_to::<i16, _>(&mut buf).unwrap(), 10000);
+  assert_eq!(buf.len(), 20001);
+  let mut calls = Vec::new();
+  let buf: Vec<f64> = open()
+    .read_all_to_vec_with_progress(|done, total| calls.push((done, total)))
+    .unwrap();
+  assert_eq!(buf.len(), 20000);
+  assert_eq!(calls.first(), Some(&(0, 10000)));
+  assert_eq!(calls.last(), Some(&(10000, 10000)));
+
+  let e = SndFileError::Unseekable { len_hint: Some(42) };
+  assert_eq!(e.len_hint(), Some(42));
+  assert_eq!(e.kind(), ErrorKind::Unsupported);
+  assert_eq!(SndFileError::Cancelled.len_hint(), None);
+}
+
+#[test]
+fn transcode_pipelined_matches_source() {
+  let tmp_dir = TempDir::new().unwrap();
+  let src = tmp_dir.as_ref().join("pipelined.wav");
+  let data: Vec<i16> = (0..50000).map(|x| (x * 7) as i16).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(
+      WriteOptions::new(
+        MajorFormat::WAV,
+        SubtypeFormat::PCM_16,
+        Endian::File,
+        44100,
+        2,
+      )
+      .with_tag(TagType::Title, "Pipelined"),
+    )
+    .from_path(&src)
+    .unwrap();
+    snd.write_from_slice(&data).unwrap();
+  }
+  let dst = tmp_dir.as_ref().join("pipelined.flac");
+  let options = WriteOptions::new(
+    MajorFormat::FLAC,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    44100,
+    2,
+  );
+  assert_eq!(
+    transcode_pipelined(&src, &dst, options, false).unwrap(),
+    25000
+  );
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&dst)
+    .unwrap();
+  assert_eq!(snd.get_tag(TagType::Title).as_deref(), Some("Pipelined"));
+  let buf: Vec<i16> = snd.read_all_to_vec().unwrap();
+  assert_eq!(buf, data);
+
+  let options = WriteOptions::new(
+    MajorFormat::WAV,
+    SubtypeFormat::PCM_16,
+    Endian::File,
+    44100,
+    1,
+  );
+  assert!(transcode_pipelined(&src, tmp_dir.as_ref().join("mono.wav"), options, false).is_err());
+}
+
+#[test]
+fn batch_retag() {
+  let tmp_dir = TempDir::new().unwrap();
+  let paths: Vec<_> = ["a.wav", "b.wav", "missing.wav"]
+    .iter()
+    .map(|x| tmp_dir.as_ref().join(x))
+    .collect();
+  for (i, path) in paths[..2].iter().enumerate() {
+    let mut options = WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      1,
+    );
+    if i == 0 {
+      options = options.with_tag(TagType::Title, "Original");
+    }
+    let mut snd = OpenOptions::WriteOnly(options).from_path(path).unwrap();
+    snd.write_from_slice(&[0i16; 100]).unwrap();
+  }
+
+  let mut tags = TagSet::new();
+  tags.insert(TagType::Title, "New".to_string());
+  tags.insert(TagType::Artist, "Someone".to_string());
+  let results = retag(&paths, &tags, TagConflict::SkipExisting);
+  assert_eq!(results.len(), 3);
+  assert_eq!(results[0].0, paths[0]);
+  assert_eq!(*results[0].1.as_ref().unwrap(), 1);
+  assert_eq!(*results[1].1.as_ref().unwrap(), 2);
+  assert!(results[2].1.is_err());
+
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&paths[0])
+    .unwrap();
+  assert_eq!(snd.get_tag(TagType::Title).as_deref(), Some("Original"));
+  assert_eq!(snd.get_tag(TagType::Artist).as_deref(), Some("Someone"));
+  drop(snd);
+
+  let results = retag(&paths[..1], &tags, TagConflict::Overwrite);
+  assert_eq!(*results[0].1.as_ref().unwrap(), 2);
+  let snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&paths[0])
+    .unwrap();
+  assert_eq!(snd.get_tag(TagType::Title).as_deref(), Some("New"));
+}
+
+#[test]
+fn scan_audio_files() {
+  let tmp_dir = TempDir::new().unwrap();
+  let root = tmp_dir.as_ref();
+  std::fs::create_dir_all(root.join("b/c")).unwrap();
+  let options = || {
+    WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      1,
+    )
+  };
+  for path in ["a.wav", "b/no_extension", "b/c/d.wav"].iter() {
+    let mut snd = OpenOptions::WriteOnly(options())
+      .from_path(root.join(path))
+      .unwrap();
+    snd.write_from_slice(&[0i16; 80]).unwrap();
+  }
+  std::fs::write(root.join("notes.txt"), "not audio").unwrap();
+  std::fs::write(root.join("b/broken.wav"), "not audio either").unwrap();
+
+  let found: Vec<_> = scan(root, ScanOptions::new()).collect();
+  assert_eq!(found.len(), 4);
+  let ok: Vec<_> = found.iter().filter_map(|x| x.as_ref().ok()).collect();
+  assert_eq!(
+    ok.iter().map(|x| x.0.clone()).collect::<Vec<_>>(),
+    vec![
+      root.join("a.wav"),
+      root.join("b/no_extension"),
+      root.join("b/c/d.wav")
+    ]
+  );
+  assert_eq!(ok[0].1.frames, Some(80));
+  let errors: Vec<_> = found.iter().filter_map(|x| x.as_ref().err()).collect();
+  assert_eq!(errors.len(), 1);
+  assert_eq!(errors[0].path, root.join("b/broken.wav"));
+
+  let found: Vec<_> = scan(root, ScanOptions::new().with_max_depth(0)).collect();
+  assert_eq!(found.len(), 1);
+  let found: Vec<_> = scan(root, ScanOptions::new().with_magic_bytes(false))
+    .filter_map(|x| x.ok())
+    .collect();
+  assert_eq!(found.len(), 2);
+  let missing = scan(root.join("missing"), ScanOptions::new())
+    .next()
+    .unwrap()
+    .unwrap_err();
+  assert_eq!(missing.path, root.join("missing"));
+  assert!(matches!(missing.error, SndFileError::IOError(_)));
+}
+
+#[test]
+fn typed_commands() {
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("commands.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      1,
+    ))
+    .from_path(&path)
+    .unwrap();
+    snd.write_from_slice(&[0i16, 16384, -32768, 100]).unwrap();
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+
+  assert_eq!(
+    snd.command(Command::GetNormFloat).unwrap(),
+    CommandOutput::Bool(true)
+  );
+  assert_eq!(
+    snd.command(Command::SetNormFloat(false)).unwrap(),
+    CommandOutput::Bool(true)
+  );
+  assert_eq!(
+    snd.command(Command::GetNormFloat).unwrap(),
+    CommandOutput::Bool(false)
+  );
+  assert_eq!(
+    unsafe { snd.command_raw(sndfile_sys::SFC_GET_NORM_FLOAT, None, 0) },
+    sndfile_sys::SF_FALSE
+  );
+
+  assert_eq!(
+    snd.command(Command::CalcSignalMax).unwrap(),
+    CommandOutput::Double(32768.0)
+  );
+  assert_eq!(
+    snd.command(Command::CalcNormSignalMax).unwrap(),
+    CommandOutput::Double(1.0)
+  );
+
+  match snd.command(Command::GetLogInfo).unwrap() {
+    CommandOutput::Text(x) => assert!(x.contains("RIFF")),
+    r => panic!("unexpected output {:?}", r),
+  }
+
+  match snd.command(Command::SetCompressionLevel(0.5)) {
+    Err(SndFileError::InvalidParameter(msg)) => assert!(msg.contains("SetCompressionLevel")),
+    r => panic!("unexpected result {:?}", r),
+  }
+}
+
+#[test]
+fn ogg_page_latency_command() {
+  // libsndfile honours the command for Opus only, from version 1.1.
+  let opus = SubtypeFormat::Unknown(0x64);
+  if !supports(MajorFormat::OGG, opus) || library_version().unwrap().contains("-1.0.") {
+    return;
+  }
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("latency.opus");
+  let data: Vec<f32> = (0..48000).map(|i| (i as f32 * 0.01).sin() * 0.5).collect();
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::OGG,
+      opus,
+      Endian::File,
+      48000,
+      1,
+    ))
+    .from_path(&path)
+    .unwrap();
+    assert_eq!(
+      snd.command(Command::SetOggPageLatencyMs(20.0)).unwrap(),
+      CommandOutput::None
+    );
+    snd.write_from_slice(&data).unwrap();
+  }
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  assert_eq!(snd.len().unwrap(), 48000);
+}
+
+#[cfg(feature = "audio_features")]
+#[test]
+fn audio_buf_round_trip() {
+  use audio_core::{Buf, BufMut, Channel, ChannelMut, ExactSizeBuf};
+
+  // A sequential buffer, one `Vec` per channel.
+  struct Seq(Vec<Vec<i16>>);
+  struct Ch<'a>(&'a [i16]);
+  struct ChMut<'a>(&'a mut [i16]);
+
+  impl Channel for Ch<'_> {
+    type Sample = i16;
+    type Channel<'this>
+      = Ch<'this>
+    where
+      Self: 'this;
+    type Iter<'this>
+      = std::iter::Copied<std::slice::Iter<'this, i16>>
+    where
+      Self: 'this;
+    fn as_channel(&self) -> Ch<'_> {
+      Ch(self.0)
+    }
+    fn len(&self) -> usize {
+      self.0.len()
+    }
+    fn get(&self, n: usize) -> Option<i16> {
+      self.0.get(n).copied()
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+      self.0.iter().copied()
+    }
+    fn try_as_linear(&self) -> Option<&[i16]> {
+      Some(self.0)
+    }
+    fn skip(self, n: usize) -> Self {
+      Ch(&self.0[n.min(self.0.len())..])
+    }
+    fn tail(self, n: usize) -> Self {
+      Ch(&self.0[self.0.len().saturating_sub(n)..])
+    }
+    fn limit(self, limit: usize) -> Self {
+      Ch(&self.0[..limit.min(self.0.len())])
+    }
+  }
+
+  impl Channel for ChMut<'_> {
+    type Sample = i16;
+    type Channel<'this>
+      = Ch<'this>
+    where
+      Self: 'this;
+    type Iter<'this>
+      = std::iter::Copied<std::slice::Iter<'this, i16>>
+    where
+      Self: 'this;
+    fn as_channel(&self) -> Ch<'_> {
+      Ch(self.0)
+    }
+    fn len(&self) -> usize {
+      self.0.len()
+    }
+    fn get(&self, n: usize) -> Option<i16> {
+      self.0.get(n).copied()
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+      self.0.iter().copied()
+    }
+    fn try_as_linear(&self) -> Option<&[i16]> {
+      Some(self.0)
+    }
+    fn skip(self, n: usize) -> Self {
+      let n = n.min(self.0.len());
+      ChMut(&mut self.0[n..])
+    }
+    fn tail(self, n: usize) -> Self {
+      let n = self.0.len().saturating_sub(n);
+      ChMut(&mut self.0[n..])
+    }
+    fn limit(self, limit: usize) -> Self {
+      let n = limit.min(self.0.len());
+      ChMut(&mut self.0[..n])
+    }
+  }
+
+  impl ChannelMut for ChMut<'_> {
+    type ChannelMut<'this>
+      = ChMut<'this>
+    where
+      Self: 'this;
+    type IterMut<'this>
+      = std::slice::IterMut<'this, i16>
+    where
+      Self: 'this;
+    fn as_channel_mut(&mut self) -> ChMut<'_> {
+      ChMut(self.0)
+    }
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+      self.0.iter_mut()
+    }
+    fn get_mut(&mut self, n: usize) -> Option<&mut i16> {
+      self.0.get_mut(n)
+    }
+    fn try_as_linear_mut(&mut self) -> Option<&mut [i16]> {
+      Some(self.0)
+    }
+  }
+
+  impl Buf for Seq {
+    type Sample = i16;
+    type Channel<'this> = Ch<'this>;
+    type IterChannels<'this> = Box<dyn Iterator<Item = Ch<'this>> + 'this>;
+    fn frames_hint(&self) -> Option<usize> {
+      Some(self.frames())
+    }
+    fn channels(&self) -> usize {
+      self.0.len()
+    }
+    fn get_channel(&self, channel: usize) -> Option<Ch<'_>> {
+      self.0.get(channel).map(|x| Ch(x))
+    }
+    fn iter_channels(&self) -> Self::IterChannels<'_> {
+      Box::new(self.0.iter().map(|x| Ch(x)))
+    }
+  }
+
+  impl BufMut for Seq {
+    type ChannelMut<'this> = ChMut<'this>;
+    type IterChannelsMut<'this> = Box<dyn Iterator<Item = ChMut<'this>> + 'this>;
+    fn iter_channels_mut(&mut self) -> Self::IterChannelsMut<'_> {
+      Box::new(self.0.iter_mut().map(|x| ChMut(x)))
+    }
+    fn get_channel_mut(&mut self, channel: usize) -> Option<ChMut<'_>> {
+      self.0.get_mut(channel).map(|x| ChMut(x))
+    }
+    fn copy_channel(&mut self, from: usize, to: usize) {
+      let x = self.0[from].clone();
+      self.0[to].copy_from_slice(&x);
+    }
+  }
+
+  impl ExactSizeBuf for Seq {
+    fn frames(&self) -> usize {
+      self.0.first().map_or(0, |x| x.len())
+    }
+  }
+
+  // Two full blocks and a partial last block.
+  let frames = 2 * 4096 + 100;
+  let src = Seq(vec![
+    (0..frames).map(|x| x as i16).collect(),
+    (0..frames).map(|x| -(x as i16)).collect(),
+  ]);
+  let tmp_dir = TempDir::new().unwrap();
+  let path = tmp_dir.as_ref().join("audio_buf.wav");
+  {
+    let mut snd = OpenOptions::WriteOnly(WriteOptions::new(
+      MajorFormat::WAV,
+      SubtypeFormat::PCM_16,
+      Endian::File,
+      8000,
+      2,
+    ))
+    .from_path(&path)
+    .unwrap();
+    assert_eq!(snd.write_from_buf(&src).unwrap(), frames);
+    assert!(matches!(
+      snd.write_from_buf(&Seq(vec![vec![0; 10]])),
+      Err(SndFileError::InvalidParameter(_))
+    ));
+  }
+
+  let mut snd = OpenOptions::ReadOnly(ReadOptions::Auto)
+    .from_path(&path)
+    .unwrap();
+  let interleaved: Vec<i16> = snd.read_all_to_vec().unwrap();
+  assert_eq!(&interleaved[..6], &[0, 0, 1, -1, 2, -2]);
+  snd.seek(SeekFrom::Start(0)).unwrap();
+  let mut dst = Seq(vec![vec![7; frames + 10]; 2]);
+  assert_eq!(snd.read_to_buf(&mut dst).unwrap(), frames);
+  for c in 0..2 {
+    assert_eq!(&dst.0[c][..frames], &src.0[c][..]);
+    assert!(dst.0[c][frames..].iter().all(|&x| x == 7));
+  }
+}
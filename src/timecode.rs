@@ -0,0 +1,229 @@
+use super::edit::duration_to_frames;
+use super::{BroadcastInfo, SndFileError};
+use std::time::Duration;
+
+fn check_samplerate(samplerate: usize) -> Result<(), SndFileError> {
+  if samplerate == 0 {
+    return Err(SndFileError::InvalidParameter(
+      "Got invalid samplerate, expect a positive number.".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+/// Frame rate of SMPTE timecodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameRate {
+  nominal: u32,
+  ntsc: bool,
+  drop_frame: bool,
+}
+
+impl FrameRate {
+  pub const FPS_23_976: FrameRate = FrameRate {
+    nominal: 24,
+    ntsc: true,
+    drop_frame: false,
+  };
+  pub const FPS_24: FrameRate = FrameRate {
+    nominal: 24,
+    ntsc: false,
+    drop_frame: false,
+  };
+  pub const FPS_25: FrameRate = FrameRate {
+    nominal: 25,
+    ntsc: false,
+    drop_frame: false,
+  };
+  pub const FPS_29_97_DF: FrameRate = FrameRate {
+    nominal: 30,
+    ntsc: true,
+    drop_frame: true,
+  };
+  pub const FPS_29_97_NDF: FrameRate = FrameRate {
+    nominal: 30,
+    ntsc: true,
+    drop_frame: false,
+  };
+  pub const FPS_30: FrameRate = FrameRate {
+    nominal: 30,
+    ntsc: false,
+    drop_frame: false,
+  };
+  pub const FPS_50: FrameRate = FrameRate {
+    nominal: 50,
+    ntsc: false,
+    drop_frame: false,
+  };
+  pub const FPS_59_94_DF: FrameRate = FrameRate {
+    nominal: 60,
+    ntsc: true,
+    drop_frame: true,
+  };
+  pub const FPS_59_94_NDF: FrameRate = FrameRate {
+    nominal: 60,
+    ntsc: true,
+    drop_frame: false,
+  };
+  pub const FPS_60: FrameRate = FrameRate {
+    nominal: 60,
+    ntsc: false,
+    drop_frame: false,
+  };
+
+  /// Create new FrameRate.
+  ///
+  /// * `nominal`: Frames per timecode second, e.g. `30` for 29.97 fps  
+  /// * `ntsc`: Run at `nominal * 1000 / 1001` frames per second  
+  /// * `drop_frame`: Skip timecode labels to stay in sync with wall clock, only valid for NTSC
+  ///   rates with a nominal rate of 30 or 60  
+  pub fn new(nominal: u32, ntsc: bool, drop_frame: bool) -> Result<Self, SndFileError> {
+    if nominal == 0 {
+      return Err(SndFileError::InvalidParameter(
+        "Got invalid nominal frame rate, expect a positive number.".to_string(),
+      ));
+    }
+    if drop_frame && !(ntsc && (nominal == 30 || nominal == 60)) {
+      return Err(SndFileError::InvalidParameter(format!(
+        "Drop frame is not defined for nominal rate {} (ntsc = {}).",
+        nominal, ntsc
+      )));
+    }
+    Ok(FrameRate {
+      nominal,
+      ntsc,
+      drop_frame,
+    })
+  }
+
+  /// Frame rate as `(numerator, denominator)` frames per second.
+  fn ratio(&self) -> (u64, u64) {
+    if self.ntsc {
+      (self.nominal as u64 * 1000, 1001)
+    } else {
+      (self.nominal as u64, 1)
+    }
+  }
+
+  /// Timecode labels skipped at the start of each minute in drop frame mode.
+  fn dropped_per_minute(&self) -> u64 {
+    if self.drop_frame {
+      self.nominal as u64 / 15
+    } else {
+      0
+    }
+  }
+
+  /// Format the frame containing sample `samples` as `HH:MM:SS:FF`, or `HH:MM:SS;FF` in drop frame
+  /// mode. Hours wrap around at 24.
+  ///
+  /// Returns `InvalidParameter` if `samplerate` is 0.
+  pub fn samples_to_timecode(
+    &self,
+    samples: u64,
+    samplerate: usize,
+  ) -> Result<String, SndFileError> {
+    check_samplerate(samplerate)?;
+    let (num, den) = self.ratio();
+    let frame = (samples as u128 * num as u128 / (samplerate as u128 * den as u128)) as u64;
+    let fps = self.nominal as u64;
+    let drop = self.dropped_per_minute();
+    let mut label = frame;
+    if drop > 0 {
+      let frames_per_minute = fps * 60 - drop;
+      let frames_per_10_minutes = frames_per_minute * 10 + drop;
+      let (tens, rem) = (frame / frames_per_10_minutes, frame % frames_per_10_minutes);
+      label += drop * 9 * tens;
+      if rem > drop {
+        label += drop * ((rem - drop) / frames_per_minute);
+      }
+    }
+    Ok(format!(
+      "{:02}:{:02}:{:02}{}{:02}",
+      label / (fps * 3600) % 24,
+      label / (fps * 60) % 60,
+      label / fps % 60,
+      if self.drop_frame { ';' } else { ':' },
+      label % fps
+    ))
+  }
+
+  /// Parse a `HH:MM:SS:FF` or `HH:MM:SS;FF` timecode, returns the first sample of the frame.
+  ///
+  /// Returns `InvalidParameter` if `samplerate` is 0.
+  pub fn timecode_to_samples(
+    &self,
+    timecode: &str,
+    samplerate: usize,
+  ) -> Result<u64, SndFileError> {
+    check_samplerate(samplerate)?;
+    let invalid = || {
+      SndFileError::InvalidParameter(format!(
+        "Got invalid timecode `{}`, expect `HH:MM:SS:FF` at {} frames per second.",
+        timecode, self.nominal
+      ))
+    };
+    let fields: Vec<u64> = timecode
+      .split(&[':', ';', '.', ','][..])
+      .map(|x| x.parse().map_err(|_| invalid()))
+      .collect::<Result<_, _>>()?;
+    if fields.len() != 4 {
+      return Err(invalid());
+    }
+    let (hh, mm, ss, ff) = (fields[0], fields[1], fields[2], fields[3]);
+    let fps = self.nominal as u64;
+    let drop = self.dropped_per_minute();
+    if mm >= 60 || ss >= 60 || ff >= fps || (ss == 0 && ff < drop && mm % 10 != 0) {
+      return Err(invalid());
+    }
+    let minutes = hh * 60 + mm;
+    let frame = (minutes * 60 + ss) * fps + ff - drop * (minutes - minutes / 10);
+    let (num, den) = self.ratio();
+    // Round up so that the sample maps back to the same frame.
+    let x = frame as u128 * samplerate as u128 * den as u128;
+    Ok(x.div_ceil(num as u128) as u64)
+  }
+}
+
+impl BroadcastInfo {
+  /// Get `time_reference` as time since midnight.
+  ///
+  /// Returns `InvalidParameter` if `samplerate` is 0, e.g. read from a malformed header.
+  pub fn time_reference_as_duration(&self, samplerate: usize) -> Result<Duration, SndFileError> {
+    check_samplerate(samplerate)?;
+    let sr = samplerate as u64;
+    Ok(Duration::new(
+      self.time_reference / sr,
+      ((self.time_reference % sr) * 1_000_000_000 / sr) as u32,
+    ))
+  }
+
+  /// Set `time_reference` from time since midnight.
+  ///
+  /// Returns `InvalidParameter` if `samplerate` is 0.
+  pub fn set_time_reference_from_duration(
+    &mut self,
+    d: Duration,
+    samplerate: usize,
+  ) -> Result<(), SndFileError> {
+    check_samplerate(samplerate)?;
+    self.time_reference = duration_to_frames(d, samplerate);
+    Ok(())
+  }
+
+  /// Get `time_reference` as SMPTE timecode, see `FrameRate::samples_to_timecode`.
+  pub fn timecode(&self, samplerate: usize, rate: FrameRate) -> Result<String, SndFileError> {
+    rate.samples_to_timecode(self.time_reference, samplerate)
+  }
+
+  /// Set `time_reference` from SMPTE timecode, see `FrameRate::timecode_to_samples`.
+  pub fn set_timecode(
+    &mut self,
+    timecode: &str,
+    samplerate: usize,
+    rate: FrameRate,
+  ) -> Result<(), SndFileError> {
+    self.time_reference = rate.timecode_to_samples(timecode, samplerate)?;
+    Ok(())
+  }
+}
@@ -0,0 +1,79 @@
+//! Instrumentation of libsndfile operations, emitting `tracing` events with feature
+//! `tracing_features` and compiled to nothing otherwise.
+
+#[cfg(not(feature = "system_linking"))]
+use super::sys as sndfile_sys;
+#[cfg(feature = "tracing_features")]
+use std::time::Instant;
+
+/// Reads and writes of at least this many frames are traced.
+#[cfg(feature = "tracing_features")]
+const LARGE_IO_FRAMES: usize = 1 << 16;
+
+/// Emits a debug event with target `sndfile` when dropped, holding the operation name, the
+/// handle address identifying the file, the frame count if any, and the elapsed time.
+pub(crate) struct OpTimer {
+  #[cfg(feature = "tracing_features")]
+  inner: Option<(&'static str, usize, Option<u64>, Instant)>,
+}
+
+impl OpTimer {
+  /// Time the operation `op` on the file `ptr`.
+  pub(crate) fn new(op: &'static str, ptr: *mut sndfile_sys::SNDFILE) -> OpTimer {
+    #[cfg(feature = "tracing_features")]
+    {
+      OpTimer {
+        inner: Some((op, ptr as usize, None, Instant::now())),
+      }
+    }
+    #[cfg(not(feature = "tracing_features"))]
+    {
+      let _ = (op, ptr);
+      OpTimer {}
+    }
+  }
+
+  /// Time the read or write `op` of `frames` frames, only if it is large.
+  pub(crate) fn io(op: &'static str, ptr: *mut sndfile_sys::SNDFILE, frames: usize) -> OpTimer {
+    #[cfg(feature = "tracing_features")]
+    {
+      OpTimer {
+        inner: if frames >= LARGE_IO_FRAMES {
+          Some((op, ptr as usize, Some(frames as u64), Instant::now()))
+        } else {
+          None
+        },
+      }
+    }
+    #[cfg(not(feature = "tracing_features"))]
+    {
+      let _ = (op, ptr, frames);
+      OpTimer {}
+    }
+  }
+
+  /// Set the file once known, e.g. after opening it.
+  pub(crate) fn set_file(&mut self, ptr: *mut sndfile_sys::SNDFILE) {
+    #[cfg(feature = "tracing_features")]
+    if let Some(inner) = self.inner.as_mut() {
+      inner.1 = ptr as usize;
+    }
+    #[cfg(not(feature = "tracing_features"))]
+    let _ = ptr;
+  }
+}
+
+#[cfg(feature = "tracing_features")]
+impl Drop for OpTimer {
+  fn drop(&mut self) {
+    if let Some((op, file, frames, start)) = self.inner.take() {
+      tracing::debug!(
+        target: "sndfile",
+        op,
+        file = %format_args!("{:#x}", file),
+        frames,
+        elapsed_us = start.elapsed().as_micros() as u64,
+      );
+    }
+  }
+}
@@ -0,0 +1,94 @@
+use super::edit::{duration_to_frames, BLOCK_FRAMES};
+use super::{SndFile, SndFileError, SndFileIO};
+use std::io::SeekFrom;
+use std::time::Duration;
+
+/// Options for trimming leading and trailing silence, see `SndFile::read_all_to_vec_trimmed`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrimOptions {
+  threshold_db: f64,
+  min_duration: Duration,
+}
+
+impl TrimOptions {
+  /// Create new TrimOptions.
+  ///
+  /// * `threshold_db`: A frame is silent if all of its samples are below this level in dBFS, e.g., `-60.0`  
+  /// * `min_duration`: Leading or trailing silence shorter than this is kept  
+  pub fn new(threshold_db: f64, min_duration: Duration) -> Self {
+    TrimOptions {
+      threshold_db,
+      min_duration,
+    }
+  }
+}
+
+impl SndFile {
+  /// Find the range of frames to keep after trimming silence, returns `(start, end)`.
+  fn find_trim_range(&mut self, options: &TrimOptions) -> Result<(u64, u64), SndFileError> {
+    let len = self.len()?;
+    let threshold = 10.0f64.powf(options.threshold_db / 20.0);
+    let channels = self.get_channels();
+    let mut buf = vec![0.0f64; BLOCK_FRAMES * channels];
+    let mut first = None;
+    let mut last = 0;
+    let mut pos = 0;
+    self.seek(SeekFrom::Start(0))?;
+    loop {
+      let n = self.read_to_slice(&mut buf)?;
+      if n == 0 {
+        break;
+      }
+      for (i, frame) in buf[..n * channels].chunks(channels).enumerate() {
+        if frame.iter().any(|x| x.abs() >= threshold) {
+          let i = pos + i as u64;
+          first.get_or_insert(i);
+          last = i + 1;
+        }
+      }
+      pos += n as u64;
+    }
+    // The header may undercount frames.
+    let len = len.max(pos);
+
+    let min_frames = duration_to_frames(options.min_duration, self.get_samplerate());
+    match first {
+      None if len >= min_frames => Ok((0, 0)),
+      None => Ok((0, len)),
+      Some(first) => {
+        let start = if first >= min_frames { first } else { 0 };
+        let end = if len.saturating_sub(last) >= min_frames {
+          last
+        } else {
+          len
+        };
+        Ok((start, end))
+      }
+    }
+  }
+
+  /// Read all frames with leading and trailing silence trimmed, returns the frames and the offset
+  /// of the first returned frame in the file if success.
+  ///
+  /// Silence is detected on normalized samples, so `Command::SetNormDouble(false)` makes the
+  /// threshold meaningless for integer encodings.
+  ///
+  /// The I/O cursor is left unchanged.
+  pub fn read_all_to_vec_trimmed<T>(
+    &mut self,
+    options: &TrimOptions,
+  ) -> Result<(Vec<T>, u64), SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+  {
+    self.preserving_cursor(|snd| {
+      let (start, end) = snd.find_trim_range(options)?;
+      let mut buf = vec![T::default(); (end - start) as usize * snd.get_channels()];
+      snd.seek(SeekFrom::Start(start))?;
+      let n_frames = snd.read_to_slice(&mut buf)?;
+      buf.truncate(n_frames * snd.get_channels());
+      Ok((buf, start))
+    })
+  }
+}
@@ -0,0 +1,143 @@
+use super::{SndFile, SndFileError, SndFileIO};
+use std::io::SeekFrom;
+use std::marker::PhantomData;
+
+/// A `SndFile` with `C` channels known at compile time, reading and writing frames of `T` as
+/// `[T; C]`, see `SndFile::with_channels`.
+#[derive(Debug)]
+pub struct TypedSndFile<T, const C: usize> {
+  inner: SndFile,
+  _marker: PhantomData<T>,
+}
+
+fn flatten<T, const C: usize>(frames: &[[T; C]]) -> &[T] {
+  // `[T; C]` has the same layout as `C` consecutive `T`.
+  unsafe { std::slice::from_raw_parts(frames.as_ptr() as *const T, frames.len() * C) }
+}
+
+fn flatten_mut<T, const C: usize>(frames: &mut [[T; C]]) -> &mut [T] {
+  unsafe { std::slice::from_raw_parts_mut(frames.as_mut_ptr() as *mut T, frames.len() * C) }
+}
+
+impl SndFile {
+  /// Returns `SndFileError::InvalidParameter` unless the file has exactly `C` channels.
+  pub(crate) fn check_channels<const C: usize>(&self) -> Result<(), SndFileError> {
+    if self.get_channels() != C {
+      return Err(SndFileError::InvalidParameter(format!(
+        "Got {} channels, expect {} channels.",
+        self.get_channels(),
+        C
+      )));
+    }
+    Ok(())
+  }
+
+  /// Wrap this file into a `TypedSndFile`, returns `SndFileError::InvalidParameter` if the file
+  /// does not have exactly `C` channels.
+  ///
+  /// The sample type can be inferred, e.g. `snd.with_channels::<_, 2>()`.
+  pub fn with_channels<T, const C: usize>(self) -> Result<TypedSndFile<T, C>, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+  {
+    self.check_channels::<C>()?;
+    Ok(TypedSndFile {
+      inner: self,
+      _marker: PhantomData,
+    })
+  }
+
+  /// Read frames as `[T; C]` from current I/O cursor, returns the number of frames read if
+  /// success, e.g. `[[f32; 2]]` for stereo.
+  ///
+  /// Returns `SndFileError::InvalidParameter` if the file does not have exactly `C` channels, use
+  /// `with_channels` to check it once for many reads.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn read_frames<T, const C: usize>(
+    &mut self,
+    dst: &mut [[T; C]],
+  ) -> Result<usize, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+  {
+    self.check_channels::<C>()?;
+    self.read_to_slice(flatten_mut(dst))
+  }
+
+  /// Write frames of `[T; C]`, returns the number of frames written if success.
+  ///
+  /// Returns `SndFileError::InvalidParameter` if the file does not have exactly `C` channels.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn write_frames<T, const C: usize>(&mut self, src: &[[T; C]]) -> Result<usize, SndFileError>
+  where
+    T: 'static + Default + Copy,
+    SndFile: SndFileIO<T>,
+  {
+    self.check_channels::<C>()?;
+    self.write_from_slice(flatten(src))
+  }
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl<T, const C: usize> TypedSndFile<T, C>
+where
+  T: 'static + Default + Copy,
+  SndFile: SndFileIO<T>,
+{
+  /// Borrow the underlying `SndFile`, e.g. to get its format or tags.
+  pub fn get_ref(&self) -> &SndFile {
+    &self.inner
+  }
+
+  /// Mutably borrow the underlying `SndFile`.
+  pub fn get_mut(&mut self) -> &mut SndFile {
+    &mut self.inner
+  }
+
+  /// Unwrap the underlying `SndFile`.
+  pub fn into_inner(self) -> SndFile {
+    self.inner
+  }
+
+  /// See `SndFile::seek`.
+  pub fn seek(&mut self, pos: SeekFrom) -> Result<u64, SndFileError> {
+    self.inner.seek(pos)
+  }
+
+  /// See `SndFile::len`.
+  pub fn len(&mut self) -> Result<u64, SndFileError> {
+    self.inner.len()
+  }
+
+  /// Read frames from current I/O cursor, returns the number of frames read if success.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn read_frames(&mut self, dst: &mut [[T; C]]) -> Result<usize, SndFileError> {
+    self.inner.read_to_slice(flatten_mut(dst))
+  }
+
+  /// Write frames, returns the number of frames written if success.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn write_frames(&mut self, src: &[[T; C]]) -> Result<usize, SndFileError> {
+    self.inner.write_from_slice(flatten(src))
+  }
+
+  /// Read all frames into a `Vec<_>` if success.
+  ///
+  /// The I/O cursor is left unchanged.
+  pub fn read_all_frames(&mut self) -> Result<Vec<[T; C]>, SndFileError> {
+    self.inner.preserving_cursor(|snd| {
+      let n = snd.check_read_all_len::<T>(super::default_read_limit())? / C;
+      snd.seek(SeekFrom::Start(0))?;
+      let mut buf = vec![[T::default(); C]; n];
+      let n = snd.read_to_slice(flatten_mut(&mut buf))?;
+      buf.truncate(n);
+      Ok(buf)
+    })
+  }
+}
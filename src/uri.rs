@@ -0,0 +1,116 @@
+use super::{OpenOptions, ReadOptions, SndFile, SndFileError};
+use std::io::Cursor;
+
+/// Open an audio file for reading from a URI, picking the backend from its scheme.
+///
+/// * `file:///path/to/a.wav` or a plain path: the local file, see `OpenOptions::from_path`.
+/// * `data:audio/wav;base64,...`: the audio file embedded in the URI, decoded to memory.
+/// * `http://` and `https://`: a remote file read with range requests, needs `http_features`.
+/// * Other schemes like `s3://bucket/a.flac`, `gs://` or `az://`: an object read with
+///   `object_store::parse_url`, needs `object_store_features` and the matching features of the
+///   `object_store` crate.
+///
+/// Returns `SndFileError::InvalidParameter` for unsupported schemes or malformed URIs.
+pub fn open_uri(uri: &str, options: ReadOptions) -> Result<SndFile, SndFileError> {
+  let open_options = OpenOptions::ReadOnly(options);
+  let scheme = match uri.split_once(':') {
+    // A single letter is the drive of a Windows path.
+    Some((x, _)) if x.len() > 1 && is_scheme(x) => x.to_ascii_lowercase(),
+    _ => return open_options.from_path(uri),
+  };
+  match scheme.as_str() {
+    "file" => {
+      let path = uri["file:".len()..].trim_start_matches("//");
+      let path = percent_decode(path).and_then(|x| String::from_utf8(x).ok());
+      open_options.from_path(path.ok_or_else(|| invalid_uri(uri))?)
+    }
+    "data" => open_options.from_virtual_io(Cursor::new(decode_data_uri(uri)?)),
+    #[cfg(feature = "http_features")]
+    "http" | "https" => open_options.from_url(uri),
+    #[cfg(feature = "object_store_features")]
+    _ => {
+      let url = url::Url::parse(uri).map_err(|e| {
+        SndFileError::InvalidParameter(format!("Got URI `{}`, expect a valid URL: {}.", uri, e))
+      })?;
+      let (store, path) = object_store::parse_url(&url).map_err(|e| {
+        SndFileError::InvalidParameter(format!(
+          "Got URI `{}`, expect a URL of an enabled object store: {}.",
+          uri, e
+        ))
+      })?;
+      open_options.from_object_store(store.into(), &path)
+    }
+    #[cfg(not(feature = "object_store_features"))]
+    _ => Err(SndFileError::InvalidParameter(format!(
+      "Got URI scheme `{}`, expect a scheme supported by the enabled features.",
+      scheme
+    ))),
+  }
+}
+
+fn invalid_uri(uri: &str) -> SndFileError {
+  SndFileError::InvalidParameter(format!("Got malformed URI `{}`.", uri))
+}
+
+/// `scheme = ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )` of RFC 3986.
+fn is_scheme(s: &str) -> bool {
+  s.starts_with(|c: char| c.is_ascii_alphabetic())
+    && s
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+}
+
+/// Decode `%XX` escapes of `s`.
+fn percent_decode(s: &str) -> Option<Vec<u8>> {
+  let bytes = s.as_bytes();
+  let mut out = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    if bytes[i] == b'%' {
+      let hex = s.get(i + 1..i + 3)?;
+      out.push(u8::from_str_radix(hex, 16).ok()?);
+      i += 3;
+    } else {
+      out.push(bytes[i]);
+      i += 1;
+    }
+  }
+  Some(out)
+}
+
+/// Decode the payload of a `data:[<media type>][;base64],<data>` URI.
+fn decode_data_uri(uri: &str) -> Result<Vec<u8>, SndFileError> {
+  let (header, data) = uri["data:".len()..]
+    .split_once(',')
+    .ok_or_else(|| invalid_uri(uri))?;
+  if header.ends_with(";base64") {
+    decode_base64(data)
+  } else {
+    percent_decode(data)
+  }
+  .ok_or_else(|| invalid_uri(uri))
+}
+
+/// Decode standard base64, padding is optional.
+fn decode_base64(s: &str) -> Option<Vec<u8>> {
+  let mut out = Vec::with_capacity(s.len() / 4 * 3);
+  let mut acc = 0u32;
+  let mut n_bits = 0;
+  for c in s.bytes().filter(|c| *c != b'=') {
+    let x = match c {
+      b'A'..=b'Z' => c - b'A',
+      b'a'..=b'z' => c - b'a' + 26,
+      b'0'..=b'9' => c - b'0' + 52,
+      b'+' => 62,
+      b'/' => 63,
+      _ => return None,
+    };
+    acc = ((acc << 6) | x as u32) & 0xffff;
+    n_bits += 6;
+    if n_bits >= 8 {
+      n_bits -= 8;
+      out.push((acc >> n_bits) as u8);
+    }
+  }
+  Some(out)
+}
@@ -0,0 +1,230 @@
+use super::edit::BLOCK_FRAMES;
+use super::ffi;
+#[cfg(not(feature = "system_linking"))]
+use super::sys as sndfile_sys;
+use super::{
+  sf_err_code_to_enum, MajorFormat, OpenOptions, ReadOptions, SndFile, SndFileError, SndFileIO,
+  SubtypeFormat, TagType,
+};
+use std::hash::Hasher;
+use std::io::SeekFrom;
+use std::path::Path;
+
+impl SndFile {
+  /// Hash all decoded frames with a new `H`, returns the hash if success.
+  ///
+  /// Samples are hashed as normalized `f64`, which represents all PCM and float encodings exactly,
+  /// so a lossless transcode of a file has the same hash as the original.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn hash_pcm<H: Hasher + Default>(&mut self) -> Result<u64, SndFileError> {
+    let mut hasher = H::default();
+    let mut buf = vec![0.0f64; BLOCK_FRAMES * self.get_channels()];
+    self.seek(SeekFrom::Start(0))?;
+    loop {
+      let n = self.read_to_slice(&mut buf)?;
+      if n == 0 {
+        break;
+      }
+      for x in buf[..n * self.get_channels()].iter() {
+        hasher.write_u64(x.to_bits());
+      }
+    }
+    Ok(hasher.finish())
+  }
+
+  /// Decode the whole file to check its integrity, e.g. for archive validation.
+  ///
+  /// Decoding errors are reported in the returned `Verification` instead of as `Err`, which is
+  /// only returned if the file can't be rewound.
+  ///
+  /// libsndfile clamps the declared length of some containers like WAV to the file size when
+  /// opening, so a truncated WAV file may decode fine with a matching length. Truncated compressed
+  /// files like FLAC are reported by an error or a length mismatch.
+  ///
+  /// This function may affect the I/O cursor.
+  pub fn verify(&mut self) -> Result<Verification, SndFileError> {
+    let declared_frames = self.len().ok();
+    let mut buf = vec![0.0f32; BLOCK_FRAMES * self.get_channels()];
+    let mut decoded_frames = 0u64;
+    let mut error = None;
+    self.seek(SeekFrom::Start(0))?;
+    loop {
+      match self.read_to_slice(&mut buf) {
+        Ok(0) => break,
+        Ok(n) => decoded_frames += n as u64,
+        Err(e) => {
+          error = Some(e);
+          break;
+        }
+      }
+    }
+    if error.is_none() {
+      // Some decoders stop with a short read and only set the error code.
+      let code = unsafe { ffi::sf_error(self.unsafe_fields.sndfile_ptr) };
+      if code != sndfile_sys::SF_ERR_NO_ERROR {
+        error = Some(sf_err_code_to_enum(code));
+      }
+    }
+    Ok(Verification {
+      decoded_frames,
+      declared_frames,
+      error,
+    })
+  }
+}
+
+/// Result of `SndFile::verify`.
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct Verification {
+  /// Number of frames decoded before the end of file or the first error.
+  pub decoded_frames: u64,
+  /// Number of frames declared by the header, `None` if the file is not seekable.
+  pub declared_frames: Option<u64>,
+  /// The first decoding error, which happened at frame `decoded_frames`.
+  pub error: Option<SndFileError>,
+}
+
+impl Verification {
+  /// Index of the first frame that failed to decode, `None` if decoding succeeded.
+  pub fn first_failed_frame(&self) -> Option<u64> {
+    self.error.as_ref().map(|_| self.decoded_frames)
+  }
+
+  /// Returns `true` if the number of decoded frames matches the header, or the header does not
+  /// declare a length.
+  pub fn length_matches(&self) -> bool {
+    self.declared_frames.unwrap_or(self.decoded_frames) == self.decoded_frames
+  }
+
+  /// Returns `true` if the whole file decoded without error and has the declared length.
+  pub fn is_ok(&self) -> bool {
+    self.error.is_none() && self.length_matches()
+  }
+}
+
+/// A difference in properties or tags found by `compare`, holding the values of both files.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataDifference {
+  Samplerate(usize, usize),
+  Channels(usize, usize),
+  Length(u64, u64),
+  MajorFormat(MajorFormat, MajorFormat),
+  SubtypeFormat(SubtypeFormat, SubtypeFormat),
+  Tag(TagType, Option<String>, Option<String>),
+}
+
+/// Result of `compare`.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+  /// Index of the first frame that differs, `None` if all frames are equal.
+  ///
+  /// If lengths differ but the common frames are equal, this is the length of the shorter file.
+  /// If channel counts differ, frames are not compared and this is `Some(0)`.
+  pub first_differing_frame: Option<u64>,
+  /// Maximum absolute difference of normalized samples over the common frames.
+  ///
+  /// If channel counts differ, this is `f64::INFINITY`.
+  pub max_sample_delta: f64,
+  /// Differences in properties and tags.
+  pub metadata_differences: Vec<MetadataDifference>,
+}
+
+impl Comparison {
+  /// Returns `true` if both files have equal frames, properties and tags.
+  pub fn is_identical(&self) -> bool {
+    self.first_differing_frame.is_none() && self.metadata_differences.is_empty()
+  }
+}
+
+/// Compare the frames, properties and tags of two audio files by streaming both of them.
+pub fn compare<P: AsRef<Path>, Q: AsRef<Path>>(a: P, b: Q) -> Result<Comparison, SndFileError> {
+  let mut a = OpenOptions::ReadOnly(ReadOptions::Auto).from_path(a)?;
+  let mut b = OpenOptions::ReadOnly(ReadOptions::Auto).from_path(b)?;
+  let (len_a, len_b) = (a.len()?, b.len()?);
+
+  let mut metadata_differences = Vec::new();
+  if a.get_samplerate() != b.get_samplerate() {
+    metadata_differences.push(MetadataDifference::Samplerate(
+      a.get_samplerate(),
+      b.get_samplerate(),
+    ));
+  }
+  if a.get_channels() != b.get_channels() {
+    metadata_differences.push(MetadataDifference::Channels(
+      a.get_channels(),
+      b.get_channels(),
+    ));
+  }
+  if len_a != len_b {
+    metadata_differences.push(MetadataDifference::Length(len_a, len_b));
+  }
+  if a.get_major_format() != b.get_major_format() {
+    metadata_differences.push(MetadataDifference::MajorFormat(
+      a.get_major_format(),
+      b.get_major_format(),
+    ));
+  }
+  if a.get_subtype_format() != b.get_subtype_format() {
+    metadata_differences.push(MetadataDifference::SubtypeFormat(
+      a.get_subtype_format(),
+      b.get_subtype_format(),
+    ));
+  }
+  for t in TagType::ALL.iter() {
+    let (tag_a, tag_b) = (a.get_tag(*t), b.get_tag(*t));
+    if tag_a != tag_b {
+      metadata_differences.push(MetadataDifference::Tag(*t, tag_a, tag_b));
+    }
+  }
+
+  if a.get_channels() != b.get_channels() {
+    return Ok(Comparison {
+      first_differing_frame: Some(0),
+      max_sample_delta: f64::INFINITY,
+      metadata_differences,
+    });
+  }
+
+  let channels = a.get_channels();
+  let mut buf_a = vec![0.0f64; BLOCK_FRAMES * channels];
+  let mut buf_b = vec![0.0f64; BLOCK_FRAMES * channels];
+  let mut first_differing_frame = None;
+  let mut max_sample_delta = 0.0f64;
+  let mut pos = 0u64;
+  a.seek(SeekFrom::Start(0))?;
+  b.seek(SeekFrom::Start(0))?;
+  loop {
+    let n = a
+      .read_to_slice(&mut buf_a)?
+      .min(b.read_to_slice(&mut buf_b)?);
+    if n == 0 {
+      break;
+    }
+    let frames_a = buf_a[..n * channels].chunks(channels);
+    let frames_b = buf_b[..n * channels].chunks(channels);
+    for (i, (x, y)) in frames_a.zip(frames_b).enumerate() {
+      let delta = x
+        .iter()
+        .zip(y.iter())
+        .fold(0.0f64, |acc, (x, y)| acc.max((x - y).abs()));
+      if delta > 0.0 && first_differing_frame.is_none() {
+        first_differing_frame = Some(pos + i as u64);
+      }
+      max_sample_delta = max_sample_delta.max(delta);
+    }
+    pos += n as u64;
+  }
+  if first_differing_frame.is_none() && len_a != len_b {
+    first_differing_frame = Some(len_a.min(len_b));
+  }
+
+  Ok(Comparison {
+    first_differing_frame,
+    max_sample_delta,
+    metadata_differences,
+  })
+}
@@ -0,0 +1,135 @@
+use super::{OpenOptions, SndFile, SndFileError};
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek, Write};
+
+/// A seekable byte stream libsndfile can decode from or encode to, e.g. a remote object or an
+/// in-memory buffer, see `OpenOptions::from_virtual_io`.
+///
+/// Callbacks of libsndfile must not unwind, so errors are reported to libsndfile as short reads or
+/// writes, which surface as `SndFileError` of the calling function.
+pub trait VirtualIo: Read + Seek + Send {
+  /// Length of the stream in bytes.
+  fn byte_len(&self) -> io::Result<u64>;
+
+  /// Returns `false` for streams which can only be read forward, e.g. a pipe.
+  ///
+  /// libsndfile may still seek to the current position while parsing the header. The opened
+  /// `SndFile` is then not seekable, see `SndFileError::Unseekable`.
+  fn is_seekable(&self) -> bool {
+    true
+  }
+
+  /// Write bytes at the cursor, returns the number of bytes written.
+  ///
+  /// Read only streams can keep the default, which always fails.
+  fn write_bytes(&mut self, src: &[u8]) -> io::Result<usize> {
+    let _ = src;
+    Err(io::Error::new(
+      io::ErrorKind::PermissionDenied,
+      "Stream is read only.",
+    ))
+  }
+}
+
+impl VirtualIo for File {
+  fn byte_len(&self) -> io::Result<u64> {
+    self.metadata().map(|x| x.len())
+  }
+
+  fn write_bytes(&mut self, src: &[u8]) -> io::Result<usize> {
+    self.write(src)
+  }
+}
+
+/// New cursor position of a stream of `len` bytes after seeking to `pos` from `current`.
+#[cfg(any(
+  feature = "http_features",
+  feature = "object_store_features",
+  feature = "zip_features"
+))]
+pub(crate) fn resolve_seek(pos: io::SeekFrom, current: u64, len: u64) -> io::Result<u64> {
+  let offset = |base: u64, x: i64| (base as i64).checked_add(x).filter(|x| *x >= 0);
+  match pos {
+    io::SeekFrom::Start(x) => Some(x),
+    io::SeekFrom::Current(x) => offset(current, x).map(|x| x as u64),
+    io::SeekFrom::End(x) => offset(len, x).map(|x| x as u64),
+  }
+  .ok_or_else(|| {
+    io::Error::new(
+      io::ErrorKind::InvalidInput,
+      "Invalid seek to a negative position.",
+    )
+  })
+}
+
+/// An in-memory audio file, which can also be written.
+impl VirtualIo for Cursor<Vec<u8>> {
+  fn byte_len(&self) -> io::Result<u64> {
+    Ok(self.get_ref().len() as u64)
+  }
+
+  fn write_bytes(&mut self, src: &[u8]) -> io::Result<usize> {
+    self.write(src)
+  }
+}
+
+/// Underlying stream of a `SndFile`.
+pub(crate) enum VIOStream {
+  File(File),
+  Custom(Box<dyn VirtualIo>),
+}
+
+impl std::fmt::Debug for VIOStream {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      VIOStream::File(x) => f.debug_tuple("File").field(x).finish(),
+      VIOStream::Custom(_) => f.write_str("Custom"),
+    }
+  }
+}
+
+impl VIOStream {
+  pub(crate) fn get_ref(&self) -> &dyn VirtualIo {
+    match self {
+      VIOStream::File(x) => x,
+      VIOStream::Custom(x) => x.as_ref(),
+    }
+  }
+
+  pub(crate) fn get_mut(&mut self) -> &mut dyn VirtualIo {
+    match self {
+      VIOStream::File(x) => x,
+      VIOStream::Custom(x) => x.as_mut(),
+    }
+  }
+
+  pub(crate) fn file(&self) -> Result<&File, SndFileError> {
+    match self {
+      VIOStream::File(x) => Ok(x),
+      VIOStream::Custom(_) => Err(not_a_file()),
+    }
+  }
+
+  pub(crate) fn into_file(self) -> Result<File, SndFileError> {
+    match self {
+      VIOStream::File(x) => Ok(x),
+      VIOStream::Custom(_) => Err(not_a_file()),
+    }
+  }
+}
+
+fn not_a_file() -> SndFileError {
+  SndFileError::InvalidParameter(
+    "Got a file opened from a `VirtualIo`, expect a file opened from a `File`.".to_string(),
+  )
+}
+
+impl OpenOptions {
+  /// Open an audio file backed by `io` instead of a `File`.
+  ///
+  /// Features reading the underlying `File` directly, e.g. `SndFile::get_vorbis_comments` and
+  /// `SndFile::close_into_inner`, return `SndFileError::InvalidParameter` for such files.
+  pub fn from_virtual_io<V: VirtualIo + 'static>(&self, io: V) -> Result<SndFile, SndFileError> {
+    self.open_stream(VIOStream::Custom(Box::new(io)))
+  }
+}
@@ -0,0 +1,49 @@
+use super::{SndFile, SndFileError, SndFileIO};
+use js_sys::Float32Array;
+
+/// Split interleaved samples into one `Float32Array` per channel, the layout of
+/// `AudioBuffer.copyToChannel` of Web Audio.
+///
+/// `src.len()` must be a multiple of `channels`, otherwise `SndFileError::InvalidParameter` is
+/// returned.
+pub fn channel_data_from_interleaved(
+  src: &[f32],
+  channels: usize,
+) -> Result<Vec<Float32Array>, SndFileError> {
+  if channels == 0 || src.len() % channels != 0 {
+    return Err(SndFileError::InvalidParameter(format!(
+      "Got {} samples, expect a multiple of {} channels.",
+      src.len(),
+      channels
+    )));
+  }
+  let mut scratch = Vec::with_capacity(src.len() / channels);
+  Ok(
+    (0..channels)
+      .map(|ch| {
+        scratch.clear();
+        scratch.extend(src.iter().skip(ch).step_by(channels));
+        Float32Array::from(&scratch[..])
+      })
+      .collect(),
+  )
+}
+
+impl SndFile {
+  /// Read all frames as one `Float32Array` per channel, ready for `AudioBuffer.copyToChannel`, so
+  /// browser tools can play formats the browser itself can't decode.
+  ///
+  /// ```ignore
+  /// let data = snd.read_all_to_channel_data()?;
+  /// let buffer = ctx.create_buffer(data.len() as u32, data[0].length(), snd.get_samplerate() as f32)?;
+  /// for (i, x) in data.iter().enumerate() {
+  ///   buffer.copy_to_channel(&x.to_vec(), i as i32)?;
+  /// }
+  /// ```
+  ///
+  /// The I/O cursor is left unchanged.
+  pub fn read_all_to_channel_data(&mut self) -> Result<Vec<Float32Array>, SndFileError> {
+    let buf: Vec<f32> = self.read_all_to_vec()?;
+    channel_data_from_interleaved(&buf, self.get_channels())
+  }
+}
@@ -0,0 +1,99 @@
+use super::vio::resolve_seek;
+use super::{default_read_limit, OpenOptions, SndFile, SndFileError, SourceKind, VirtualIo};
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+use zip::result::ZipError;
+use zip::{CompressionMethod, ZipArchive};
+
+/// The data of a stored (uncompressed) zip entry, read in place from the archive.
+struct StoredEntry {
+  f: File,
+  start: u64,
+  len: u64,
+  pos: u64,
+}
+
+impl Read for StoredEntry {
+  fn read(&mut self, dst: &mut [u8]) -> io::Result<usize> {
+    if self.pos >= self.len {
+      return Ok(0);
+    }
+    let n = ((self.len - self.pos) as usize).min(dst.len());
+    self.f.seek(SeekFrom::Start(self.start + self.pos))?;
+    let n = self.f.read(&mut dst[..n])?;
+    self.pos += n as u64;
+    Ok(n)
+  }
+}
+
+impl Seek for StoredEntry {
+  fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+    self.pos = resolve_seek(pos, self.pos, self.len)?;
+    Ok(self.pos)
+  }
+}
+
+impl VirtualIo for StoredEntry {
+  fn byte_len(&self) -> io::Result<u64> {
+    Ok(self.len)
+  }
+}
+
+fn zip_error(e: ZipError, name: &str) -> SndFileError {
+  match e {
+    ZipError::Io(e) => SndFileError::IOError(e),
+    ZipError::FileNotFound => SndFileError::InvalidParameter(format!(
+      "Got entry `{}`, expect an entry of the archive.",
+      name
+    )),
+    e => SndFileError::IOError(io::Error::new(io::ErrorKind::InvalidData, e)),
+  }
+}
+
+impl OpenOptions {
+  /// Open the entry `name` of the zip archive at `archive`, e.g. a WAV file of a sample library,
+  /// only `OpenOptions::ReadOnly` is supported.
+  ///
+  /// Stored entries are read in place from the archive. Compressed entries are extracted to
+  /// memory first, returning `SndFileError::MemoryLimitExceeded` if they are larger than the limit
+  /// set by `set_default_read_limit`.
+  pub fn from_zip_entry<P: AsRef<Path>>(
+    &self,
+    archive: P,
+    name: &str,
+  ) -> Result<SndFile, SndFileError> {
+    let archive = archive.as_ref();
+    let f = File::open(archive).map_err(SndFileError::IOError)?;
+    let mut zip = ZipArchive::new(f).map_err(|e| zip_error(e, name))?;
+    let mut entry = zip.by_name(name).map_err(|e| zip_error(e, name))?;
+    let len = entry.size();
+    if entry.compression() == CompressionMethod::Stored {
+      let start = entry.data_start();
+      drop(entry);
+      return self
+        .from_virtual_io(StoredEntry {
+          f: zip.into_inner(),
+          start,
+          len,
+          pos: 0,
+        })
+        .map(|x| x.with_source(SourceKind::ZipEntry, Some(archive)));
+    }
+    let limit = default_read_limit().unwrap_or(u64::MAX);
+    if len > limit {
+      return Err(SndFileError::MemoryLimitExceeded {
+        required: len,
+        limit,
+      });
+    }
+    let mut data = Vec::with_capacity(len as usize);
+    entry
+      .read_to_end(&mut data)
+      .map_err(SndFileError::IOError)?;
+    drop(entry);
+    self
+      .from_virtual_io(Cursor::new(data))
+      .map(|x| x.with_source(SourceKind::ZipEntry, Some(archive)))
+  }
+}